@@ -0,0 +1,21 @@
+use crate::accessibility::UIElementInfo;
+
+pub use xray_core::actions::*;
+
+/// Lists the actions the element supports, such as `AXPress`.
+#[tauri::command]
+pub fn list_element_actions(element: UIElementInfo) -> Result<Vec<String>, String> {
+    list_actions(&element)
+}
+
+/// Performs an action such as `AXPress` or `AXShowMenu` on the element.
+#[tauri::command]
+pub fn perform_element_action(element: UIElementInfo, action: String) -> Result<(), String> {
+    perform_action(&element, &action)
+}
+
+/// Writes an attribute such as `AXValue` or `AXFocused` of the element.
+#[tauri::command]
+pub fn set_element_attribute(element: UIElementInfo, attribute: String, value: AttributeValue) -> Result<(), String> {
+    set_attribute(&element, &attribute, &value)
+}
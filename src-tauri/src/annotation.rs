@@ -0,0 +1,491 @@
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use ab_glyph::{FontVec, PxScale};
+use base64::Engine;
+use image::{ImageFormat, Rgba, RgbaImage};
+use imageproc::drawing::{
+    draw_filled_circle_mut, draw_filled_rect_mut, draw_polygon_mut, draw_text_mut, text_size, Blend,
+};
+use imageproc::point::Point as PolyPoint;
+use imageproc::rect::Rect as PixelRect;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use crate::capture;
+use crate::constants::{
+    ANNOTATION_DOCUMENT_VERSION, ANNOTATION_FONT_PATHS, ANNOTATION_ORIGINALS_DIR, ANNOTATION_SIDECAR_SUFFIX,
+    WINDOW_LABEL_PREVIEW,
+};
+use crate::hash::fnv1a;
+use crate::AppState;
+
+/// An RGBA color.
+pub type Color = [u8; 4];
+
+/// A position in image pixels.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// A rectangle in image pixels.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// A single shape drawn on top of a capture. Coordinates are in image pixels.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Annotation {
+    Arrow {
+        from: Point,
+        to: Point,
+        color: Color,
+        width: f32,
+    },
+    Rectangle {
+        rect: Rect,
+        color: Color,
+        width: f32,
+        #[serde(default)]
+        fill: Option<Color>,
+    },
+    /// A filled circle with a number in it, for step-by-step callouts.
+    Callout {
+        center: Point,
+        number: u32,
+        color: Color,
+        radius: f32,
+    },
+    Text {
+        position: Point,
+        text: String,
+        color: Color,
+        size: f32,
+        #[serde(default)]
+        background: Option<Color>,
+    },
+    /// A translucent fill, like a highlighter pen.
+    Highlight {
+        rect: Rect,
+        color: Color,
+    },
+}
+
+/// The editable annotations of one capture.
+///
+/// Annotations are always drawn on the unannotated copy `source` names, so a
+/// document can be re-opened and re-rendered any number of times without
+/// drawing twice. The editor works out that copy itself rather than reading it
+/// from a document the webview sends back.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnotationDocument {
+    pub version: u32,
+    pub source: String,
+    pub annotations: Vec<Annotation>,
+}
+
+impl AnnotationDocument {
+    fn sidecar_path(capture_path: &Path) -> PathBuf {
+        let mut name = capture_path.as_os_str().to_owned();
+        name.push(ANNOTATION_SIDECAR_SUFFIX);
+        PathBuf::from(name)
+    }
+}
+
+/// Draws `annotations` onto `image` in order.
+pub fn render(image: &mut RgbaImage, annotations: &[Annotation]) {
+    for annotation in annotations {
+        match annotation {
+            Annotation::Arrow { from, to, color, width } => draw_arrow(image, *from, *to, *color, *width),
+            Annotation::Rectangle { rect, color, width, fill } => {
+                if let Some(fill) = fill {
+                    blend_rect(image, rect, *fill);
+                }
+                draw_rect_outline(image, rect, *color, *width);
+            }
+            Annotation::Callout { center, number, color, radius } => {
+                draw_callout(image, *center, *number, *color, *radius)
+            }
+            Annotation::Text { position, text, color, size, background } => {
+                draw_label(image, *position, text, *color, *size, *background)
+            }
+            Annotation::Highlight { rect, color } => blend_rect(image, rect, *color),
+        }
+    }
+}
+
+fn draw_arrow(image: &mut RgbaImage, from: Point, to: Point, color: Color, width: f32) {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length < 1.0 {
+        return;
+    }
+    let (ux, uy) = (dx / length, dy / length);
+
+    // The head is a triangle whose size scales with the stroke width.
+    let head_length = (width * 4.0).max(10.0).min(length);
+    let head_half_width = head_length * 0.6;
+    let base = Point { x: to.x - ux * head_length, y: to.y - uy * head_length };
+
+    draw_thick_line(image, from, base, color, width);
+    polygon(image, &[
+        to,
+        Point { x: base.x - uy * head_half_width, y: base.y + ux * head_half_width },
+        Point { x: base.x + uy * head_half_width, y: base.y - ux * head_half_width },
+    ], color);
+}
+
+fn draw_thick_line(image: &mut RgbaImage, from: Point, to: Point, color: Color, width: f32) {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length < 0.5 {
+        return;
+    }
+    let half = width.max(1.0) / 2.0;
+    let (nx, ny) = (-dy / length * half, dx / length * half);
+
+    polygon(image, &[
+        Point { x: from.x + nx, y: from.y + ny },
+        Point { x: to.x + nx, y: to.y + ny },
+        Point { x: to.x - nx, y: to.y - ny },
+        Point { x: from.x - nx, y: from.y - ny },
+    ], color);
+}
+
+fn polygon(image: &mut RgbaImage, points: &[Point], color: Color) {
+    let mut poly: Vec<PolyPoint<i32>> = points
+        .iter()
+        .map(|p| PolyPoint::new(p.x.round() as i32, p.y.round() as i32))
+        .collect();
+    poly.dedup();
+    if poly.len() > 2 && poly.first() == poly.last() {
+        poly.pop();
+    }
+    if poly.len() < 3 {
+        return;
+    }
+    draw_polygon_mut(image, &poly, Rgba(color));
+}
+
+fn draw_rect_outline(image: &mut RgbaImage, rect: &Rect, color: Color, width: f32) {
+    let w = width.max(1.0);
+    let edges = [
+        Rect { x: rect.x, y: rect.y, width: rect.width, height: w },
+        Rect { x: rect.x, y: rect.y + rect.height - w, width: rect.width, height: w },
+        Rect { x: rect.x, y: rect.y, width: w, height: rect.height },
+        Rect { x: rect.x + rect.width - w, y: rect.y, width: w, height: rect.height },
+    ];
+    for edge in &edges {
+        if let Some(r) = to_pixel_rect(edge) {
+            draw_filled_rect_mut(image, r, Rgba(color));
+        }
+    }
+}
+
+/// Fills `rect` with `color`, blending by the color's alpha.
+fn blend_rect(image: &mut RgbaImage, rect: &Rect, color: Color) {
+    let Some(r) = to_pixel_rect(rect) else {
+        return;
+    };
+    let mut canvas = Blend(std::mem::take(image));
+    draw_filled_rect_mut(&mut canvas, r, Rgba(color));
+    *image = canvas.0;
+}
+
+fn draw_callout(image: &mut RgbaImage, center: Point, number: u32, color: Color, radius: f32) {
+    let radius = radius.max(4.0);
+    draw_filled_circle_mut(image, (center.x.round() as i32, center.y.round() as i32), radius.round() as i32, Rgba(color));
+
+    let Some(font) = font() else {
+        return;
+    };
+    let label = number.to_string();
+    let scale = PxScale::from(radius * 1.2);
+    let (w, h) = text_size(scale, font, &label);
+    draw_text_mut(
+        image,
+        Rgba(contrasting(color)),
+        (center.x - w as f32 / 2.0).round() as i32,
+        (center.y - h as f32 / 2.0).round() as i32,
+        scale,
+        font,
+        &label,
+    );
+}
+
+fn draw_label(image: &mut RgbaImage, position: Point, text: &str, color: Color, size: f32, background: Option<Color>) {
+    let Some(font) = font() else {
+        log::warn!("No system font available, skipping text annotation");
+        return;
+    };
+    let scale = PxScale::from(size.max(6.0));
+    let line_height = size.max(6.0) * 1.2;
+    let padding = size * 0.25;
+
+    for (i, line) in text.lines().enumerate() {
+        let y = position.y + line_height * i as f32;
+        if let Some(background) = background {
+            let (w, _) = text_size(scale, font, line);
+            blend_rect(image, &Rect {
+                x: position.x - padding,
+                y: y - padding,
+                width: w as f32 + padding * 2.0,
+                height: line_height + padding,
+            }, background);
+        }
+        draw_text_mut(image, Rgba(color), position.x.round() as i32, y.round() as i32, scale, font, line);
+    }
+}
+
+fn to_pixel_rect(rect: &Rect) -> Option<PixelRect> {
+    let (w, h) = (rect.width.round(), rect.height.round());
+    if w < 1.0 || h < 1.0 {
+        return None;
+    }
+    Some(PixelRect::at(rect.x.round() as i32, rect.y.round() as i32).of_size(w as u32, h as u32))
+}
+
+/// Picks black or white, whichever reads better on `color`.
+fn contrasting(color: Color) -> Color {
+    let luma = 0.299 * color[0] as f32 + 0.587 * color[1] as f32 + 0.114 * color[2] as f32;
+    if luma > 150.0 { [0, 0, 0, 255] } else { [255, 255, 255, 255] }
+}
+
+/// Loads the first available system font once.
+fn font() -> Option<&'static FontVec> {
+    static FONT: OnceLock<Option<FontVec>> = OnceLock::new();
+    FONT.get_or_init(|| {
+        ANNOTATION_FONT_PATHS.iter().find_map(|path| {
+            let data = std::fs::read(path).ok()?;
+            FontVec::try_from_vec_and_index(data, 0).ok()
+        })
+    })
+    .as_ref()
+}
+
+/// Opens the preview window for annotating the capture at `path`.
+///
+/// The editor can only read and write this capture and its annotation copy;
+/// the paths never come from the webview.
+pub fn open_editor(app: &AppHandle, path: &str) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL_PREVIEW) {
+        let _ = window.close();
+    }
+    let capture = std::fs::canonicalize(path).map_err(|e| e.to_string())?;
+    if let Ok(mut annotating) = app.state::<AppState>().annotating.lock() {
+        *annotating = Some(capture);
+    }
+
+    let query = utf8_percent_encode(path, NON_ALPHANUMERIC);
+    WebviewWindowBuilder::new(app, WINDOW_LABEL_PREVIEW, WebviewUrl::App(format!("index.html?annotate={}", query).into()))
+        .title("xray — Annotate")
+        .inner_size(900.0, 700.0)
+        .build()
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// The capture open in the editor and the pristine copy of it annotations are drawn on.
+///
+/// The copy lives in the app data directory, named after the capture's path,
+/// so flattening back onto the capture never loses the original pixels.
+fn editing(app: &AppHandle) -> Result<(PathBuf, PathBuf), String> {
+    let capture = app
+        .state::<AppState>()
+        .annotating
+        .lock()
+        .ok()
+        .and_then(|c| c.clone())
+        .ok_or_else(|| "No capture is open for annotation".to_string())?;
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?.join(ANNOTATION_ORIGINALS_DIR);
+    let ext = capture.extension().and_then(|e| e.to_str()).unwrap_or("png");
+    let original = dir.join(format!("{:016x}.{}", fnv1a(&capture.to_string_lossy()), ext));
+    Ok((capture, original))
+}
+
+/// Loads the annotation document saved next to the capture open in the editor, or starts a new one.
+///
+/// A new document copies the capture as its original first, replacing any
+/// copy left from an earlier session.
+#[tauri::command]
+pub fn load_annotation_document(app: AppHandle) -> Result<AnnotationDocument, String> {
+    let (capture, original) = editing(&app)?;
+    if let Ok(json) = std::fs::read_to_string(AnnotationDocument::sidecar_path(&capture)) {
+        let document: AnnotationDocument = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        if original.exists() {
+            return Ok(AnnotationDocument { source: original.to_string_lossy().into_owned(), ..document });
+        }
+        log::warn!("The original of {} is gone; starting over from the capture", capture.display());
+    }
+
+    std::fs::create_dir_all(original.parent().unwrap_or(&original)).map_err(|e| e.to_string())?;
+    std::fs::copy(&capture, &original).map_err(|e| e.to_string())?;
+    Ok(AnnotationDocument {
+        version: ANNOTATION_DOCUMENT_VERSION,
+        source: original.to_string_lossy().into_owned(),
+        annotations: Vec::new(),
+    })
+}
+
+/// Renders `annotations` onto the original of the capture in the editor, as a PNG data URL for the preview.
+#[tauri::command]
+pub fn render_annotation_preview(annotations: Vec<Annotation>, app: AppHandle) -> Result<String, String> {
+    let (_, original) = editing(&app)?;
+    let mut image = image::open(&original).map_err(|e| e.to_string())?.to_rgba8();
+    render(&mut image, &annotations);
+
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png).map_err(|e| e.to_string())?;
+    Ok(format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(png)))
+}
+
+/// Flattens `annotations` onto the capture in the editor, keeping them in a sidecar for later re-editing.
+#[tauri::command]
+pub fn flatten_annotations(annotations: Vec<Annotation>, app: AppHandle) -> Result<(), String> {
+    let (capture, original) = editing(&app)?;
+    let mut image = image::open(&original).map_err(|e| e.to_string())?.to_rgba8();
+    render(&mut image, &annotations);
+    capture::save_image(&image, &capture.to_string_lossy())?;
+
+    let document = AnnotationDocument {
+        version: ANNOTATION_DOCUMENT_VERSION,
+        source: original.to_string_lossy().into_owned(),
+        annotations,
+    };
+    let json = serde_json::to_string_pretty(&document).map_err(|e| e.to_string())?;
+    std::fs::write(AnnotationDocument::sidecar_path(&capture), json).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+    const RED: Color = [230, 30, 30, 255];
+
+    fn canvas(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, WHITE)
+    }
+
+    fn rendered(width: u32, height: u32, annotation: Annotation) -> RgbaImage {
+        let mut image = canvas(width, height);
+        render(&mut image, &[annotation]);
+        image
+    }
+
+    fn is(image: &RgbaImage, x: u32, y: u32, color: Color) -> bool {
+        image.get_pixel(x, y).0 == color
+    }
+
+    #[test]
+    fn arrow_has_a_shaft_and_a_wider_head() {
+        let arrow = Annotation::Arrow { from: Point { x: 10.0, y: 20.0 }, to: Point { x: 58.0, y: 20.0 }, color: RED, width: 4.0 };
+        let image = rendered(64, 40, arrow);
+        // The shaft is 4 px thick; the head starts 16 px before the tip and is 19 px wide.
+        assert!(is(&image, 20, 20, RED));
+        assert!(is(&image, 20, 26, WHITE.0));
+        assert!(is(&image, 45, 26, RED));
+        assert!(is(&image, 57, 20, RED));
+        assert!(is(&image, 5, 20, WHITE.0));
+        assert!(is(&image, 62, 20, WHITE.0));
+    }
+
+    #[test]
+    fn rectangle_outlines_and_fills() {
+        let rect = Rect { x: 10.0, y: 10.0, width: 20.0, height: 20.0 };
+        let image = rendered(40, 40, Annotation::Rectangle { rect, color: RED, width: 2.0, fill: None });
+        for (x, y) in [(10, 15), (11, 15), (28, 15), (29, 15), (15, 10), (15, 29)] {
+            assert!(is(&image, x, y, RED), "({}, {})", x, y);
+        }
+        for (x, y) in [(12, 15), (20, 20), (30, 15), (15, 30), (9, 15)] {
+            assert!(is(&image, x, y, WHITE.0), "({}, {})", x, y);
+        }
+
+        let filled = rendered(40, 40, Annotation::Rectangle { rect, color: RED, width: 2.0, fill: Some([0, 0, 255, 255]) });
+        assert!(is(&filled, 20, 20, [0, 0, 255, 255]));
+        assert!(is(&filled, 10, 15, RED));
+        assert!(is(&filled, 35, 35, WHITE.0));
+    }
+
+    #[test]
+    fn callout_is_a_filled_circle_with_its_number() {
+        let callout = Annotation::Callout { center: Point { x: 20.0, y: 20.0 }, number: 1, color: RED, radius: 10.0 };
+        let image = rendered(40, 40, callout);
+        assert!(is(&image, 20, 11, RED));
+        assert!(is(&image, 11, 20, RED));
+        assert!(is(&image, 20, 32, WHITE.0));
+        assert!(is(&image, 3, 3, WHITE.0));
+
+        // The number is drawn in white on red, inside the circle.
+        let digit = (14..27).flat_map(|y| (14..27).map(move |x| (x, y))).filter(|&(x, y)| !is(&image, x, y, RED)).count();
+        if font().is_some() {
+            assert!(digit > 0);
+        } else {
+            assert_eq!(digit, 0);
+        }
+    }
+
+    #[test]
+    fn text_is_drawn_on_its_background() {
+        let text = Annotation::Text {
+            position: Point { x: 10.0, y: 10.0 },
+            text: "Hi".to_string(),
+            color: [0, 0, 0, 255],
+            size: 20.0,
+            background: Some([0, 0, 255, 255]),
+        };
+        let image = rendered(80, 50, text);
+        let Some(_) = font() else {
+            // Without a system font nothing is drawn at all.
+            assert_eq!(image, canvas(80, 50));
+            return;
+        };
+        // The background starts a quarter of the size above and left of the position.
+        assert!(is(&image, 6, 6, [0, 0, 255, 255]));
+        assert!(is(&image, 4, 4, WHITE.0));
+        let inked = image.pixels().filter(|p| p.0 == [0, 0, 0, 255]).count();
+        assert!(inked > 10, "{} text pixels", inked);
+    }
+
+    #[test]
+    fn highlight_blends_by_alpha() {
+        let rect = Rect { x: 5.0, y: 5.0, width: 10.0, height: 10.0 };
+        let image = rendered(20, 20, Annotation::Highlight { rect, color: [255, 230, 0, 110] });
+        let [r, g, b, a] = image.get_pixel(10, 10).0;
+        assert_eq!((r, a), (255, 255));
+        assert!((240..=250).contains(&g), "g = {}", g);
+        assert!((140..=150).contains(&b), "b = {}", b);
+        assert!(is(&image, 2, 2, WHITE.0));
+        assert!(is(&image, 15, 15, WHITE.0));
+    }
+
+    #[test]
+    fn out_of_bounds_annotations_are_clipped() {
+        let far = Point { x: -500.0, y: 900.0 };
+        let annotations = [
+            Annotation::Arrow { from: far, to: Point { x: -100.0, y: -100.0 }, color: RED, width: 4.0 },
+            Annotation::Callout { center: far, number: 3, color: RED, radius: 12.0 },
+            Annotation::Text { position: far, text: "gone".to_string(), color: RED, size: 16.0, background: Some(RED) },
+            Annotation::Highlight { rect: Rect { x: 100.0, y: 100.0, width: 50.0, height: 50.0 }, color: RED },
+            Annotation::Rectangle { rect: Rect { x: 0.0, y: 0.0, width: 0.0, height: 0.0 }, color: RED, width: 3.0, fill: Some(RED) },
+        ];
+        let mut image = canvas(20, 20);
+        render(&mut image, &annotations);
+        assert_eq!(image, canvas(20, 20));
+
+        // A rectangle hanging off the top left keeps the edges that are on the image.
+        let rect = Rect { x: -10.0, y: -10.0, width: 20.0, height: 20.0 };
+        let image = rendered(20, 20, Annotation::Rectangle { rect, color: RED, width: 2.0, fill: None });
+        assert!(is(&image, 9, 0, RED));
+        assert!(is(&image, 0, 9, RED));
+        assert!(is(&image, 5, 5, WHITE.0));
+        assert!(is(&image, 15, 15, WHITE.0));
+    }
+}
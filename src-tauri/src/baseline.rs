@@ -0,0 +1,169 @@
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager, State};
+use crate::constants::{BASELINE_DIR, DIFF_IMAGE_SUFFIX, EVENT_COMPARISON_RESULT};
+use crate::diff::{self, DiffOptions, DiffResult};
+use crate::hash::fnv1a;
+use crate::history::{self, HistoryEntry};
+use crate::capture::{self, CaptureError};
+use crate::AppState;
+
+/// The result of comparing a capture against its element's baseline.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComparisonReport {
+    pub baseline: String,
+    pub capture: String,
+    /// The rendered diff image, written next to the capture.
+    pub diff_image: String,
+    pub result: DiffResult,
+}
+
+/// Names the baseline of a history entry's element.
+///
+/// The name combines the app with the element's path in the app's tree, so
+/// re-captures of the same element share a baseline even after it moved.
+/// Elements without a locator fall back to their role and rect.
+pub fn baseline_key(entry: &HistoryEntry) -> String {
+    let element = &entry.element;
+    let description = match &element.locator {
+        Some(locator) => {
+            let path: Vec<String> = locator.path.iter().map(|s| format!("{}[{}]", s.role, s.index)).collect();
+            format!("{}#{}", path.join("/"), locator.identifier.as_deref().unwrap_or(""))
+        }
+        None => format!(
+            "{}@{},{},{}x{}",
+            element.role,
+            element.global_x.round(),
+            element.global_y.round(),
+            element.width.round(),
+            element.height.round()
+        ),
+    };
+
+    let app: String = entry.app_name.as_deref().unwrap_or("unknown")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    format!("{}-{:016x}", app, fnv1a(&description))
+}
+
+fn baseline_path(app: &AppHandle, entry: &HistoryEntry) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?.join(BASELINE_DIR);
+    Ok(dir.join(format!("{}.png", baseline_key(entry))))
+}
+
+/// Stores the capture of `entry` as the baseline for its element, replacing any previous one.
+pub fn set_baseline(app: &AppHandle, entry: &HistoryEntry) -> Result<(), String> {
+    let path = baseline_path(app, entry)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let image = image::open(&entry.path).map_err(|e| e.to_string())?;
+    image.save(&path).map_err(|e| e.to_string())
+}
+
+/// Compares the capture at `capture` against the baseline of `entry`'s element.
+///
+/// Returns `None` when the element has no baseline. The diff image is written
+/// next to the capture and the report is also sent to the frontend.
+pub fn compare_with_baseline(
+    app: &AppHandle,
+    entry: &HistoryEntry,
+    capture: &str,
+    options: &DiffOptions,
+) -> Result<Option<ComparisonReport>, String> {
+    let baseline = baseline_path(app, entry)?;
+    if !baseline.exists() {
+        return Ok(None);
+    }
+
+    let diff_image = diff_image_path(Path::new(capture));
+    let result = compare_files(&baseline, Path::new(capture), Some(&diff_image), options)?;
+    let report = ComparisonReport {
+        baseline: baseline.to_string_lossy().into_owned(),
+        capture: capture.to_string(),
+        diff_image: diff_image.to_string_lossy().into_owned(),
+        result,
+    };
+
+    log::info!(
+        "Compared {} with its baseline: {} differing pixels, score {:.3}, {}",
+        capture,
+        report.result.diff_pixels,
+        report.result.perceptual_score,
+        if report.result.passed { "passed" } else { "failed" }
+    );
+    let _ = app.emit(EVENT_COMPARISON_RESULT, report.clone());
+    Ok(Some(report))
+}
+
+/// Re-captures `entry` and checks the new capture against its element's baseline, if any.
+pub fn recapture_and_compare(app: &AppHandle, entry: &HistoryEntry) -> Result<String, CaptureError> {
+    let path = capture::recapture_entry(app, &app.state::<AppState>(), entry)?;
+    if let Err(e) = compare_with_baseline(app, entry, &path, &DiffOptions::default()) {
+        log::warn!("Failed to compare {} with its baseline: {}", path, e);
+    }
+    Ok(path)
+}
+
+/// Re-captures the newest history entry and checks it against its baseline.
+pub fn recapture_last_and_compare(app: &AppHandle) -> Result<String, CaptureError> {
+    let entry = capture::last_entry(&app.state::<AppState>())?;
+    recapture_and_compare(app, &entry)
+}
+
+/// Compares two image files, writing the diff image to `output` when given.
+pub fn compare_files(
+    baseline: &Path,
+    candidate: &Path,
+    output: Option<&Path>,
+    options: &DiffOptions,
+) -> Result<DiffResult, String> {
+    let baseline = image::open(baseline).map_err(|e| e.to_string())?.to_rgba8();
+    let candidate = image::open(candidate).map_err(|e| e.to_string())?.to_rgba8();
+    let (result, diff_image) = diff::compare(&baseline, &candidate, options);
+    if let Some(output) = output {
+        diff_image.save(output).map_err(|e| e.to_string())?;
+    }
+    Ok(result)
+}
+
+/// `shot.png` becomes `shot.diff.png`.
+fn diff_image_path(capture: &Path) -> PathBuf {
+    let stem = capture.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    capture.with_file_name(format!("{}{}", stem, DIFF_IMAGE_SUFFIX))
+}
+
+/// Makes a recorded capture the baseline for its element.
+#[tauri::command]
+pub fn set_history_baseline(id: u64, app: AppHandle, state: State<AppState>) -> Result<(), String> {
+    set_baseline(&app, &history::find_entry(&state, id)?)
+}
+
+/// Compares a recorded capture against its element's baseline. Returns `None` if there is none.
+#[tauri::command]
+pub fn compare_history_entry(
+    id: u64,
+    options: Option<DiffOptions>,
+    app: AppHandle,
+    state: State<AppState>,
+) -> Result<Option<ComparisonReport>, String> {
+    let entry = history::find_entry(&state, id)?;
+    compare_with_baseline(&app, &entry, &entry.path, &options.unwrap_or_default())
+}
+
+/// Compares any two image files, optionally writing the diff image to `output`.
+#[tauri::command]
+pub fn compare_images(
+    baseline: String,
+    candidate: String,
+    output: Option<String>,
+    options: Option<DiffOptions>,
+) -> Result<DiffResult, String> {
+    compare_files(
+        Path::new(&baseline),
+        Path::new(&candidate),
+        output.as_deref().map(Path::new),
+        &options.unwrap_or_default(),
+    )
+}
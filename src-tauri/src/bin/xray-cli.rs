@@ -0,0 +1,384 @@
+//! Command-line access to the inspector, for scripts and CI.
+//!
+//! This is the `xray` CLI. It is built as `xray-cli` because the `xray` binary is
+//! the app itself, which `xray --stdio` runs as a JSON-RPC server for editors.
+//!
+//! ```text
+//! xray-cli inspect --at 120,340
+//! xray-cli dump --pid 512 --depth 4 --json
+//! xray-cli capture --app Finder --selector 'Toolbar Button[description="Back"]' -o back.png
+//! xray-cli audit --app Finder
+//! xray-cli replay --script login.yaml --failures failures/
+//! xray-cli act --app Finder --selector 'Toolbar Button[description="Back"]' --action AXPress
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use xray_core::accessibility::{self, UIElementInfo};
+use xray_core::actions::{self, AttributeValue};
+use xray_core::audit::{self, AuditIssue};
+use xray_core::constants::{ax_attributes, ACCESSIBILITY_RECURSION_LIMIT, APP_IDENTIFIER};
+use xray_core::element_format::{self, ElementFormat};
+use xray_core::export::{self, ExportFormat};
+use xray_core::manifest::{self, ShotSpec};
+use xray_core::permissions::{Permission, PermissionState};
+use xray_core::redaction::RedactionConfig;
+use xray_core::script::{self, Script};
+use xray_core::selector::Selector;
+use xray_core::tree::{self, ElementNode};
+use xray_core::window_list::{self, WindowInfo};
+
+const USAGE: &str = "\
+Usage: xray-cli <command> [options] [--json]
+
+The xray command line, installed as xray-cli since `xray` starts the app
+(`xray --stdio` runs the app as a JSON-RPC server instead).
+
+Commands:
+  inspect --at X,Y [--format F]          Describe the element at a screen position, or print it as
+                                         json, markdown, locator, bounds, swiftui or html
+  dump (--pid N | --app NAME | --window N) [--depth D]
+                                         Print an app's or window's accessibility tree
+  capture --app NAME [--window TITLE] --selector S [--padding P] [--no-redact] -o FILE
+                                         Capture the first element matching a selector
+  audit (--pid N | --app NAME | --window N)
+                                         Report unlabeled and undersized controls
+  act (--at X,Y | --app NAME --selector S) (--list | --action NAME | --set-value V | --focus)
+                                         List or perform accessibility actions on an element
+  replay --script FILE [--failures DIR]  Replay a recorded interaction script
+  export --script FILE (--format xcuitest|appium | -o FILE)
+                                         Turn an interaction script into test code
+  windows                                List the windows on screen
+  permissions                            Check the Accessibility and Screen Recording permissions";
+
+/// Parsed command-line flags: `--name value` pairs plus boolean switches.
+struct Args {
+    command: String,
+    options: Vec<(String, Option<String>)>,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let command = args.next().ok_or("Missing command")?;
+        let mut options = Vec::new();
+        while let Some(arg) = args.next() {
+            let name = match arg.as_str() {
+                "-o" => "output".to_string(),
+                _ => arg.strip_prefix("--").ok_or_else(|| format!("Unexpected argument '{}'", arg))?.to_string(),
+            };
+            let value = match name.as_str() {
+                "json" | "no-redact" | "list" | "focus" => None,
+                _ => Some(args.next().ok_or_else(|| format!("Missing value for --{}", name))?),
+            };
+            options.push((name, value));
+        }
+        Ok(Self { command, options })
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(n, _)| n == name)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options.iter().find(|(n, _)| n == name).and_then(|(_, v)| v.as_deref())
+    }
+
+    fn required(&self, name: &str) -> Result<&str, String> {
+        self.value(name).ok_or_else(|| format!("Missing --{}", name))
+    }
+
+    fn parsed<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        self.value(name)
+            .map(|v| v.parse().map_err(|_| format!("Invalid value for --{}: {}", name, v)))
+            .transpose()
+    }
+}
+
+/// A failure and the exit code it maps to.
+enum Failure {
+    Usage(String),
+    Error(String),
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Failure::Error(message)
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = match args.command.as_str() {
+        "inspect" => inspect(&args),
+        "dump" => dump(&args),
+        "capture" => capture(&args),
+        "audit" => run_audit(&args),
+        "act" => act(&args),
+        "replay" => replay(&args),
+        "export" => export_script(&args),
+        "windows" => windows(&args),
+        "permissions" => permissions(&args),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(ExitCode::SUCCESS)
+        }
+        other => Err(Failure::Usage(format!("Unknown command '{}'", other))),
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(Failure::Usage(e)) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            ExitCode::from(2)
+        }
+        Err(Failure::Error(e)) => {
+            eprintln!("xray-cli: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn print_json(value: &impl serde::Serialize) -> Result<(), Failure> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    println!("{}", json);
+    Ok(())
+}
+
+fn describe(info: &UIElementInfo) -> String {
+    format!(
+        "{}{}{} at {},{} size {}x{}",
+        info.role,
+        info.title.as_ref().map(|t| format!(" \"{}\"", t)).unwrap_or_default(),
+        info.app_name.as_ref().map(|a| format!(" in {}", a)).unwrap_or_default(),
+        info.global_x.round(),
+        info.global_y.round(),
+        info.width.round(),
+        info.height.round()
+    )
+}
+
+/// Parses `--at X,Y`.
+fn position(at: &str) -> Result<(f64, f64), Failure> {
+    at.split_once(',')
+        .and_then(|(x, y)| Some((x.trim().parse::<f64>().ok()?, y.trim().parse::<f64>().ok()?)))
+        .ok_or_else(|| Failure::Usage(format!("Invalid position '{}', expected X,Y", at)))
+}
+
+fn inspect(args: &Args) -> Result<ExitCode, Failure> {
+    let at = args.value("at").ok_or_else(|| Failure::Usage("Missing --at X,Y".to_string()))?;
+    let (x, y) = position(at)?;
+
+    let info = accessibility::get_element_at(x, y, true).ok_or_else(|| format!("No element at {},{}", x, y))?;
+    if let Some(format) = args.parsed::<ElementFormat>("format").map_err(Failure::Usage)? {
+        println!("{}", element_format::format_element(&info, format)?.trim_end());
+    } else if args.flag("json") {
+        print_json(&info)?;
+    } else {
+        println!("{}", describe(&info));
+        if let Some(locator) = &info.locator {
+            let path: Vec<String> = locator.path.iter().map(|s| format!("{}[{}]", s.role, s.index)).collect();
+            println!("path: {}", path.join(" > "));
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Snapshots the tree chosen by `--pid`, `--app` or `--window`.
+fn target_tree(args: &Args, max_depth: usize) -> Result<ElementNode, Failure> {
+    if let Some(window) = args.parsed::<u32>("window").map_err(Failure::Usage)? {
+        return Ok(tree::snapshot_window(window, max_depth).ok_or_else(|| format!("No window {}", window))?);
+    }
+    let pid = match (args.parsed::<i32>("pid").map_err(Failure::Usage)?, args.value("app")) {
+        (Some(pid), _) => pid,
+        (None, Some(name)) => app_pid(name)?,
+        (None, None) => return Err(Failure::Usage("Missing --pid, --app or --window".to_string())),
+    };
+    Ok(tree::snapshot_app(pid, max_depth).ok_or_else(|| format!("Cannot access process {}", pid))?)
+}
+
+fn app_pid(name: &str) -> Result<i32, String> {
+    window_list::find_app_pid(name).ok_or_else(|| format!("{} has no window on screen", name))
+}
+
+fn dump(args: &Args) -> Result<ExitCode, Failure> {
+    let depth = args.parsed("depth").map_err(Failure::Usage)?.unwrap_or(ACCESSIBILITY_RECURSION_LIMIT as usize);
+    let root = target_tree(args, depth)?;
+    if args.flag("json") {
+        print_json(&root)?;
+    } else {
+        print_tree(&root, 0);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_tree(node: &ElementNode, indent: usize) {
+    println!("{}{}", "  ".repeat(indent), describe(&node.info));
+    for child in &node.children {
+        print_tree(child, indent + 1);
+    }
+}
+
+fn capture(args: &Args) -> Result<ExitCode, Failure> {
+    let usage = Failure::Usage;
+    let output = PathBuf::from(args.required("output").map_err(usage)?);
+    let shot = ShotSpec {
+        app: args.required("app").map_err(usage)?.to_string(),
+        window: args.value("window").map(str::to_string),
+        selector: args.required("selector").map_err(usage)?.to_string(),
+        padding: None,
+        format: None,
+        output: output.to_string_lossy().into_owned(),
+    };
+    let padding = args.parsed("padding").map_err(usage)?.unwrap_or(0.0);
+
+    let mut redaction = load_redaction_config();
+    if args.flag("no-redact") {
+        redaction.enabled = false;
+    }
+    manifest::capture_shot(&shot, &output, padding, &redaction)?;
+
+    if args.flag("json") {
+        print_json(&serde_json::json!({ "output": output }))?;
+    } else {
+        println!("{}", output.display());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Finds the element chosen by `--at` or by `--app` and `--selector`.
+fn target_element(args: &Args) -> Result<UIElementInfo, Failure> {
+    if let Some(at) = args.value("at") {
+        let (x, y) = position(at)?;
+        return Ok(accessibility::get_element_at(x, y, true).ok_or_else(|| format!("No element at {},{}", x, y))?);
+    }
+    let usage = Failure::Usage;
+    let selector: Selector = args.required("selector").map_err(usage)?.parse().map_err(usage)?;
+    let pid = app_pid(args.required("app").map_err(usage)?)?;
+    Ok(selector.find_in_app(pid).ok_or_else(|| "Selector matched nothing".to_string())?)
+}
+
+fn act(args: &Args) -> Result<ExitCode, Failure> {
+    let element = target_element(args)?;
+    if args.flag("list") {
+        let names = actions::list_actions(&element)?;
+        if args.flag("json") {
+            print_json(&names)?;
+        } else {
+            names.iter().for_each(|name| println!("{}", name));
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(action) = args.value("action") {
+        actions::perform_action(&element, action)?;
+    } else if let Some(value) = args.value("set-value") {
+        actions::set_attribute(&element, ax_attributes::VALUE, &AttributeValue::Text(value.to_string()))?;
+    } else if args.flag("focus") {
+        actions::set_attribute(&element, ax_attributes::FOCUSED, &AttributeValue::Bool(true))?;
+    } else {
+        return Err(Failure::Usage("Missing --list, --action, --set-value or --focus".to_string()));
+    }
+    if args.flag("json") {
+        print_json(&element)?;
+    } else {
+        println!("{}", describe(&element));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn replay(args: &Args) -> Result<ExitCode, Failure> {
+    let script = Script::load(Path::new(args.required("script").map_err(Failure::Usage)?))?;
+    let failure_dir = PathBuf::from(args.value("failures").unwrap_or("."));
+    let report = script::replay(&script, &failure_dir);
+
+    if args.flag("json") {
+        print_json(&report)?;
+    } else if let Some(failure) = &report.failure {
+        println!("Step {} of {} failed: {}", failure.index, report.total, failure.error);
+        println!("  {:?} {}", failure.step.action, failure.step.selector);
+        if let Some(capture) = &failure.capture {
+            println!("  capture: {}", capture);
+        }
+    } else {
+        println!("{} steps passed", report.passed);
+    }
+    Ok(if report.failure.is_some() { ExitCode::FAILURE } else { ExitCode::SUCCESS })
+}
+
+fn export_script(args: &Args) -> Result<ExitCode, Failure> {
+    let usage = Failure::Usage;
+    let script = Script::load(Path::new(args.required("script").map_err(usage)?))?;
+    match args.value("output") {
+        Some(output) => export::export_to_file(&script, Path::new(output))?,
+        None => {
+            let format: ExportFormat = args.required("format").map_err(usage)?.parse().map_err(usage)?;
+            print!("{}", export::export(&script, format));
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Uses the app's redaction settings, so scripted captures hide the same fields.
+fn load_redaction_config() -> RedactionConfig {
+    let home = std::env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+    RedactionConfig::load(&home.join("Library/Application Support").join(APP_IDENTIFIER))
+}
+
+fn run_audit(args: &Args) -> Result<ExitCode, Failure> {
+    let root = target_tree(args, ACCESSIBILITY_RECURSION_LIMIT as usize)?;
+    let issues: Vec<AuditIssue> = audit::audit(&root);
+    if args.flag("json") {
+        print_json(&issues)?;
+    } else {
+        for issue in &issues {
+            println!(
+                "{:?}: {} at {},{} size {}x{}\n  {}",
+                issue.kind,
+                issue.title.as_deref().unwrap_or(&issue.role),
+                issue.x.round(),
+                issue.y.round(),
+                issue.width.round(),
+                issue.height.round(),
+                issue.path
+            );
+        }
+        println!("{} issue(s)", issues.len());
+    }
+    Ok(if issues.is_empty() { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
+
+fn windows(args: &Args) -> Result<ExitCode, Failure> {
+    let windows: Vec<WindowInfo> = window_list::list_windows();
+    if args.flag("json") {
+        print_json(&windows)?;
+    } else {
+        for w in &windows {
+            println!("{:>6}  {:<24} {} ({})", w.number, w.owner_name, w.title, w.owner_pid);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Exits with 1 when a permission is missing, so scripts can check before running other commands.
+fn permissions(args: &Args) -> Result<ExitCode, Failure> {
+    let state = PermissionState::check();
+    if args.flag("json") {
+        print_json(&state)?;
+    } else {
+        for permission in Permission::ALL {
+            let status = if state.has(permission) { "granted" } else { "missing" };
+            println!("{:<18} {}", permission.label(), status);
+        }
+        for permission in state.missing() {
+            println!("Grant {0} access to your terminal in System Settings › Privacy & Security › {0}", permission.label());
+        }
+    }
+    Ok(if state.missing().is_empty() { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
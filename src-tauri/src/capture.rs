@@ -1,48 +1,210 @@
-use std::process::Command;
+use std::path::Path;
+use image::RgbaImage;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+use crate::accessibility::UIElementInfo;
+use crate::history::HistoryEntry;
+use crate::{clipboard, locator, permissions, redaction, scroll_capture, tray, AppState};
+
+pub use xray_core::capture::{grab_element, grab_rect, next_available_path, save_image, ScreenRect};
+pub use xray_core::error::CaptureError;
 
 /// Captures a specific rectangular region or window and saves it to a file.
 ///
-/// `path` is the full file path chosen by the user via the save dialog.
-/// If `copy_to_clipboard` in AppState is true, the saved file is also copied
-/// to the clipboard via osascript (no extra shutter sound).
+/// `path` is the full file path chosen by the user via the save dialog; its
+/// extension picks the encoder. If `copy_to_clipboard` is enabled in the
+/// settings, the image is also copied to the clipboard.
+/// Successful captures are recorded in the capture history.
 #[tauri::command]
 pub fn capture_rect_to_file(
-    x: f64,
-    y: f64,
-    width: f64,
-    height: f64,
-    window_id: u32,
-    role: String,
+    element: UIElementInfo,
     path: String,
-    state: tauri::State<crate::AppState>,
-) -> Result<(), String> {
-    let copy_to_clipboard = state.copy_to_clipboard.load(std::sync::atomic::Ordering::Relaxed);
-    let mut command = Command::new("screencapture");
-
-    if role.contains("Window") && window_id > 0 {
-        command.arg("-l");
-        command.arg(window_id.to_string());
+    app: AppHandle,
+    state: tauri::State<AppState>,
+) -> Result<(), CaptureError> {
+    capture_element_to_file(&app, &state, &element, &path)
+        .inspect_err(|e| log::error!("Capture of {} to {} failed: {}", element.role, path, e))
+}
+
+/// Captures `element` to `path`, copying it to the clipboard and recording it in the history.
+///
+/// Sensitive fields are redacted before the image is written when redaction is enabled.
+pub fn capture_element_to_file(
+    app: &AppHandle,
+    state: &AppState,
+    element: &UIElementInfo,
+    path: &str,
+) -> Result<(), CaptureError> {
+    let image = grab_redacted(state, element)?;
+    finish_capture(app, state, element, &image, path)
+}
+
+/// Grabs `element` with the configured padding and redacts sensitive fields in it when redaction is enabled.
+pub fn grab_redacted(state: &AppState, element: &UIElementInfo) -> Result<RgbaImage, CaptureError> {
+    let padding = state.settings.read(|s| s.padding);
+    let (mut image, frame) = if padding > 0.0 {
+        let frame = ScreenRect {
+            x: element.global_x - padding,
+            y: element.global_y - padding,
+            width: element.width + padding * 2.0,
+            height: element.height + padding * 2.0,
+        };
+        (grab_rect(&frame)?, frame)
     } else {
-        let region = format!("{},{},{},{}", x, y, width, height);
-        command.arg("-R");
-        command.arg(&region);
+        grab_element(element)?
+    };
+    apply_redaction(state, &mut image, &frame, element)?;
+    Ok(image)
+}
+
+/// Redacts sensitive fields in an `image` of `element` covering `frame` when redaction is enabled.
+pub fn apply_redaction(
+    state: &AppState,
+    image: &mut RgbaImage,
+    frame: &ScreenRect,
+    element: &UIElementInfo,
+) -> Result<(), CaptureError> {
+    let redaction = state.redaction.lock().map(|c| c.clone()).unwrap_or_default();
+    if redaction.enabled {
+        redaction::redact(image, frame, element, &redaction).map_err(CaptureError::RedactionFailed)?;
     }
+    Ok(())
+}
 
-    command.arg(&path);
+/// Captures the whole content of the scroll area containing `element` as one tall image.
+///
+/// The area is scrolled from top to bottom and the viewport frames are stitched
+/// together; redaction is applied to every frame before stitching. Runs off the
+/// main thread, since scrolling waits for every frame to settle.
+#[tauri::command(async)]
+pub fn capture_scroll_area_to_file(
+    element: UIElementInfo,
+    path: String,
+    app: AppHandle,
+    state: tauri::State<AppState>,
+) -> Result<(), CaptureError> {
+    let redaction = state.redaction.lock().map(|c| c.clone()).unwrap_or_default();
+    let image = scroll_capture::capture_scroll_area(&element, &redaction)
+        .inspect_err(|e| log::error!("Scrolling capture of {} failed: {}", element.role, e))?;
+    finish_capture(&app, &state, &element, &image, &path)
+        .inspect_err(|e| log::error!("Saving the scrolling capture to {} failed: {}", path, e))
+}
 
-    let output = command.output().map_err(|e| e.to_string())?;
+/// Saves a grabbed `image` of `element` to `path`, records it in the history and
+/// copies it to the clipboard if enabled.
+///
+/// A failed copy is returned as [`CaptureError::ClipboardFailed`] even though
+/// the file was saved, so the user knows the clipboard holds something else.
+pub fn finish_capture(
+    app: &AppHandle,
+    state: &AppState,
+    element: &UIElementInfo,
+    image: &RgbaImage,
+    path: &str,
+) -> Result<(), CaptureError> {
+    save_image(image, path)?;
 
-    if !output.status.success() {
-        return Err(String::from_utf8_lossy(&output.stderr).to_string());
+    if let Ok(mut history) = state.history.lock() {
+        if let Err(e) = history.record(path, element) {
+            log::warn!("Failed to record capture history for {}: {}", path, e);
+        }
     }
+    tray::refresh_menu(app);
 
-    if copy_to_clipboard {
-        let script = format!(
-            "set the clipboard to (read (POSIX file \"{}\") as «class PNGf»)",
-            path
-        );
-        let _ = Command::new("osascript").arg("-e").arg(&script).output();
+    if state.settings.read(|s| s.copy_to_clipboard) {
+        clipboard::copy_image(image, Some(Path::new(path)))?;
     }
-
     Ok(())
 }
+
+/// Logs a failed capture and tells the user why.
+///
+/// Captures started from the tray or a shortcut have no overlay to show the
+/// error in, so a dialog is used; a missing permission opens the permission guide instead.
+pub fn report_error(app: &AppHandle, context: &str, error: &CaptureError) {
+    log::error!("{}: {}", context, error);
+    if let CaptureError::PermissionDenied(_) = error {
+        permissions::ensure(app);
+        return;
+    }
+    app.dialog()
+        .message(error.to_string())
+        .title(context)
+        .kind(MessageDialogKind::Error)
+        .show(|_| {});
+}
+
+/// What clicking an element in the overlay captures.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CaptureMode {
+    /// The element's visible rect.
+    #[default]
+    Element,
+    /// The full content of the scroll area containing the element.
+    ScrollArea,
+    /// The element's rect after a countdown, so transient UI can be brought up first.
+    /// The image is grabbed before the save dialog opens and kept as the pending capture.
+    Delayed,
+    /// Repeated grabs of the element's rect, encoded as an animation when recording stops.
+    Record,
+    /// Nothing is captured; the element is sent to the external client that asked for it.
+    Pick,
+}
+
+/// Payload of the capture-click event.
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureClick {
+    pub element: UIElementInfo,
+    pub mode: CaptureMode,
+    /// Whether the image was already grabbed and only needs to be saved with `save_pending_capture`.
+    pub pending: bool,
+}
+
+/// Re-captures the most recently captured element.
+#[tauri::command]
+pub fn recapture_last_element(app: AppHandle) -> Result<String, CaptureError> {
+    recapture_last(&app)
+}
+
+/// Re-captures the newest history entry. Returns the path that was written.
+pub fn recapture_last(app: &AppHandle) -> Result<String, CaptureError> {
+    let state = app.state::<AppState>();
+    recapture_entry(app, &state, &last_entry(&state)?)
+}
+
+/// The newest history entry.
+pub fn last_entry(state: &AppState) -> Result<HistoryEntry, CaptureError> {
+    state.history.lock()
+        .map_err(|e| CaptureError::Other(e.to_string()))?
+        .entries()
+        .into_iter()
+        .next()
+        .ok_or_else(|| "Nothing has been captured yet".to_string().into())
+}
+
+/// Captures the element of a history entry again.
+///
+/// The element is re-resolved through its locator so that moved or resized
+/// elements are captured where they are now; if it cannot be found, the
+/// previously captured rect is used. The capture overwrites the original file
+/// or goes to an auto-incremented name, depending on `overwrite_recapture`.
+pub fn recapture_entry(app: &AppHandle, state: &AppState, entry: &HistoryEntry) -> Result<String, CaptureError> {
+    let element = entry.element.locator
+        .as_ref()
+        .and_then(locator::resolve)
+        .unwrap_or_else(|| {
+            log::info!("Could not re-resolve element, using its previous rect");
+            entry.element.clone()
+        });
+
+    let path = if state.settings.read(|s| s.overwrite_recapture) {
+        entry.path.clone()
+    } else {
+        next_available_path(Path::new(&entry.path)).to_string_lossy().into_owned()
+    };
+
+    capture_element_to_file(app, state, &element, &path)?;
+    Ok(path)
+}
@@ -0,0 +1,153 @@
+use std::io::Cursor;
+use std::path::Path;
+use image::{ImageFormat, RgbaImage};
+use objc2::runtime::ProtocolObject;
+use objc2_app_kit::{
+    NSPasteboard, NSPasteboardItem, NSPasteboardTypeFileURL, NSPasteboardTypePNG, NSPasteboardTypeTIFF,
+    NSPasteboardWriting,
+};
+use objc2_foundation::{NSArray, NSData, NSString};
+use crate::capture::CaptureError;
+
+/// Puts `image` on the clipboard as PNG and TIFF, plus the URL of `file` when it was saved.
+///
+/// Image editors paste the pixels, while Finder, Mail and chat apps paste the
+/// file. Everything is written in-process from memory, so the file name is
+/// never interpreted by anything.
+///
+/// `arboard` clears the clipboard on every write and can't offer several
+/// representations at once, which is why the pasteboard is used directly.
+pub fn copy_image(image: &RgbaImage, file: Option<&Path>) -> Result<(), CaptureError> {
+    let contents = ImageContents::new(image, file)?;
+
+    let item = NSPasteboardItem::new();
+    let mut written = item.setData_forType(&NSData::with_bytes(&contents.png), unsafe { NSPasteboardTypePNG })
+        && item.setData_forType(&NSData::with_bytes(&contents.tiff), unsafe { NSPasteboardTypeTIFF });
+    if let Some(url) = &contents.file_url {
+        written &= item.setString_forType(&NSString::from_str(url), unsafe { NSPasteboardTypeFileURL });
+    }
+    if !written {
+        return Err(CaptureError::ClipboardFailed("The image could not be added to the pasteboard item".to_string()));
+    }
+
+    let pasteboard = NSPasteboard::generalPasteboard();
+    pasteboard.clearContents();
+    let objects = NSArray::from_retained_slice(&[ProtocolObject::<dyn NSPasteboardWriting>::from_retained(item)]);
+    if pasteboard.writeObjects(&objects) {
+        Ok(())
+    } else {
+        Err(CaptureError::ClipboardFailed("The pasteboard refused the image".to_string()))
+    }
+}
+
+/// The representations [`copy_image`] puts on the pasteboard.
+struct ImageContents {
+    png: Vec<u8>,
+    tiff: Vec<u8>,
+    file_url: Option<String>,
+}
+
+impl ImageContents {
+    fn new(image: &RgbaImage, file: Option<&Path>) -> Result<Self, CaptureError> {
+        Ok(Self {
+            png: encode(image, ImageFormat::Png)?,
+            tiff: encode(image, ImageFormat::Tiff)?,
+            file_url: file.and_then(file_url),
+        })
+    }
+}
+
+/// Puts plain `text` on the clipboard.
+pub fn copy_text(text: &str) -> Result<(), CaptureError> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| CaptureError::ClipboardFailed(e.to_string()))
+}
+
+/// Copies a saved capture, e.g. from the history, reading its pixels back from disk.
+pub fn copy_file(path: &Path) -> Result<(), CaptureError> {
+    let image = image::open(path)
+        .map_err(|e| CaptureError::ClipboardFailed(format!("Failed to read {}: {}", path.display(), e)))?
+        .to_rgba8();
+    copy_image(&image, Some(path))
+}
+
+fn encode(image: &RgbaImage, format: ImageFormat) -> Result<Vec<u8>, CaptureError> {
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), format)
+        .map_err(|e| CaptureError::EncoderFailed(e.to_string()))?;
+    Ok(bytes)
+}
+
+/// The percent-encoded `file://` URL of `path`; `None` if the file is gone.
+///
+/// Every byte outside the unreserved characters and `/` is escaped, so quotes,
+/// `%`, `#`, newlines and non-ASCII names survive the round trip.
+fn file_url(path: &Path) -> Option<String> {
+    let path = path.canonicalize().ok()?;
+    let mut url = String::from("file://");
+    for byte in path.as_os_str().as_encoded_bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => url.push(*byte as char),
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    Some(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("xray-clipboard-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, b"").unwrap();
+        path
+    }
+
+    fn url_of(name: &str) -> (String, String) {
+        let path = temp_file(name);
+        let dir = path.parent().unwrap().canonicalize().unwrap();
+        let url = file_url(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        (url, format!("file://{}/", dir.display()))
+    }
+
+    #[test]
+    fn file_url_escapes_odd_names() {
+        let cases = [
+            ("say \"hi\".png", "say%20%22hi%22.png"),
+            ("it's.png", "it%27s.png"),
+            ("two words.png", "two%20words.png"),
+            ("100%.png", "100%25.png"),
+            ("#1.png", "%231.png"),
+            ("line\nbreak.png", "line%0Abreak.png"),
+            ("café ☕.png", "caf%C3%A9%20%E2%98%95.png"),
+        ];
+        for (name, encoded) in cases {
+            let (url, dir) = url_of(name);
+            assert_eq!(url, format!("{}{}", dir, encoded), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn file_url_of_missing_file_is_none() {
+        assert_eq!(file_url(Path::new("/nonexistent/xray/capture.png")), None);
+    }
+
+    #[test]
+    fn image_contents_round_trip() {
+        let image = RgbaImage::from_fn(7, 5, |x, y| Rgba([x as u8 * 30, y as u8 * 40, 200, 255]));
+        let contents = ImageContents::new(&image, None).unwrap();
+
+        for (bytes, format) in [(&contents.png, ImageFormat::Png), (&contents.tiff, ImageFormat::Tiff)] {
+            let decoded = image::load_from_memory_with_format(bytes, format).unwrap().to_rgba8();
+            assert_eq!(decoded, image, "{:?}", format);
+        }
+        assert_eq!(contents.file_url, None);
+    }
+}
@@ -1,39 +1,164 @@
+pub use xray_core::constants::*;
+
 /// The label/ID of the main overlay window.
 pub const WINDOW_LABEL_MAIN: &str = "main";
 
+/// The label/ID of the annotation preview window.
+pub const WINDOW_LABEL_PREVIEW: &str = "preview";
+
+/// The label/ID of the window picker.
+pub const WINDOW_LABEL_PICKER: &str = "picker";
+
 /// The event name emitted to the frontend when a UI element is hovered.
 pub const EVENT_ELEMENT_HOVER: &str = "element-hover";
 
 /// The event name emitted to the frontend when the user clicks to capture.
 pub const EVENT_CAPTURE_CLICK: &str = "capture-click";
 
+/// Event emitted when a recording has stopped and can be saved.
+pub const EVENT_RECORDING_FINISHED: &str = "recording-finished";
+
+/// Event emitted with the report of comparing a re-capture against its baseline.
+pub const EVENT_COMPARISON_RESULT: &str = "comparison-result";
+
+/// Event emitted with the report of a screenshot manifest run.
+pub const EVENT_MANIFEST_FINISHED: &str = "manifest-finished";
+
+/// Event emitted with the frozen image of the display the overlay is on.
+pub const EVENT_FROZEN_FRAME: &str = "frozen-frame";
+
+/// Event sent to the frontend with the report of a replayed interaction script.
+pub const EVENT_SCRIPT_REPLAYED: &str = "script-replayed";
+
+/// Event sent to the frontend with the new settings whenever they change.
+pub const EVENT_SETTINGS_CHANGED: &str = "settings-changed";
+
+/// Event sent to the frontend with the status of every shortcut after they are registered.
+pub const EVENT_SHORTCUTS_CHANGED: &str = "shortcuts-changed";
+
+/// Event sent to the frontend with the new permission state whenever a permission is granted or revoked.
+pub const EVENT_PERMISSIONS_CHANGED: &str = "permissions-changed";
+
+/// How often in seconds missing permissions are re-checked until they are granted.
+pub const PERMISSION_POLL_SECS: u64 = 2;
+
 /// The polling interval in milliseconds (approx. 60 FPS).
 pub const POLLING_INTERVAL_MS: u64 = 16;
 
 /// The delay in milliseconds to wait for the window to hide before capturing the screen.
 pub const WINDOW_HIDE_DELAY_MS: u64 = 150;
 
-/// The maximum depth to drill down into accessibility elements.
-pub const ACCESSIBILITY_RECURSION_LIMIT: i32 = 50;
-
-/// Accessibility attribute names.
-pub mod ax_attributes {
-    pub const CHILDREN: &str = "AXChildren";
-    pub const ROLE: &str = "AXRole";
-    pub const POSITION: &str = "AXPosition";
-    pub const SIZE: &str = "AXSize";
-}
-
-/// Accessibility roles.
-pub mod ax_roles {
-    pub const IMAGE: &str = "AXImage";
-    pub const CHECKBOX: &str = "AXCheckBox";
-    pub const RADIO_BUTTON: &str = "AXRadioButton";
-    pub const STATIC_TEXT: &str = "AXStaticText";
-    pub const HEADING: &str = "AXHeading";
-    pub const BUTTON: &str = "AXButton";
-    pub const LINK: &str = "AXLink";
-    pub const GROUP: &str = "AXGroup";
-    pub const WEB_AREA: &str = "AXWebArea";
-    pub const SCROLL_AREA: &str = "AXScrollArea";
-}
+/// The file name of the capture history index inside the app data directory.
+pub const HISTORY_INDEX_FILE: &str = "history.json";
+
+/// The directory holding capture thumbnails inside the app data directory.
+pub const HISTORY_THUMBNAIL_DIR: &str = "thumbnails";
+
+/// The maximum edge length in pixels of a history thumbnail.
+pub const HISTORY_THUMBNAIL_SIZE: u32 = 160;
+
+/// The maximum number of entries kept in the capture history.
+pub const HISTORY_LIMIT: usize = 500;
+
+/// The id of the menu bar tray icon.
+pub const TRAY_ID: &str = "main";
+
+/// The number of captures listed in the tray's "Recent Captures" submenu.
+pub const RECENT_CAPTURES_LIMIT: usize = 10;
+
+/// The maximum edge length in pixels of a thumbnail shown in the tray menu.
+pub const TRAY_THUMBNAIL_SIZE: u32 = 32;
+
+/// The file name of the settings inside the app config directory.
+pub const SETTINGS_FILE: &str = "settings.toml";
+
+/// The copy kept of a settings file that had invalid values, next to it.
+pub const SETTINGS_INVALID_FILE: &str = "settings.toml.invalid";
+
+/// The version written to the settings file.
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// The largest padding in points the settings accept for element captures.
+pub const SETTINGS_MAX_PADDING: f64 = 200.0;
+
+/// The global shortcut that starts capture mode.
+pub const SHORTCUT_START_CAPTURE: &str = "CommandOrControl+Shift+X";
+
+/// The global shortcut that re-captures the most recently captured element.
+pub const SHORTCUT_RECAPTURE_LAST: &str = "CommandOrControl+Alt+Shift+X";
+
+/// The global shortcut that starts picking an element to record, or stops the recording.
+pub const SHORTCUT_TOGGLE_RECORDING: &str = "CommandOrControl+Alt+Shift+R";
+
+/// The global shortcut that captures the window under the cursor.
+pub const SHORTCUT_CAPTURE_WINDOW: &str = "CommandOrControl+Alt+Shift+W";
+
+/// The global shortcut that captures the parent of the element under the cursor.
+pub const SHORTCUT_CAPTURE_PARENT: &str = "CommandOrControl+Alt+Shift+P";
+
+/// The global shortcut that turns freezing the screen while inspecting on or off.
+pub const SHORTCUT_TOGGLE_FREEZE: &str = "CommandOrControl+Alt+Shift+F";
+
+/// The global shortcut that copies the element under the cursor as text.
+pub const SHORTCUT_COPY_ELEMENT: &str = "CommandOrControl+Alt+Shift+C";
+
+/// The current version of the annotation document format.
+pub const ANNOTATION_DOCUMENT_VERSION: u32 = 1;
+
+/// Appended to a capture's path to name its annotation document.
+pub const ANNOTATION_SIDECAR_SUFFIX: &str = ".annotations.json";
+
+/// The directory inside the app data directory holding unannotated copies of captures.
+pub const ANNOTATION_ORIGINALS_DIR: &str = "originals";
+
+/// System fonts tried in order for text and callout annotations.
+pub const ANNOTATION_FONT_PATHS: &[&str] = &[
+    "/System/Library/Fonts/SFNS.ttf",
+    "/System/Library/Fonts/Helvetica.ttc",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+];
+
+/// The countdown in seconds used by delayed captures until the user picks another.
+pub const DEFAULT_CAPTURE_DELAY_SECS: u64 = 5;
+
+/// The countdowns in seconds offered in the tray's "Capture Delay" submenu.
+pub const CAPTURE_DELAY_CHOICES: &[u64] = &[3, 5, 10];
+
+/// The maximum number of elements recorded per app when freezing the screen.
+pub const FROZEN_TREE_NODE_LIMIT: usize = 5000;
+
+/// The frame rate used by recordings until the user picks another.
+pub const DEFAULT_RECORDING_FPS: u32 = 15;
+
+/// The frame rates offered in the tray's "Recording Frame Rate" submenu.
+pub const RECORDING_FPS_CHOICES: &[u32] = &[10, 15, 30];
+
+/// Recordings stop on their own after this many seconds.
+pub const RECORDING_MAX_SECS: u64 = 60;
+
+/// Recordings stop on their own once their frames take this many bytes, 1 GiB.
+pub const RECORDING_MAX_BYTES: usize = 1 << 30;
+
+/// How often a recording looks for sensitive fields again, in milliseconds.
+pub const RECORDING_REDACTION_REFRESH_MS: u64 = 1000;
+
+/// Where ffmpeg is looked for before falling back to `PATH`, which is minimal for apps started from Finder.
+pub const FFMPEG_PATHS: &[&str] = &["/opt/homebrew/bin/ffmpeg", "/usr/local/bin/ffmpeg"];
+
+/// The directory inside the app data directory holding baseline images for visual comparison.
+pub const BASELINE_DIR: &str = "baselines";
+
+/// Replaces a capture's extension to name its diff image, e.g. `shot.diff.png`.
+pub const DIFF_IMAGE_SUFFIX: &str = ".diff.png";
+
+/// The localhost port of the inspection server.
+pub const SERVER_PORT: u16 = 9753;
+
+/// The file name of the inspection server's access token inside the app config directory.
+pub const SERVER_TOKEN_FILE: &str = "server-token";
+
+/// The permissions of the server token file: readable and writable by the user only.
+pub const SERVER_TOKEN_FILE_MODE: u32 = 0o600;
+
+/// How often in milliseconds the inspection server checks for new connections, requests and events.
+pub const SERVER_POLL_MS: u64 = 50;
@@ -0,0 +1,41 @@
+use std::thread;
+use tauri::{AppHandle, Manager};
+use crate::accessibility::{self, UIElementInfo};
+use crate::{clipboard, AppState};
+
+pub use xray_core::element_format::*;
+
+/// Copies `element` to the clipboard as text in `format`.
+pub fn copy(element: &UIElementInfo, format: ElementFormat) -> Result<String, String> {
+    let text = format_element(element, format)?;
+    clipboard::copy_text(&text)?;
+    log::info!("Copied {} as {}", element.role, format.label());
+    Ok(text)
+}
+
+/// Copies the element under the cursor in the format chosen in the settings.
+pub fn copy_under_cursor(app: &AppHandle) {
+    let format = app.state::<AppState>().settings.read(|s| s.copy_format);
+    thread::spawn(move || {
+        let Some(element) = accessibility::get_located_element_at_mouse() else {
+            log::warn!("Found no element to copy under the cursor");
+            return;
+        };
+        if let Err(e) = copy(&element, format) {
+            log::error!("Failed to copy {} as {}: {}", element.role, format.label(), e);
+        }
+    });
+}
+
+/// Renders `element` in `format` without touching the clipboard.
+#[tauri::command]
+pub fn format_element_info(element: UIElementInfo, format: ElementFormat) -> Result<String, String> {
+    format_element(&element, format)
+}
+
+/// Copies `element` as text, in `format` or else the one chosen in the settings, and returns the text.
+#[tauri::command]
+pub fn copy_element_info(element: UIElementInfo, format: Option<ElementFormat>, app: AppHandle) -> Result<String, String> {
+    let format = format.unwrap_or_else(|| app.state::<AppState>().settings.read(|s| s.copy_format));
+    copy(&element, format)
+}
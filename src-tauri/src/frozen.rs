@@ -0,0 +1,211 @@
+use std::collections::BTreeSet;
+use std::ffi::c_void;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use accessibility_sys::{AXUIElementCreateApplication, AXUIElementRef};
+use base64::Engine;
+use core_graphics::display::CGDisplay;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{imageops, RgbaImage};
+use crate::accessibility::{self, element_info, UIElementInfo};
+use crate::capture::{self, ScreenRect};
+use crate::constants::FROZEN_TREE_NODE_LIMIT;
+use crate::{locator, tree, window_list};
+
+/// Every display and the on-screen windows, captured at one instant, with the
+/// accessibility trees of their apps.
+///
+/// While a frozen frame exists the overlay shows its images, hover hit-tests
+/// run against its elements, and clicks crop from it instead of grabbing the
+/// screen again.
+///
+/// Only the tree of the app under the cursor is read up front; other apps are
+/// read the first time the pointer reaches one of their windows, so freezing
+/// stays quick with many apps open.
+pub struct FrozenFrame {
+    id: u64,
+    displays: Vec<FrozenDisplay>,
+    /// On-screen windows, frontmost first.
+    windows: Vec<window_list::WindowInfo>,
+    /// The elements of each app with an on-screen window, read on first use.
+    apps: Vec<(i32, OnceLock<Vec<FrozenElement>>)>,
+}
+
+struct FrozenDisplay {
+    bounds: ScreenRect,
+    image: RgbaImage,
+    data_url: OnceLock<String>,
+}
+
+struct FrozenElement {
+    element: AXUIElementRef,
+    info: UIElementInfo,
+    depth: usize,
+}
+
+// Accessibility elements are immutable CoreFoundation objects that may be used from any thread.
+unsafe impl Send for FrozenElement {}
+
+impl Drop for FrozenElement {
+    fn drop(&mut self) {
+        unsafe { core_foundation::base::CFRelease(self.element as *const c_void) };
+    }
+}
+
+impl FrozenFrame {
+    /// Grabs every active display, then walks the tree of the app under the cursor.
+    pub fn capture() -> Result<Self, String> {
+        let ids = CGDisplay::active_displays().map_err(|e| format!("Failed to list displays: {}", e))?;
+        let mut displays = Vec::with_capacity(ids.len());
+        for id in ids {
+            let rect = CGDisplay::new(id).bounds();
+            let bounds = ScreenRect { x: rect.origin.x, y: rect.origin.y, width: rect.size.width, height: rect.size.height };
+            displays.push(FrozenDisplay { bounds, image: capture::grab_rect(&bounds)?, data_url: OnceLock::new() });
+        }
+
+        let windows = window_list::list_windows();
+        let pids: BTreeSet<i32> = windows.iter().map(|w| w.owner_pid).filter(|pid| *pid > 0).collect();
+        let apps = pids.into_iter().map(|pid| (pid, OnceLock::new())).collect();
+
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        let frame = Self { id: NEXT_ID.fetch_add(1, Ordering::Relaxed), displays, windows, apps };
+        if let Some(mouse) = accessibility::mouse_location() {
+            for window in frame.windows_at(mouse.x, mouse.y) {
+                frame.elements_of(window.owner_pid);
+            }
+        }
+        Ok(frame)
+    }
+
+    /// Distinguishes frames taken by different capture sessions.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Finds the most specific element at a global position, as the live hover does.
+    ///
+    /// The elements of the frontmost window with any at that position are
+    /// considered; among those the smallest wins, and the deepest on ties.
+    pub fn element_at(&self, x: f64, y: f64) -> Option<UIElementInfo> {
+        self.hit_test(x, y).map(|e| self.describe(e))
+    }
+
+    /// Like [`FrozenFrame::element_at`], but also builds the element's locator.
+    pub fn located_element_at(&self, x: f64, y: f64) -> Option<UIElementInfo> {
+        self.hit_test(x, y).map(|e| {
+            let mut info = self.describe(e);
+            info.locator = Some(unsafe { locator::build(e.element) });
+            info
+        })
+    }
+
+    /// A copy of the element's info with its window's title and layer as they were when frozen.
+    fn describe(&self, element: &FrozenElement) -> UIElementInfo {
+        let mut info = element.info.clone();
+        if let Some(window) = self.windows.iter().find(|w| w.number == info.window_id) {
+            info.window_title = Some(window.title.clone()).filter(|t| !t.is_empty());
+            info.window_layer = Some(window.layer);
+        }
+        info
+    }
+
+    /// Tries the windows at the position front to back, so transparent or
+    /// shadow-only windows without elements there don't hide the ones below.
+    fn hit_test(&self, x: f64, y: f64) -> Option<&FrozenElement> {
+        let contains = |r: &ScreenRect| x >= r.x && x < r.x + r.width && y >= r.y && y < r.y + r.height;
+        self.windows_at(x, y).find_map(|window| {
+            self.elements_of(window.owner_pid)
+                .iter()
+                .filter(|e| e.info.window_id == window.number && contains(&ScreenRect::of_element(&e.info)))
+                .min_by(|a, b| {
+                    let area = |e: &FrozenElement| e.info.width * e.info.height;
+                    area(a).total_cmp(&area(b)).then(b.depth.cmp(&a.depth))
+                })
+        })
+    }
+
+    /// The windows containing a global position, frontmost first.
+    fn windows_at(&self, x: f64, y: f64) -> impl Iterator<Item = &window_list::WindowInfo> {
+        self.windows.iter().filter(move |w| x >= w.x && x < w.x + w.width && y >= w.y && y < w.y + w.height)
+    }
+
+    /// The elements of the app with process id `pid`, reading its tree the first time.
+    fn elements_of(&self, pid: i32) -> &[FrozenElement] {
+        let Some((_, elements)) = self.apps.iter().find(|(p, _)| *p == pid) else {
+            return &[];
+        };
+        elements.get_or_init(|| unsafe { snapshot_app(pid, &self.displays) })
+    }
+
+    /// Crops the frozen pixels showing `rect` from the display that shows most of it.
+    pub fn crop(&self, rect: &ScreenRect) -> Result<RgbaImage, String> {
+        let overlap = |d: &FrozenDisplay| {
+            let w = (rect.x + rect.width).min(d.bounds.x + d.bounds.width) - rect.x.max(d.bounds.x);
+            let h = (rect.y + rect.height).min(d.bounds.y + d.bounds.height) - rect.y.max(d.bounds.y);
+            w.max(0.0) * h.max(0.0)
+        };
+        let display = self.displays
+            .iter()
+            .filter(|d| overlap(d) > 0.0)
+            .max_by(|a, b| overlap(a).total_cmp(&overlap(b)))
+            .ok_or_else(|| "The element is not on any display".to_string())?;
+
+        let scale = display.image.width() as f64 / display.bounds.width;
+        let left = ((rect.x - display.bounds.x) * scale).floor().max(0.0) as u32;
+        let top = ((rect.y - display.bounds.y) * scale).floor().max(0.0) as u32;
+        let right = (((rect.x + rect.width - display.bounds.x) * scale).ceil().max(0.0) as u32).min(display.image.width());
+        let bottom = (((rect.y + rect.height - display.bounds.y) * scale).ceil().max(0.0) as u32).min(display.image.height());
+        if right <= left || bottom <= top {
+            return Err("The element has no visible area".to_string());
+        }
+
+        Ok(imageops::crop_imm(&display.image, left, top, right - left, bottom - top).to_image())
+    }
+
+    /// Returns the frozen image of the display whose top-left corner is at `(x, y)` as a PNG data URL.
+    pub fn display_data_url(&self, x: f64, y: f64) -> Option<&str> {
+        let display = self.displays
+            .iter()
+            .find(|d| (d.bounds.x - x).abs() < 1.0 && (d.bounds.y - y).abs() < 1.0)?;
+
+        let url = display.data_url.get_or_init(|| {
+            let mut png = Vec::new();
+            let encoder = PngEncoder::new_with_quality(Cursor::new(&mut png), CompressionType::Fast, FilterType::NoFilter);
+            if let Err(e) = display.image.write_with_encoder(encoder) {
+                log::warn!("Failed to encode frozen frame: {}", e);
+                return String::new();
+            }
+            format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(png))
+        });
+        (!url.is_empty()).then_some(url.as_str())
+    }
+}
+
+/// Records every element of `pid` that is visible on some display, keeping a retained reference to each.
+unsafe fn snapshot_app(pid: i32, displays: &[FrozenDisplay]) -> Vec<FrozenElement> {
+    let mut elements = Vec::new();
+    let app = AXUIElementCreateApplication(pid);
+    if app.is_null() {
+        return elements;
+    }
+
+    tree::walk_descendants(app, FROZEN_TREE_NODE_LIMIT, |child, depth| {
+        let Some(info) = element_info(child) else {
+            return true;
+        };
+
+        // Skip subtrees that are entirely off-screen, e.g. minimized or scrolled-away content.
+        let rect = ScreenRect::of_element(&info);
+        if !displays.iter().any(|d| rect.intersects(&d.bounds)) {
+            return false;
+        }
+
+        core_foundation::base::CFRetain(child as *const c_void);
+        elements.push(FrozenElement { element: child, info, depth });
+        true
+    });
+
+    core_foundation::base::CFRelease(app as *const c_void);
+    elements
+}
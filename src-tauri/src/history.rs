@@ -0,0 +1,218 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, State};
+use tauri_plugin_opener::OpenerExt;
+use crate::accessibility::UIElementInfo;
+use crate::capture::CaptureError;
+use crate::constants::{HISTORY_INDEX_FILE, HISTORY_LIMIT, HISTORY_THUMBNAIL_DIR, HISTORY_THUMBNAIL_SIZE};
+use crate::{clipboard, tray, window_list, AppState};
+
+/// A single capture recorded in the history.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: u64,
+    /// Absolute path of the saved capture.
+    pub path: String,
+    /// Capture time in milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The element that was captured.
+    pub element: UIElementInfo,
+    /// The name of the application owning the captured window, if known.
+    pub app_name: Option<String>,
+    /// The title of the captured window, if known.
+    pub window_title: Option<String>,
+    /// Absolute path of the thumbnail image, if one could be generated.
+    pub thumbnail: Option<String>,
+    pub file_size: u64,
+}
+
+/// Filters for searching the history. All fields are case-insensitive substring matches.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryQuery {
+    pub app: Option<String>,
+    pub role: Option<String>,
+    pub title: Option<String>,
+}
+
+impl HistoryQuery {
+    fn matches(&self, entry: &HistoryEntry) -> bool {
+        let contains = |haystack: Option<&str>, needle: &Option<String>| match needle {
+            Some(needle) if !needle.is_empty() => haystack
+                .map(|h| h.to_lowercase().contains(&needle.to_lowercase()))
+                .unwrap_or(false),
+            _ => true,
+        };
+
+        let title = entry.element.title.as_deref().or(entry.window_title.as_deref());
+
+        contains(entry.app_name.as_deref(), &self.app)
+            && contains(Some(&entry.element.role), &self.role)
+            && contains(title, &self.title)
+    }
+}
+
+/// The capture history, persisted as a JSON index in the app data directory.
+pub struct History {
+    dir: PathBuf,
+    entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    /// Loads the history index from `dir`, starting empty if it is missing or unreadable.
+    pub fn load(dir: PathBuf) -> Self {
+        let entries = std::fs::read_to_string(dir.join(HISTORY_INDEX_FILE))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+
+        Self { dir, entries }
+    }
+
+    /// Returns all entries, newest first.
+    pub fn entries(&self) -> Vec<HistoryEntry> {
+        self.entries.iter().rev().cloned().collect()
+    }
+
+    /// Returns the entries matching `query`, newest first.
+    pub fn search(&self, query: &HistoryQuery) -> Vec<HistoryEntry> {
+        self.entries.iter().rev().filter(|e| query.matches(e)).cloned().collect()
+    }
+
+    pub fn get(&self, id: u64) -> Option<&HistoryEntry> {
+        self.entries.iter().find(|e| e.id == id)
+    }
+
+    /// Records a capture saved at `path`, generating its thumbnail.
+    pub fn record(&mut self, path: &str, element: &UIElementInfo) -> Result<HistoryEntry, String> {
+        let file_size = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+        let id = self.entries.iter().map(|e| e.id).max().map_or(1, |id| id + 1);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let window = window_list::find_window(element.window_id);
+
+        let thumbnail = match self.write_thumbnail(id, Path::new(path)) {
+            Ok(thumb) => Some(thumb.to_string_lossy().into_owned()),
+            Err(e) => {
+                log::warn!("Failed to create thumbnail for {}: {}", path, e);
+                None
+            }
+        };
+
+        let entry = HistoryEntry {
+            id,
+            path: path.to_string(),
+            timestamp,
+            element: element.clone(),
+            app_name: element.app_name.clone().or_else(|| window.as_ref().map(|w| w.owner_name.clone())),
+            window_title: element.window_title.clone().or_else(|| window.map(|w| w.title).filter(|t| !t.is_empty())),
+            thumbnail,
+            file_size,
+        };
+
+        self.entries.push(entry.clone());
+
+        // Drop the oldest entries beyond the limit, along with their thumbnails.
+        if self.entries.len() > HISTORY_LIMIT {
+            let excess = self.entries.len() - HISTORY_LIMIT;
+            for old in self.entries.drain(..excess) {
+                remove_thumbnail(&old);
+            }
+        }
+
+        self.save()?;
+        Ok(entry)
+    }
+
+    /// Removes the entry with `id` and its thumbnail. The capture file itself is left untouched.
+    pub fn remove(&mut self, id: u64) -> Result<Option<HistoryEntry>, String> {
+        let Some(index) = self.entries.iter().position(|e| e.id == id) else {
+            return Ok(None);
+        };
+        let entry = self.entries.remove(index);
+        remove_thumbnail(&entry);
+        self.save()?;
+        Ok(Some(entry))
+    }
+
+    fn write_thumbnail(&self, id: u64, source: &Path) -> Result<PathBuf, String> {
+        let dir = self.dir.join(HISTORY_THUMBNAIL_DIR);
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+        let thumb_path = dir.join(format!("{}.png", id));
+        image::open(source)
+            .map_err(|e| e.to_string())?
+            .thumbnail(HISTORY_THUMBNAIL_SIZE, HISTORY_THUMBNAIL_SIZE)
+            .save(&thumb_path)
+            .map_err(|e| e.to_string())?;
+
+        Ok(thumb_path)
+    }
+
+    fn save(&self) -> Result<(), String> {
+        std::fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+        let json = serde_json::to_string_pretty(&self.entries).map_err(|e| e.to_string())?;
+        std::fs::write(self.dir.join(HISTORY_INDEX_FILE), json).map_err(|e| e.to_string())
+    }
+}
+
+fn remove_thumbnail(entry: &HistoryEntry) {
+    if let Some(thumb) = &entry.thumbnail {
+        let _ = std::fs::remove_file(thumb);
+    }
+}
+
+/// Looks up a history entry by id, cloning it out of the lock.
+pub(crate) fn find_entry(state: &State<AppState>, id: u64) -> Result<HistoryEntry, String> {
+    state.history.lock()
+        .map_err(|e| e.to_string())?
+        .get(id)
+        .cloned()
+        .ok_or_else(|| format!("No history entry with id {}", id))
+}
+
+/// Lists all recorded captures, newest first.
+#[tauri::command]
+pub fn list_history(state: State<AppState>) -> Result<Vec<HistoryEntry>, String> {
+    Ok(state.history.lock().map_err(|e| e.to_string())?.entries())
+}
+
+/// Searches recorded captures by application, role and title.
+#[tauri::command]
+pub fn search_history(query: HistoryQuery, state: State<AppState>) -> Result<Vec<HistoryEntry>, String> {
+    Ok(state.history.lock().map_err(|e| e.to_string())?.search(&query))
+}
+
+/// Opens a recorded capture in the default image viewer.
+#[tauri::command]
+pub fn reopen_history_entry(id: u64, app: AppHandle, state: State<AppState>) -> Result<(), String> {
+    let entry = find_entry(&state, id)?;
+    app.opener().open_path(entry.path, None::<&str>).map_err(|e| e.to_string())
+}
+
+/// Copies a recorded capture to the clipboard again.
+#[tauri::command]
+pub fn copy_history_entry(id: u64, state: State<AppState>) -> Result<(), CaptureError> {
+    let entry = find_entry(&state, id)?;
+    clipboard::copy_file(Path::new(&entry.path))
+}
+
+/// Deletes a history entry. When `delete_file` is true the capture file is removed as well.
+#[tauri::command]
+pub fn delete_history_entry(
+    id: u64,
+    delete_file: bool,
+    app: AppHandle,
+    state: State<AppState>,
+) -> Result<(), String> {
+    let removed = state.history.lock().map_err(|e| e.to_string())?.remove(id)?;
+    tray::refresh_menu(&app);
+
+    if let (Some(entry), true) = (removed, delete_file) {
+        std::fs::remove_file(&entry.path).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
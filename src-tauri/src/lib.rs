@@ -1,53 +1,126 @@
-mod accessibility;
-mod capture;
-mod constants;
+mod actions;
+mod annotation;
+mod baseline;
+pub mod capture;
+mod clipboard;
+pub mod constants;
+mod element_format;
+mod frozen;
+mod history;
+pub mod manifest;
+mod permissions;
 mod polling;
+mod recording;
+pub mod redaction;
+mod rpc;
+mod script;
+mod server;
+mod settings;
+mod shortcuts;
+mod stdio;
+mod timed_capture;
+mod tray;
+mod window_picker;
 
-use tauri::{
-    menu::{CheckMenuItem, Menu, MenuItem},
-    tray::TrayIconBuilder,
-    Manager,
-};
+pub use xray_core::{accessibility, audit, diff, hash, locator, scroll_capture, selector, stitch, tree, window_list};
+
+use tauri::Manager;
 use std::sync::Mutex;
 use std::sync::atomic::{AtomicBool, Ordering};
+use tauri_plugin_global_shortcut::ShortcutState;
 use crate::constants::WINDOW_LABEL_MAIN;
 
-const PREF_FILE: &str = "copy_to_clipboard";
-
 /// Manages the application's global state.
 pub struct AppState {
     /// Information about the UI element currently being hovered over.
     pub current_info: Mutex<Option<accessibility::UIElementInfo>>,
     /// Whether capture mode (overlay enabled) is currently active.
     pub is_snip_active: AtomicBool,
-    /// Whether to also copy the capture to the clipboard.
-    pub copy_to_clipboard: AtomicBool,
-}
-
-fn load_pref(app: &tauri::AppHandle) -> bool {
-    app.path().app_config_dir()
-        .ok()
-        .and_then(|dir| std::fs::read_to_string(dir.join(PREF_FILE)).ok())
-        .map(|s| s.trim() != "false")
-        .unwrap_or(true)
-}
-
-fn save_pref(app: &tauri::AppHandle, value: bool) {
-    if let Ok(dir) = app.path().app_config_dir() {
-        let _ = std::fs::create_dir_all(&dir);
-        let _ = std::fs::write(dir.join(PREF_FILE), value.to_string());
-    }
+    /// What the active capture session captures when an element is clicked.
+    pub capture_mode: Mutex<capture::CaptureMode>,
+    /// Which macOS permissions were granted when last checked.
+    pub permissions: Mutex<permissions::PermissionState>,
+    /// The user's preferences.
+    pub settings: settings::SettingsStore,
+    /// Whether each global shortcut could be registered.
+    pub shortcut_status: Mutex<Vec<shortcuts::ShortcutStatus>>,
+    /// Every capture saved so far, persisted in the app data directory.
+    pub history: Mutex<history::History>,
+    /// Which parts of a capture are masked before it is saved.
+    pub redaction: Mutex<redaction::RedactionConfig>,
+    /// An image grabbed by a delayed capture, waiting for the user to pick a file.
+    pub pending_capture: Mutex<Option<timed_capture::PendingCapture>>,
+    /// The snapshot inspected by the active capture session, if it is frozen.
+    pub frozen: Mutex<Option<frozen::FrozenFrame>>,
+    /// The element recording in progress or waiting to be saved.
+    pub recording: Mutex<recording::RecordingState>,
+    /// The local server external tools inspect through, when enabled.
+    pub server: server::InspectionServer,
+    /// Set to stop the interaction recording in progress, if any.
+    pub script_recorder: Mutex<Option<std::sync::Arc<AtomicBool>>>,
+    /// The window the active capture session is limited to, when started from the window picker.
+    pub inspect_window: Mutex<Option<u32>>,
+    /// The capture open in the annotation editor.
+    pub annotating: Mutex<Option<std::path::PathBuf>>,
 }
 
 /// Command to hide the main overlay window.
 #[tauri::command]
 fn hide_window(window: tauri::WebviewWindow, state: tauri::State<AppState>) {
-    state.is_snip_active.store(false, Ordering::Relaxed);
+    let was_active = state.is_snip_active.swap(false, Ordering::Relaxed);
+    let mode = state.capture_mode.lock().map(|m| *m).unwrap_or_default();
+    if was_active && mode == capture::CaptureMode::Pick {
+        rpc::notify(window.app_handle(), "pickCancelled", ());
+    }
+    if let Ok(mut frozen) = state.frozen.lock() {
+        *frozen = None;
+    }
     let _ = window.hide();
 }
 
 /// Starts capture mode: shows the overlay window and enables accessibility scanning.
-fn start_capture_session(app: &tauri::AppHandle) {
+///
+/// When freezing is enabled, element captures first snapshot the screen and
+/// the accessibility tree so that showing the overlay cannot change what is inspected.
+pub(crate) fn start_capture_session(app: &tauri::AppHandle, mode: capture::CaptureMode) {
+    start_scoped_capture_session(app, mode, None);
+}
+
+/// Starts a capture session that only inspects the window with window server number `window_id`, if given.
+pub(crate) fn start_scoped_capture_session(app: &tauri::AppHandle, mode: capture::CaptureMode, window_id: Option<u32>) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    if !permissions::ensure(app) {
+        return;
+    }
+    if let Ok(mut lock) = state.capture_mode.lock() {
+        *lock = mode;
+    }
+    if let Ok(mut lock) = state.inspect_window.lock() {
+        *lock = window_id;
+    }
+
+    if mode == capture::CaptureMode::Element && state.settings.read(|s| s.freeze_frame) {
+        let app = app.clone();
+        std::thread::spawn(move || {
+            let frame = frozen::FrozenFrame::capture()
+                .inspect_err(|e| log::warn!("Failed to freeze the screen, inspecting live: {}", e))
+                .ok();
+            if let Ok(mut lock) = app.state::<AppState>().frozen.lock() {
+                *lock = frame;
+            }
+            show_overlay(&app);
+        });
+    } else {
+        if let Ok(mut lock) = state.frozen.lock() {
+            *lock = None;
+        }
+        show_overlay(app);
+    }
+}
+
+fn show_overlay(app: &tauri::AppHandle) {
     if let Some(state) = app.try_state::<AppState>() {
         state.is_snip_active.store(true, Ordering::Relaxed);
     }
@@ -62,62 +135,112 @@ fn start_capture_session(app: &tauri::AppHandle) {
 pub fn run() {
     let builder = tauri::Builder::default();
 
+    // In stdio mode stdout belongs to JSON-RPC, so logs go to stderr instead.
+    let stdio_mode = stdio::is_requested();
+
     #[cfg(debug_assertions)]
-    let builder = builder.plugin(tauri_plugin_log::Builder::new().build());
+    let builder = builder.plugin({
+        use tauri_plugin_log::{Target, TargetKind};
+        let console = if stdio_mode { TargetKind::Stderr } else { TargetKind::Stdout };
+        tauri_plugin_log::Builder::new()
+            .targets([Target::new(console), Target::new(TargetKind::LogDir { file_name: None })])
+            .build()
+    });
 
     builder
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(
             tauri_plugin_global_shortcut::Builder::new()
-                .with_shortcut("CommandOrControl+Shift+X")
-                .expect("Failed to register capture shortcut")
-                .with_handler(|app, _shortcut, event| {
-                    if event.state == tauri_plugin_global_shortcut::ShortcutState::Pressed {
-                        start_capture_session(app);
+                .with_handler(|app, shortcut, event| {
+                    if event.state == ShortcutState::Pressed {
+                        shortcuts::handle(app, shortcut);
                     }
                 })
                 .build(),
         )
-        .setup(|app| {
-            let copy_enabled = load_pref(app.handle());
+        .setup(move |app| {
+            let settings = settings::Settings::load(&app.path().app_config_dir()?);
+            let server_enabled = settings.inspection_server;
+            let shortcuts = settings.shortcuts.clone();
+            let history = history::History::load(app.path().app_data_dir()?);
+            let redaction = redaction::RedactionConfig::load(&app.path().app_config_dir()?);
 
             app.manage(AppState {
                 current_info: Mutex::new(None),
                 is_snip_active: AtomicBool::new(false),
-                copy_to_clipboard: AtomicBool::new(copy_enabled),
+                capture_mode: Mutex::new(capture::CaptureMode::default()),
+                permissions: Mutex::new(permissions::PermissionState::default()),
+                settings: settings::SettingsStore::new(settings),
+                shortcut_status: Mutex::new(Vec::new()),
+                history: Mutex::new(history),
+                redaction: Mutex::new(redaction),
+                pending_capture: Mutex::new(None),
+                frozen: Mutex::new(None),
+                recording: Mutex::new(recording::RecordingState::default()),
+                server: server::InspectionServer::default(),
+                script_recorder: Mutex::new(None),
+                inspect_window: Mutex::new(None),
+                annotating: Mutex::new(None),
             });
 
-            let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-            let snip_i = MenuItem::with_id(app, "snip", "Snip Screen", true, None::<&str>)?;
-            let copy_i = CheckMenuItem::with_id(app, "copy_to_clipboard", "Copy to Clipboard", true, copy_enabled, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&snip_i, &copy_i, &quit_i])?;
-
-            let _tray = TrayIconBuilder::new()
-                .menu(&menu)
-                .icon(app.default_window_icon().unwrap().clone())
-                .on_menu_event(|app: &tauri::AppHandle, event| {
-                    match event.id.as_ref() {
-                        "quit" => app.exit(0),
-                        "snip" => start_capture_session(app),
-                        "copy_to_clipboard" => {
-                            if let Some(state) = app.try_state::<AppState>() {
-                                let new_val = !state.copy_to_clipboard.load(Ordering::Relaxed);
-                                state.copy_to_clipboard.store(new_val, Ordering::Relaxed);
-                                save_pref(app, new_val);
-                            }
-                        }
-                        _ => {}
-                    }
-                })
-                .build(app)?;
+            if server_enabled {
+                if let Err(e) = server::start(app.handle()) {
+                    log::error!("Failed to start the inspection server: {}", e);
+                }
+            }
+
+            tray::create(app.handle())?;
+            shortcuts::register(app.handle(), &shortcuts);
+            permissions::check_on_startup(app.handle());
 
             polling::spawn_polling_thread(app.handle().clone());
 
+            if stdio_mode {
+                stdio::spawn(app.handle());
+            }
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             capture::capture_rect_to_file,
+            capture::capture_scroll_area_to_file,
+            capture::recapture_last_element,
+            history::list_history,
+            history::search_history,
+            history::reopen_history_entry,
+            history::copy_history_entry,
+            history::delete_history_entry,
+            annotation::load_annotation_document,
+            annotation::render_annotation_preview,
+            annotation::flatten_annotations,
+            redaction::get_redaction_config,
+            redaction::set_redaction_config,
+            timed_capture::save_pending_capture,
+            timed_capture::discard_pending_capture,
+            recording::save_recording,
+            recording::discard_recording,
+            baseline::set_history_baseline,
+            baseline::compare_history_entry,
+            baseline::compare_images,
+            manifest::run_screenshot_manifest,
+            actions::list_element_actions,
+            actions::perform_element_action,
+            actions::set_element_attribute,
+            script::replay_interaction_script,
+            script::export_interaction_script,
+            window_picker::list_app_windows,
+            window_picker::get_window_element,
+            window_picker::export_window_tree,
+            window_picker::inspect_window,
+            settings::get_settings,
+            settings::set_settings,
+            shortcuts::get_shortcut_status,
+            shortcuts::set_shortcut,
+            element_format::format_element_info,
+            element_format::copy_element_info,
+            permissions::get_permission_state,
+            permissions::open_permission_settings,
             hide_window
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,28 @@
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager, State};
+use crate::constants::EVENT_MANIFEST_FINISHED;
+use crate::AppState;
+
+pub use xray_core::manifest::*;
+
+/// Captures every shot of a screenshot manifest and reports which failed.
+#[tauri::command(async)]
+pub fn run_screenshot_manifest(path: String, state: State<AppState>) -> Result<ManifestReport, String> {
+    let redaction = state.redaction.lock().map(|c| c.clone()).unwrap_or_default();
+    run(Path::new(&path), &redaction)
+}
+
+/// Runs a manifest on a background thread, sending the report to the frontend when done.
+pub fn run_in_background(app: &AppHandle, path: PathBuf) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let redaction = app.state::<AppState>().redaction.lock().map(|c| c.clone()).unwrap_or_default();
+        match run(&path, &redaction) {
+            Ok(report) => {
+                log::info!("Manifest {}: {} captured, {} failed", path.display(), report.captured, report.failed);
+                let _ = app.emit(EVENT_MANIFEST_FINISHED, report);
+            }
+            Err(e) => log::error!("Failed to run manifest {}: {}", path.display(), e),
+        }
+    });
+}
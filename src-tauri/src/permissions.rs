@@ -0,0 +1,122 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tauri_plugin_opener::OpenerExt;
+use crate::constants::{EVENT_PERMISSIONS_CHANGED, PERMISSION_POLL_SECS};
+use crate::{tray, AppState};
+
+pub use xray_core::permissions::*;
+
+/// Whether a thread is already waiting for the missing permissions to be granted.
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+/// Re-checks the permissions, announcing the new state and updating the tray if it changed.
+pub fn refresh(app: &AppHandle) -> PermissionState {
+    let current = PermissionState::check();
+    let previous = app
+        .state::<AppState>()
+        .permissions
+        .lock()
+        .map(|mut state| std::mem::replace(&mut *state, current))
+        .ok();
+    if previous != Some(current) {
+        let _ = app.emit(EVENT_PERMISSIONS_CHANGED, current);
+        tray::refresh_menu(app);
+    }
+    current
+}
+
+/// Checks the permissions at launch, guiding the user through any that are missing.
+pub fn check_on_startup(app: &AppHandle) {
+    let state = refresh(app);
+    if !state.missing().is_empty() {
+        log::warn!("Missing permissions: {:?}", state.missing());
+        guide(app, &state.missing());
+    }
+}
+
+/// Returns whether a session can start; otherwise shows the guide.
+///
+/// Without Accessibility nothing would be highlighted, and without Screen
+/// Recording captures would only show the wallpaper.
+pub fn ensure(app: &AppHandle) -> bool {
+    let missing = refresh(app).missing();
+    if !missing.is_empty() {
+        guide(app, &missing);
+    }
+    missing.is_empty()
+}
+
+/// Explains what the missing permissions are for and offers to open System Settings.
+fn guide(app: &AppHandle, missing: &[Permission]) {
+    let Some(&first) = missing.first() else {
+        return;
+    };
+    watch(app);
+    let names: Vec<&str> = missing.iter().map(|p| p.label()).collect();
+    let mut message = format!("xray needs the {} permission to inspect and capture other apps.", names.join(" and "));
+    if missing.contains(&Permission::Accessibility) {
+        message.push_str("\n\nWithout Accessibility, no elements are highlighted.");
+    }
+    if missing.contains(&Permission::ScreenRecording) {
+        message.push_str("\n\nWithout Screen Recording, captures only show the desktop. macOS may ask you to quit and reopen xray after granting it.");
+    }
+    message.push_str("\n\nTurn on xray in System Settings › Privacy & Security, and it is picked up automatically.");
+
+    let handle = app.clone();
+    app.dialog()
+        .message(message)
+        .title("Permissions Required")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom("Open System Settings".to_string(), "Later".to_string()))
+        .show(move |open| {
+            if open {
+                if let Err(e) = open_settings(&handle, first) {
+                    log::error!("Failed to open System Settings: {}", e);
+                }
+            }
+        });
+}
+
+/// Registers xray for `permission` and opens the System Settings pane that grants it.
+pub fn open_settings(app: &AppHandle, permission: Permission) -> Result<(), String> {
+    // Requesting adds xray to the pane's list, which it is missing from until it first asks.
+    if permission.request() {
+        refresh(app);
+        return Ok(());
+    }
+    watch(app);
+    app.opener().open_url(permission.settings_url(), None::<&str>).map_err(|e| e.to_string())
+}
+
+/// Re-checks the permissions in the background until all are granted.
+fn watch(app: &AppHandle) {
+    if WATCHING.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || {
+        loop {
+            thread::sleep(Duration::from_secs(PERMISSION_POLL_SECS));
+            if refresh(&app).missing().is_empty() {
+                log::info!("All permissions granted");
+                break;
+            }
+        }
+        WATCHING.store(false, Ordering::Relaxed);
+    });
+}
+
+/// Returns which permissions xray currently has.
+#[tauri::command]
+pub fn get_permission_state(app: AppHandle) -> PermissionState {
+    refresh(&app)
+}
+
+/// Opens the System Settings pane where `permission` is granted.
+#[tauri::command]
+pub fn open_permission_settings(permission: Permission, app: AppHandle) -> Result<(), String> {
+    open_settings(&app, permission)
+}
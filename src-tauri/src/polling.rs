@@ -2,14 +2,25 @@ use std::thread;
 use std::time::Duration;
 use std::sync::atomic::Ordering;
 use tauri::{Manager, Emitter, PhysicalPosition, PhysicalSize, AppHandle, Monitor};
-use crate::{accessibility, AppState};
-use crate::constants::{EVENT_CAPTURE_CLICK, EVENT_ELEMENT_HOVER, POLLING_INTERVAL_MS, WINDOW_HIDE_DELAY_MS, WINDOW_LABEL_MAIN};
+use crate::capture::{self, CaptureClick, CaptureMode, ScreenRect};
+use crate::frozen::FrozenFrame;
+use crate::{accessibility, element_format, recording, rpc, server, timed_capture, AppState};
+use crate::constants::{
+    EVENT_CAPTURE_CLICK, EVENT_ELEMENT_HOVER, EVENT_FROZEN_FRAME, POLLING_INTERVAL_MS, WINDOW_HIDE_DELAY_MS,
+    WINDOW_LABEL_MAIN,
+};
 
 /// Spawns the background thread that handles mouse polling and screen capture logic.
 pub fn spawn_polling_thread(handle: AppHandle) {
     thread::spawn(move || {
         let mut current_monitor_pos: Option<(i32, i32)> = None;
+        // The frozen frame id and monitor whose image the overlay currently shows.
+        let mut shown_frozen_frame: Option<(u64, (i32, i32))> = None;
         let mut was_mouse_down = false;
+        // The drill-down policy and app filter, refreshed whenever the settings change.
+        let mut settings_revision = None;
+        let mut drill_down = accessibility::DrillDown::default();
+        let mut inspect_apps = Vec::new();
 
         loop {
             // Sleep to maintain approx. 60 FPS polling rate
@@ -23,6 +34,12 @@ pub fn spawn_polling_thread(handle: AppHandle) {
                 continue;
             }
 
+            let revision = state.settings.revision();
+            if settings_revision != Some(revision) {
+                (drill_down, inspect_apps) = state.settings.read(|s| (s.drill_down, s.inspect_apps.clone()));
+                settings_revision = Some(revision);
+            }
+
             let is_mouse_down = accessibility::is_mouse_left_down();
 
             // Detect Mouse Click (Trigger Capture)
@@ -37,7 +54,7 @@ pub fn spawn_polling_thread(handle: AppHandle) {
             }
 
             // Process Hover Logic (Scan UI elements and move overlay)
-            process_hover_logic(&handle, &state, &mut current_monitor_pos);
+            process_hover_logic(&handle, &state, drill_down, &inspect_apps, &mut current_monitor_pos, &mut shown_frozen_frame);
         }
     });
 }
@@ -46,12 +63,26 @@ pub fn spawn_polling_thread(handle: AppHandle) {
 fn handle_click_capture(handle: &AppHandle, state: &tauri::State<AppState>) {
     // 1. Disable capture mode
     state.is_snip_active.store(false, Ordering::Relaxed);
+    let frozen = state.frozen.lock().ok().and_then(|mut f| f.take());
     
     // 2. Hide the overlay window
     if let Some(win) = handle.get_webview_window(WINDOW_LABEL_MAIN) {
         let _ = win.hide();
     }
 
+    // Option-click copies the element as text instead of capturing it.
+    let mode = state.capture_mode.lock().map(|m| *m).unwrap_or_default();
+    if mode == CaptureMode::Element && accessibility::is_option_key_down() {
+        copy_clicked(state, frozen.as_ref());
+        return;
+    }
+
+    // Frozen sessions crop from the snapshot, so there is nothing to wait for.
+    if let Some(frame) = frozen {
+        capture_frozen(handle, state, &frame);
+        return;
+    }
+
     // 3. Wait for the window to disappear animation to finish
     thread::sleep(Duration::from_millis(WINDOW_HIDE_DELAY_MS));
 
@@ -61,25 +92,108 @@ fn handle_click_capture(handle: &AppHandle, state: &tauri::State<AppState>) {
         .ok()
         .flatten();
 
-    // 5. Emit capture-click event to frontend with element info.
-    //    The frontend will show the save dialog and invoke capture commands.
-    if let Some(info) = rect_to_capture {
-        let _ = handle.emit(EVENT_CAPTURE_CLICK, info);
+    // 5. Attach a locator so the element can be found again for re-captures.
+    let rect_to_capture = rect_to_capture.map(|info| attach_locator(state, info));
+
+    // 6. Emit capture-click event to frontend with element info and the session's mode.
+    //    The frontend will show the save dialog and invoke the matching capture command.
+    //    Delayed captures and recordings emit their own events once they have finished.
+    if let Some(element) = rect_to_capture {
+        match mode {
+            CaptureMode::Delayed => timed_capture::capture_after_delay(handle, Some(element)),
+            CaptureMode::Record => recording::start(handle, element),
+            CaptureMode::Pick => rpc::notify(handle, "elementPicked", &element),
+            _ => {
+                let _ = handle.emit(EVENT_CAPTURE_CLICK, CaptureClick { element, mode, pending: false });
+            }
+        }
+    }
+}
+
+/// Adds the locator of the element under the cursor to the hovered `info`.
+/// It is only trusted if the fresh hit test lands on the same rect as the hover.
+fn attach_locator(state: &tauri::State<AppState>, mut info: accessibility::UIElementInfo) -> accessibility::UIElementInfo {
+    let drill_down = state.settings.read(|s| s.drill_down);
+    if let Some(located) = accessibility::get_element_at_mouse_with(drill_down, true) {
+        if located.global_x == info.global_x && located.global_y == info.global_y
+            && located.width == info.width && located.height == info.height
+        {
+            info.locator = located.locator;
+        }
+    }
+    info
+}
+
+/// Copies the clicked element as text in the format chosen in the settings.
+fn copy_clicked(state: &tauri::State<AppState>, frozen: Option<&FrozenFrame>) {
+    let element = match frozen {
+        Some(frame) => accessibility::mouse_location().and_then(|p| frame.located_element_at(p.x, p.y)),
+        None => state.current_info.lock().ok().and_then(|lock| lock.clone()).map(|info| attach_locator(state, info)),
+    };
+    let Some(element) = element else {
+        return;
+    };
+    let format = state.settings.read(|s| s.copy_format);
+    if let Err(e) = element_format::copy(&element, format) {
+        log::error!("Failed to copy {} as {}: {}", element.role, format.label(), e);
+    }
+}
+
+/// Crops the element under the cursor from the frozen `frame` and offers it for saving.
+fn capture_frozen(handle: &AppHandle, state: &tauri::State<AppState>, frame: &FrozenFrame) {
+    let element = accessibility::mouse_location()
+        .and_then(|p| frame.located_element_at(p.x, p.y))
+        .or_else(|| state.current_info.lock().ok().and_then(|lock| lock.clone()));
+    let Some(element) = element else {
+        return;
+    };
+
+    let rect = ScreenRect::of_element(&element);
+    match frame.crop(&rect) {
+        Ok(mut image) => match capture::apply_redaction(state, &mut image, &rect, &element) {
+            Ok(()) => timed_capture::offer_pending(handle, state, element, image, CaptureMode::Element),
+            Err(e) => capture::report_error(handle, "Frozen capture failed", &e),
+        },
+        Err(e) => log::error!("Frozen capture failed: {}", e),
     }
 }
 
 /// Scans the UI element under the mouse and updates the overlay window position.
+///
+/// During a frozen session the element is looked up in the snapshot, and the
+/// overlay is sent the frozen image of the monitor it is on.
 fn process_hover_logic(
     handle: &AppHandle, 
     state: &tauri::State<AppState>, 
-    current_monitor_pos: &mut Option<(i32, i32)>
+    drill_down: accessibility::DrillDown,
+    inspect_apps: &[String],
+    current_monitor_pos: &mut Option<(i32, i32)>,
+    shown_frozen_frame: &mut Option<(u64, (i32, i32))>,
 ) {
-    if let Some(mut info) = accessibility::get_element_at_mouse() {
+    // The frozen frame is only locked for the hit test: `hide_window` locks it on the
+    // main thread, which the window and monitor getters below wait for.
+    let frozen_hit = state.frozen.lock().ok().and_then(|frozen| {
+        frozen.as_ref().map(|frame| (frame.id(), accessibility::mouse_location().and_then(|p| frame.element_at(p.x, p.y))))
+    });
+    let (frozen_id, element) = match frozen_hit {
+        Some((id, element)) => (Some(id), element),
+        None => (None, accessibility::get_element_at_mouse_with(drill_down, false)),
+    };
+    // Elements of apps outside the filter, or outside the window picked to inspect, are not highlighted.
+    let scope = state.inspect_window.lock().ok().and_then(|w| *w);
+    let element = element.filter(|info| {
+        (inspect_apps.is_empty()
+            || info.app().is_some_and(|app| inspect_apps.iter().any(|name| app.matches(name))))
+            && scope.is_none_or(|window_id| info.window_id == window_id)
+    });
+
+    if let Some(mut info) = element {
         
         // Find which monitor the element is on and move the overlay window there
         if let Ok(monitors) = handle.available_monitors() {
             if let Some(target_monitor) = find_monitor_for_element(&monitors, &info) {
                 update_overlay_window(handle, &target_monitor, current_monitor_pos, &mut info);
+                show_frozen_frame(handle, state, frozen_id, &target_monitor, shown_frozen_frame);
             }
         }
 
@@ -88,11 +202,36 @@ fn process_hover_logic(
             *lock = Some(info.clone());
         }
         
-        // Notify frontend
+        // Notify frontend and inspection clients
+        server::broadcast_hover(handle, &info);
         let _ = handle.emit(EVENT_ELEMENT_HOVER, info);
     }
 }
 
+/// Sends the overlay the image of `monitor` from the frozen frame `frozen_id`, or clears it when the session is live.
+fn show_frozen_frame(
+    handle: &AppHandle,
+    state: &tauri::State<AppState>,
+    frozen_id: Option<u64>,
+    monitor: &Monitor,
+    shown_frozen_frame: &mut Option<(u64, (i32, i32))>,
+) {
+    let pos = monitor.position();
+    let wanted = frozen_id.map(|id| (id, (pos.x, pos.y)));
+    if wanted == *shown_frozen_frame {
+        return;
+    }
+
+    let scale_factor = monitor.scale_factor();
+    let url = frozen_id.and_then(|id| {
+        let frozen = state.frozen.lock().ok()?;
+        let frame = frozen.as_ref().filter(|f| f.id() == id)?;
+        frame.display_data_url(pos.x as f64 / scale_factor, pos.y as f64 / scale_factor).map(str::to_string)
+    });
+    let _ = handle.emit(EVENT_FROZEN_FRAME, url);
+    *shown_frozen_frame = wanted;
+}
+
 /// Finds the monitor that contains the given UI element.
 fn find_monitor_for_element(monitors: &[Monitor], info: &accessibility::UIElementInfo) -> Option<Monitor> {
     for m in monitors {
@@ -0,0 +1,353 @@
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{imageops, Delay, Frame, RgbaImage};
+use tauri::{AppHandle, Emitter, Manager, State};
+use crate::accessibility::UIElementInfo;
+use crate::capture::{self, CaptureMode, ScreenRect};
+use crate::constants::{
+    EVENT_RECORDING_FINISHED, FFMPEG_PATHS, RECORDING_MAX_BYTES, RECORDING_MAX_SECS, RECORDING_REDACTION_REFRESH_MS, TRAY_ID,
+};
+use crate::{redaction, start_capture_session, tray, AppState};
+
+/// Frames recorded from one element.
+pub struct Recording {
+    pub element: UIElementInfo,
+    pub frames: Vec<RgbaImage>,
+    pub fps: u32,
+}
+
+/// Where the recorder is at; kept in [`AppState`].
+#[derive(Default)]
+pub enum RecordingState {
+    #[default]
+    Idle,
+    /// Frames are being grabbed until the flag is set.
+    Recording { stop: Arc<AtomicBool> },
+    /// Recording has stopped and the frames wait for the user to pick a file.
+    Finished(Box<Recording>),
+}
+
+/// Summary sent to the frontend when a recording stops.
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordingFinished {
+    frame_count: usize,
+    fps: u32,
+    /// The app the recorded element belongs to, for naming the file.
+    app_name: Option<String>,
+}
+
+/// Starts picking an element to record, or stops the recording in progress.
+pub fn toggle(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let stop = match state.recording.lock() {
+        Ok(recording) => match &*recording {
+            RecordingState::Recording { stop } => Some(stop.clone()),
+            _ => None,
+        },
+        Err(_) => return,
+    };
+    match stop {
+        Some(stop) => stop.store(true, Ordering::Relaxed),
+        None => start_capture_session(app, CaptureMode::Record),
+    }
+}
+
+/// Returns whether frames are currently being recorded.
+pub fn is_recording(state: &AppState) -> bool {
+    state.recording.lock().is_ok_and(|r| matches!(*r, RecordingState::Recording { .. }))
+}
+
+/// Records `element` at the configured frame rate on a background thread.
+///
+/// Recording runs until [`toggle`] is called again, [`RECORDING_MAX_SECS`]
+/// have passed or the frames take [`RECORDING_MAX_BYTES`]. The frames are then kept in the app state and the frontend is
+/// asked where to save them.
+pub fn start(app: &AppHandle, element: UIElementInfo) {
+    let state = app.state::<AppState>();
+    let stop = Arc::new(AtomicBool::new(false));
+    match state.recording.lock() {
+        Ok(mut recording) if !matches!(*recording, RecordingState::Recording { .. }) => {
+            *recording = RecordingState::Recording { stop: stop.clone() };
+        }
+        _ => return,
+    }
+
+    let fps = state.settings.read(|s| s.recording_fps).max(1);
+    let app = app.clone();
+    thread::spawn(move || {
+        set_tray_title(&app, Some("● REC"));
+        tray::refresh_menu(&app);
+
+        let state = app.state::<AppState>();
+        let frames = record_frames(&state, &element, fps, &stop);
+
+        set_tray_title(&app, None);
+        let count = frames.len();
+        let app_name = element.app_name.clone();
+        if let Ok(mut recording) = state.recording.lock() {
+            *recording = if frames.is_empty() {
+                RecordingState::Idle
+            } else {
+                RecordingState::Finished(Box::new(Recording { element, frames, fps }))
+            };
+        }
+        tray::refresh_menu(&app);
+
+        if count > 0 {
+            let _ = app.emit(EVENT_RECORDING_FINISHED, RecordingFinished { frame_count: count, fps, app_name });
+        }
+    });
+}
+
+fn record_frames(state: &AppState, element: &UIElementInfo, fps: u32, stop: &AtomicBool) -> Vec<RgbaImage> {
+    let interval = Duration::from_secs_f64(1.0 / fps as f64);
+    let started = Instant::now();
+    let redaction = state.redaction.lock().map(|c| c.clone()).unwrap_or_default();
+    // Walking the tree for every frame would cost the frame rate, so sensitive fields are
+    // looked up again only periodically or when the element moves.
+    let mut redacted: Option<(ScreenRect, Instant, Vec<ScreenRect>)> = None;
+    let mut frames: Vec<RgbaImage> = Vec::new();
+    let mut bytes = 0;
+
+    while !stop.load(Ordering::Relaxed) && started.elapsed() < Duration::from_secs(RECORDING_MAX_SECS) {
+        let tick = Instant::now();
+        match capture::grab_element(element) {
+            Ok((mut image, frame)) => {
+                if redaction.enabled {
+                    let stale = redacted.as_ref().is_none_or(|(at, found, _)| {
+                        *at != frame || found.elapsed() >= Duration::from_millis(RECORDING_REDACTION_REFRESH_MS)
+                    });
+                    if stale {
+                        redacted = match redaction::find_redacted_rects(&frame, element, &redaction) {
+                            Ok(rects) => Some((frame, Instant::now(), rects)),
+                            Err(e) => {
+                                log::warn!("Dropped recording frame that could not be redacted: {}", e);
+                                None
+                            }
+                        };
+                    }
+                    // Frames are dropped rather than recorded with sensitive fields showing.
+                    match &redacted {
+                        Some((_, _, rects)) => redaction::redact_rects(&mut image, &frame, rects, &redaction),
+                        None => {
+                            thread::sleep(interval.saturating_sub(tick.elapsed()));
+                            continue;
+                        }
+                    }
+                }
+                // Moving to a display with another scale changes the pixel size; keep every frame like the first.
+                if let Some(first) = frames.first() {
+                    if image.dimensions() != first.dimensions() {
+                        image = imageops::resize(&image, first.width(), first.height(), imageops::FilterType::Triangle);
+                    }
+                }
+                bytes += image.as_raw().len();
+                if bytes > RECORDING_MAX_BYTES {
+                    log::warn!("Stopped recording after {} frames, the memory limit was reached", frames.len());
+                    break;
+                }
+                frames.push(image);
+            }
+            Err(e) => log::warn!("Dropped recording frame: {}", e),
+        }
+        thread::sleep(interval.saturating_sub(tick.elapsed()));
+    }
+    frames
+}
+
+pub(crate) fn set_tray_title(app: &AppHandle, title: Option<&str>) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_title(title);
+    }
+}
+
+/// Encodes the finished recording to `path`; the extension picks GIF, APNG (`.png`) or MP4.
+///
+/// Runs off the main thread, since encoding hundreds of frames takes a while.
+#[tauri::command(async)]
+pub fn save_recording(path: String, state: State<AppState>) -> Result<(), String> {
+    let recording = {
+        let mut lock = state.recording.lock().map_err(|e| e.to_string())?;
+        match std::mem::take(&mut *lock) {
+            RecordingState::Finished(recording) => recording,
+            other => {
+                *lock = other;
+                return Err("There is no finished recording".to_string());
+            }
+        }
+    };
+
+    // The frames are kept on failure so the user can try again, e.g. as a GIF when ffmpeg is missing.
+    let result = encode(&recording.frames, recording.fps, &path);
+    if result.is_err() {
+        if let Ok(mut lock) = state.recording.lock() {
+            if matches!(*lock, RecordingState::Idle) {
+                *lock = RecordingState::Finished(recording);
+            }
+        }
+    }
+    result
+}
+
+/// Encodes `frames` to `path`; the extension picks GIF, APNG (`.png`) or MP4.
+fn encode(frames: &[RgbaImage], fps: u32, path: &str) -> Result<(), String> {
+    if frames.is_empty() {
+        return Err("The recording has no frames".to_string());
+    }
+    let ext = Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+    match ext.as_str() {
+        "gif" => encode_gif(frames, fps, path),
+        "png" | "apng" => encode_apng(frames, fps, path),
+        "mp4" => encode_mp4(frames, fps, path),
+        _ => Err(format!("Unsupported recording format: .{}", ext)),
+    }
+}
+
+/// Drops the finished recording, e.g. when the user cancels the save dialog.
+#[tauri::command]
+pub fn discard_recording(state: State<AppState>) {
+    if let Ok(mut recording) = state.recording.lock() {
+        if matches!(*recording, RecordingState::Finished(_)) {
+            *recording = RecordingState::Idle;
+        }
+    }
+}
+
+fn encode_gif(frames: &[RgbaImage], fps: u32, path: &str) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder = GifEncoder::new_with_speed(BufWriter::new(file), 10);
+    encoder.set_repeat(Repeat::Infinite).map_err(|e| e.to_string())?;
+
+    let delay = Delay::from_numer_denom_ms(1000, fps);
+    let frames = frames.iter().map(|f| Frame::from_parts(f.clone(), 0, 0, delay));
+    encoder.encode_frames(frames).map_err(|e| e.to_string())
+}
+
+fn encode_apng(frames: &[RgbaImage], fps: u32, path: &str) -> Result<(), String> {
+    let (width, height) = frames[0].dimensions();
+    let file = File::create(path).map_err(|e| e.to_string())?;
+
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, 0).map_err(|e| e.to_string())?;
+    encoder.set_frame_delay(1, fps as u16).map_err(|e| e.to_string())?;
+
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    for frame in frames {
+        writer.write_image_data(frame.as_raw()).map_err(|e| e.to_string())?;
+    }
+    writer.finish().map_err(|e| e.to_string())
+}
+
+/// Pipes the raw frames through a local ffmpeg into an H.264 MP4.
+///
+/// ffmpeg's error output is read on its own thread so it can't fill its pipe
+/// and stall while frames are still being written, and is reported on failure.
+fn encode_mp4(frames: &[RgbaImage], fps: u32, path: &str) -> Result<(), String> {
+    let ffmpeg = FFMPEG_PATHS
+        .iter()
+        .find(|p| Path::new(p).exists())
+        .copied()
+        .unwrap_or("ffmpeg");
+    let (width, height) = frames[0].dimensions();
+
+    let mut child = Command::new(ffmpeg)
+        .args(["-y", "-loglevel", "error", "-f", "rawvideo", "-pix_fmt", "rgba"])
+        .args(["-s", &format!("{}x{}", width, height), "-r", &fps.to_string(), "-i", "-"])
+        // H.264 needs even dimensions.
+        .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2", "-c:v", "libx264", "-pix_fmt", "yuv420p", path])
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("MP4 export needs ffmpeg, which could not be started: {}", e))?;
+
+    let stderr = child.stderr.take();
+    let errors = thread::spawn(move || {
+        let mut text = String::new();
+        if let Some(mut stderr) = stderr {
+            let _ = stderr.read_to_string(&mut text);
+        }
+        text
+    });
+
+    // Dropping stdin at the end of the match closes the pipe, so ffmpeg finishes.
+    let written = match child.stdin.take() {
+        Some(mut stdin) => frames.iter().try_for_each(|frame| stdin.write_all(frame.as_raw())),
+        None => Ok(()),
+    };
+    let status = child.wait().map_err(|e| e.to_string())?;
+    let errors = errors.join().unwrap_or_default();
+
+    if !status.success() {
+        return Err(format!("ffmpeg failed: {}", errors.trim()));
+    }
+    written.map_err(|e| format!("Failed to send frames to ffmpeg: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::gif::GifDecoder;
+    use image::{AnimationDecoder, Rgba};
+
+    fn frames() -> Vec<RgbaImage> {
+        [Rgba([255, 0, 0, 255]), Rgba([0, 255, 0, 255]), Rgba([0, 0, 255, 255])]
+            .into_iter()
+            .map(|color| RgbaImage::from_pixel(6, 4, color))
+            .collect()
+    }
+
+    fn temp_path(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("xray-recording-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn gif_round_trip() {
+        let path = temp_path("round-trip.gif");
+        encode(&frames(), 10, &path).unwrap();
+
+        let decoder = GifDecoder::new(std::io::BufReader::new(File::open(&path).unwrap())).unwrap();
+        let decoded = decoder.into_frames().collect_frames().unwrap();
+        assert_eq!(decoded.len(), 3);
+        for (frame, expected) in decoded.iter().zip(frames()) {
+            assert_eq!(frame.buffer().dimensions(), (6, 4));
+            assert_eq!(frame.buffer().get_pixel(3, 2), expected.get_pixel(3, 2));
+            assert_eq!(frame.delay().numer_denom_ms(), (100, 1));
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn apng_round_trip() {
+        let path = temp_path("round-trip.png");
+        encode(&frames(), 10, &path).unwrap();
+
+        let mut reader = png::Decoder::new(File::open(&path).unwrap()).read_info().unwrap();
+        let control = reader.info().animation_control.unwrap();
+        assert_eq!(control.num_frames, 3);
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        for expected in frames() {
+            let info = reader.next_frame(&mut buffer).unwrap();
+            assert_eq!((info.width, info.height), (6, 4));
+            assert_eq!(&buffer[..info.buffer_size()], expected.as_raw().as_slice());
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn unsupported_or_empty_recordings_fail() {
+        assert!(encode(&frames(), 10, &temp_path("clip.mov")).is_err());
+        assert!(encode(&[], 10, &temp_path("clip.gif")).is_err());
+    }
+}
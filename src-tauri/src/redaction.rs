@@ -0,0 +1,19 @@
+use tauri::{AppHandle, Manager, State};
+use crate::{tray, AppState};
+
+pub use xray_core::redaction::*;
+
+/// Returns the current redaction config.
+#[tauri::command]
+pub fn get_redaction_config(state: State<AppState>) -> Result<RedactionConfig, String> {
+    state.redaction.lock().map(|c| c.clone()).map_err(|e| e.to_string())
+}
+
+/// Replaces and persists the redaction config.
+#[tauri::command]
+pub fn set_redaction_config(config: RedactionConfig, app: AppHandle, state: State<AppState>) -> Result<(), String> {
+    config.save(&app.path().app_config_dir().map_err(|e| e.to_string())?)?;
+    *state.redaction.lock().map_err(|e| e.to_string())? = config;
+    tray::refresh_menu(&app);
+    Ok(())
+}
@@ -0,0 +1,216 @@
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+use crate::accessibility::{self, UIElementInfo};
+use crate::actions::{self, AttributeValue};
+use crate::capture::{CaptureError, CaptureMode};
+use crate::constants::ACCESSIBILITY_RECURSION_LIMIT;
+use crate::selector::Selector;
+use crate::{capture, server, start_capture_session, stdio, tree, window_list, AppState};
+
+/// JSON-RPC error code for a message that is not valid JSON.
+pub const PARSE_ERROR: i32 = -32700;
+/// JSON-RPC error code for JSON that is not a request.
+pub const INVALID_REQUEST: i32 = -32600;
+/// JSON-RPC error code for an unknown method.
+pub const METHOD_NOT_FOUND: i32 = -32601;
+/// JSON-RPC error code for parameters that don't fit the method.
+pub const INVALID_PARAMS: i32 = -32602;
+/// JSON-RPC error code for a method that failed, e.g. because the element is gone.
+pub const SERVER_ERROR: i32 = -32000;
+
+/// Why a method call failed, with a JSON-RPC error code.
+#[derive(Debug, serde::Serialize)]
+pub struct RpcError {
+    pub code: i32,
+    pub message: String,
+}
+
+impl From<String> for RpcError {
+    fn from(message: String) -> Self {
+        Self { code: SERVER_ERROR, message }
+    }
+}
+
+impl From<CaptureError> for RpcError {
+    fn from(error: CaptureError) -> Self {
+        error.to_string().into()
+    }
+}
+
+impl From<&str> for RpcError {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
+
+/// Picks an app by process id or by name.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AppTarget {
+    pid: Option<i32>,
+    app: Option<String>,
+}
+
+impl AppTarget {
+    fn pid(&self) -> Result<i32, String> {
+        match (self.pid, &self.app) {
+            (Some(pid), _) => Ok(pid),
+            (None, Some(name)) => window_list::find_app_pid(name).ok_or_else(|| format!("{} has no window on screen", name)),
+            (None, None) => Err("Expected `pid` or `app`".to_string()),
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct PointParams {
+    x: f64,
+    y: f64,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TreeParams {
+    #[serde(flatten)]
+    target: AppTarget,
+    window_id: Option<u32>,
+    depth: Option<usize>,
+}
+
+#[derive(serde::Deserialize)]
+struct QueryParams {
+    #[serde(flatten)]
+    target: AppTarget,
+    selector: String,
+}
+
+/// The element a method acts on: given directly, found by selector in an app, or hit-tested at a point.
+#[derive(serde::Deserialize)]
+struct ElementTarget {
+    element: Option<UIElementInfo>,
+    selector: Option<String>,
+    #[serde(flatten)]
+    app: AppTarget,
+    x: Option<f64>,
+    y: Option<f64>,
+}
+
+impl ElementTarget {
+    fn resolve(&self) -> Result<UIElementInfo, RpcError> {
+        if let Some(element) = &self.element {
+            return Ok(element.clone());
+        }
+        if let Some(selector) = &self.selector {
+            let selector: Selector = selector.parse().map_err(invalid_params)?;
+            return Ok(selector.find_in_app(self.app.pid()?).ok_or("Selector matched nothing")?);
+        }
+        match (self.x, self.y) {
+            (Some(x), Some(y)) => Ok(accessibility::get_element_at(x, y, true).ok_or_else(|| format!("No element at {},{}", x, y))?),
+            _ => Err(invalid_params("Expected `element`, `selector` or `x` and `y`".to_string())),
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct CaptureParams {
+    path: String,
+    #[serde(flatten)]
+    target: ElementTarget,
+}
+
+#[derive(serde::Deserialize)]
+struct ActionParams {
+    action: String,
+    #[serde(flatten)]
+    target: ElementTarget,
+}
+
+#[derive(serde::Deserialize)]
+struct AttributeParams {
+    attribute: String,
+    value: AttributeValue,
+    #[serde(flatten)]
+    target: ElementTarget,
+}
+
+/// Runs one inspection method for an external client and returns its result.
+///
+/// These are the methods shared by the inspection server and the stdio mode:
+/// `getElementAt`, `getTree`, `querySelector`, `capture`, `listWindows`,
+/// `getActions`, `performAction`, `setAttribute` and `pickElement`. Parameters
+/// and results use the same camelCase JSON as the frontend.
+///
+/// `pickElement` only opens the overlay; the element the user clicks arrives
+/// later as an `elementPicked` notification, or `pickCancelled` on Escape.
+pub fn call(app: &AppHandle, method: &str, params: Value) -> Result<Value, RpcError> {
+    match method {
+        "getElementAt" => {
+            let PointParams { x, y } = parse(params)?;
+            to_value(accessibility::get_element_at(x, y, true))
+        }
+        "getTree" => {
+            let params: TreeParams = parse(params)?;
+            let depth = params.depth.unwrap_or(ACCESSIBILITY_RECURSION_LIMIT as usize);
+            let root = match params.window_id {
+                Some(window_id) => tree::snapshot_window(window_id, depth),
+                None => tree::snapshot_app(params.target.pid()?, depth),
+            };
+            to_value(root.ok_or("The app or window cannot be inspected")?)
+        }
+        "querySelector" => {
+            let params: QueryParams = parse(params)?;
+            let selector: Selector = params.selector.parse().map_err(invalid_params)?;
+            to_value(selector.find_in_app(params.target.pid()?))
+        }
+        "capture" => {
+            let params: CaptureParams = parse(params)?;
+            let element = params.target.resolve()?;
+            let state = app.state::<AppState>();
+            capture::capture_element_to_file(app, &state, &element, &params.path)?;
+            Ok(json!({ "path": params.path, "element": element }))
+        }
+        "listWindows" => to_value(window_list::list_windows()),
+        "getActions" => {
+            let target: ElementTarget = parse(params)?;
+            to_value(actions::list_actions(&target.resolve()?)?)
+        }
+        "performAction" => {
+            let params: ActionParams = parse(params)?;
+            actions::perform_action(&params.target.resolve()?, &params.action)?;
+            Ok(Value::Null)
+        }
+        "setAttribute" => {
+            let params: AttributeParams = parse(params)?;
+            actions::set_attribute(&params.target.resolve()?, &params.attribute, &params.value)?;
+            Ok(Value::Null)
+        }
+        "pickElement" => {
+            start_capture_session(app, CaptureMode::Pick);
+            Ok(Value::Null)
+        }
+        _ => Err(RpcError { code: METHOD_NOT_FOUND, message: format!("Unknown method: {}", method) }),
+    }
+}
+
+/// Sends a notification to the clients of the inspection server and of the stdio mode.
+pub fn notify(app: &AppHandle, method: &str, params: impl serde::Serialize) {
+    let Ok(params) = serde_json::to_value(params) else {
+        return;
+    };
+    server::broadcast(app, method, &params);
+    stdio::notify(method, &params);
+}
+
+fn parse<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    // Methods without parameters may be called with `params` omitted.
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| invalid_params(e.to_string()))
+}
+
+fn invalid_params(message: String) -> RpcError {
+    RpcError { code: INVALID_PARAMS, message }
+}
+
+fn to_value(value: impl serde::Serialize) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|e| e.to_string().into())
+}
@@ -0,0 +1,180 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::DialogExt;
+use crate::accessibility::{self, UIElementInfo};
+use crate::constants::{ax_attributes, EVENT_SCRIPT_REPLAYED, POLLING_INTERVAL_MS};
+use crate::{permissions, recording, tray, window_list, AppState};
+
+pub use xray_core::export::*;
+pub use xray_core::script::*;
+
+/// A text field clicked during recording, whose value becomes a `type` step if it changes.
+struct PendingInput {
+    element: UIElementInfo,
+    step: ScriptStep,
+    initial: Option<String>,
+}
+
+/// Starts recording clicks and typing, or stops and asks where to save the script.
+pub fn toggle(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let Ok(running) = state.script_recorder.lock().map(|mut r| r.take()) else {
+        return;
+    };
+    if let Some(stop) = running {
+        stop.store(true, Ordering::Relaxed);
+        return;
+    }
+    // Checked without holding the lock, since a change refreshes the tray, which reads it.
+    if !permissions::ensure(app) {
+        return;
+    }
+    let stop = Arc::new(AtomicBool::new(false));
+    let Ok(mut recorder) = state.script_recorder.lock() else {
+        return;
+    };
+    *recorder = Some(stop.clone());
+    drop(recorder);
+
+    let app = app.clone();
+    thread::spawn(move || {
+        recording::set_tray_title(&app, Some("● REC"));
+        tray::refresh_menu(&app);
+        let script = record(&app, &stop);
+        recording::set_tray_title(&app, None);
+        tray::refresh_menu(&app);
+
+        match script {
+            Some(script) if !script.steps.is_empty() => offer_save(&app, script),
+            _ => log::info!("No interactions were recorded"),
+        }
+    });
+}
+
+/// Returns whether clicks and typing are being recorded.
+pub fn is_recording(state: &AppState) -> bool {
+    state.script_recorder.lock().is_ok_and(|r| r.is_some())
+}
+
+/// Polls the mouse until `stop` is set, turning each click into a step.
+///
+/// Clicks are only recorded in the app clicked first. Text typed into a
+/// clicked field is recorded as a `type` step with the field's final value
+/// once the next click happens; password fields are never read.
+fn record(app: &AppHandle, stop: &AtomicBool) -> Option<Script> {
+    let own_pid = std::process::id() as i32;
+    let mut script: Option<Script> = None;
+    let mut pending: Option<PendingInput> = None;
+    let mut was_mouse_down = false;
+
+    while !stop.load(Ordering::Relaxed) {
+        thread::sleep(Duration::from_millis(POLLING_INTERVAL_MS));
+        let is_mouse_down = accessibility::is_mouse_left_down();
+        let clicked = is_mouse_down && !was_mouse_down;
+        was_mouse_down = is_mouse_down;
+        if !clicked || app.state::<AppState>().is_snip_active.load(Ordering::Relaxed) {
+            continue;
+        }
+
+        if let (Some(script), Some(input)) = (script.as_mut(), pending.take()) {
+            record_input(script, input);
+        }
+        let Some(element) = accessibility::get_located_element_at_mouse() else {
+            continue;
+        };
+        let Some(pid) = element.locator.as_ref().map(|l| l.pid).filter(|pid| *pid != own_pid) else {
+            continue;
+        };
+        let Some(app_name) = element.app_name.clone().or_else(|| window_list::find_app_name(pid)) else {
+            continue;
+        };
+
+        let script = script.get_or_insert_with(|| Script::new(app_name.clone(), element.bundle_id.clone()));
+        if !script.app.eq_ignore_ascii_case(&app_name) {
+            log::warn!("Skipped a click in {} while recording {}", app_name, script.app);
+            continue;
+        }
+        let Some(step) = click_step(&element) else {
+            log::warn!("Skipped a click on an element that can't be found again");
+            continue;
+        };
+        if is_text_input(&element.role) && !is_secure(&element) {
+            let initial = read_attribute(&element, ax_attributes::VALUE);
+            pending = Some(PendingInput { element, step: step.clone(), initial });
+        }
+        script.steps.push(step);
+    }
+
+    if let (Some(script), Some(input)) = (script.as_mut(), pending) {
+        record_input(script, input);
+    }
+    script
+}
+
+fn record_input(script: &mut Script, input: PendingInput) {
+    let value = read_attribute(&input.element, ax_attributes::VALUE);
+    if value.is_some() && value != input.initial {
+        script.steps.push(ScriptStep { action: StepAction::Type, text: value, ..input.step });
+    }
+}
+
+fn offer_save(app: &AppHandle, script: Script) {
+    app.dialog()
+        .file()
+        .add_filter("Interaction Script", &["yaml", "yml", "json"])
+        .set_file_name("interaction.yaml")
+        .save_file(move |path| {
+            let Some(path) = path.and_then(|p| p.into_path().ok()) else {
+                return;
+            };
+            match script.save(&path) {
+                Ok(()) => log::info!("Saved {} steps to {}", script.steps.len(), path.display()),
+                Err(e) => log::error!("Failed to save the interaction script: {}", e),
+            }
+        });
+}
+
+/// Where the captures of failing steps go: a folder next to the script.
+fn failure_dir(path: &Path) -> PathBuf {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("script");
+    path.with_file_name(format!("{}-failures", stem))
+}
+
+/// Replays an interaction script and reports the first failing step.
+#[tauri::command(async)]
+pub fn replay_interaction_script(path: String) -> Result<ReplayReport, String> {
+    let path = PathBuf::from(path);
+    let script = Script::load(&path)?;
+    Ok(replay(&script, &failure_dir(&path)))
+}
+
+/// Writes an interaction script as XCUITest (`.swift`) or Appium (`.py`) code.
+#[tauri::command]
+pub fn export_interaction_script(path: String, output: String) -> Result<(), String> {
+    let script = Script::load(Path::new(&path))?;
+    export_to_file(&script, Path::new(&output))
+}
+
+/// Replays a script on a background thread, sending the report to the frontend when done.
+pub fn replay_in_background(app: &AppHandle, path: PathBuf) {
+    let app = app.clone();
+    thread::spawn(move || {
+        let script = match Script::load(&path) {
+            Ok(script) => script,
+            Err(e) => {
+                log::error!("Failed to load script {}: {}", path.display(), e);
+                return;
+            }
+        };
+        let report = replay(&script, &failure_dir(&path));
+        match &report.failure {
+            Some(failure) => log::warn!("Script {} failed at step {}: {}", path.display(), failure.index, failure.error),
+            None => log::info!("Script {}: {} steps passed", path.display(), report.passed),
+        }
+        let _ = app.emit(EVENT_SCRIPT_REPLAYED, report);
+    });
+}
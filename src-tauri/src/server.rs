@@ -0,0 +1,302 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::http::StatusCode;
+use tungstenite::{Message, WebSocket};
+use crate::constants::{SERVER_POLL_MS, SERVER_PORT, SERVER_TOKEN_FILE, SERVER_TOKEN_FILE_MODE};
+use crate::accessibility::UIElementInfo;
+use crate::rpc::{self, RpcError};
+use crate::AppState;
+
+/// The opt-in WebSocket server that lets external tools inspect and capture live.
+///
+/// It listens on `ws://127.0.0.1:9753` and only accepts clients presenting the
+/// token stored in the app config directory, either as `?token=` or as an
+/// `Authorization: Bearer` header. Messages follow the DevTools protocol:
+///
+/// ```text
+/// → {"id": 1, "method": "querySelector", "params": {"app": "Finder", "selector": "Toolbar Button"}}
+/// ← {"id": 1, "result": {"role": "AXButton", ...}}
+/// ← {"method": "elementHovered", "params": {"role": "AXWindow", ...}}
+/// ```
+///
+/// See [`rpc::call`] for the methods.
+#[derive(Default)]
+pub struct InspectionServer {
+    /// Set to stop the running server, if any.
+    stop: Mutex<Option<Arc<AtomicBool>>>,
+    /// One event queue per connected client.
+    clients: Mutex<Vec<Sender<String>>>,
+    /// The last hover event sent, so an element that stays under the cursor is only announced once.
+    last_hover: Mutex<Option<String>>,
+}
+
+impl InspectionServer {
+    pub fn is_running(&self) -> bool {
+        self.stop.lock().map(|s| s.is_some()).unwrap_or(false)
+    }
+
+    fn shutdown(&self) {
+        if let Some(stop) = self.stop.lock().ok().and_then(|mut s| s.take()) {
+            stop.store(true, Ordering::Relaxed);
+            log::info!("Inspection server stopped");
+        }
+        if let Ok(mut clients) = self.clients.lock() {
+            clients.clear();
+        }
+    }
+}
+
+/// Starts the server on a background thread unless it is already running.
+pub fn start(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let mut stop_lock = state.server.stop.lock().map_err(|e| e.to_string())?;
+    if stop_lock.is_some() {
+        return Ok(());
+    }
+
+    let token = load_or_create_token(app)?;
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, SERVER_PORT)).map_err(|e| e.to_string())?;
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    *stop_lock = Some(stop.clone());
+    log::info!("Inspection server listening on ws://127.0.0.1:{}", SERVER_PORT);
+
+    let app = app.clone();
+    thread::spawn(move || {
+        while !stop.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let (app, token, stop) = (app.clone(), token.clone(), stop.clone());
+                    thread::spawn(move || serve_client(app, stream, &token, stop));
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(SERVER_POLL_MS)),
+                Err(e) => {
+                    log::error!("Inspection server stopped: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Stops the server and disconnects all clients.
+pub fn stop(app: &AppHandle) {
+    app.state::<AppState>().server.shutdown();
+}
+
+/// Sends an event to every connected client.
+pub fn broadcast(app: &AppHandle, method: &str, params: impl serde::Serialize) {
+    let state = app.state::<AppState>();
+    let Ok(mut clients) = state.server.clients.lock() else {
+        return;
+    };
+    if clients.is_empty() {
+        return;
+    }
+    let message = json!({ "method": method, "params": params }).to_string();
+    // Queues of disconnected clients fail to send and are dropped.
+    clients.retain(|client| client.send(message.clone()).is_ok());
+}
+
+/// Sends `elementHovered` for the element under the cursor, skipping repeats of the same element.
+pub fn broadcast_hover(app: &AppHandle, info: &UIElementInfo) {
+    let state = app.state::<AppState>();
+    if state.server.clients.lock().map(|c| c.is_empty()).unwrap_or(true) {
+        return;
+    }
+    let Ok(payload) = serde_json::to_string(info) else {
+        return;
+    };
+    if let Ok(mut last) = state.server.last_hover.lock() {
+        if last.as_deref() == Some(payload.as_str()) {
+            return;
+        }
+        *last = Some(payload);
+    }
+    broadcast(app, "elementHovered", info);
+}
+
+/// Returns the URL clients connect with, including the token.
+pub fn connection_url(app: &AppHandle) -> Result<String, String> {
+    Ok(format!("ws://127.0.0.1:{}/?token={}", SERVER_PORT, load_or_create_token(app)?))
+}
+
+/// Reads the access token, generating and storing a random one on first use.
+///
+/// The file is readable by the user only, and one left readable by others is
+/// restricted before its token is used.
+fn load_or_create_token(app: &AppHandle) -> Result<String, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    let path = dir.join(SERVER_TOKEN_FILE);
+    if let Ok(token) = std::fs::read_to_string(&path) {
+        if !token.trim().is_empty() {
+            let permissions = std::fs::Permissions::from_mode(SERVER_TOKEN_FILE_MODE);
+            std::fs::set_permissions(&path, permissions).map_err(|e| e.to_string())?;
+            return Ok(token.trim().to_string());
+        }
+    }
+
+    let mut bytes = [0u8; 16];
+    std::fs::File::open("/dev/urandom")
+        .and_then(|mut f| f.read_exact(&mut bytes))
+        .map_err(|e| e.to_string())?;
+    let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let _ = std::fs::remove_file(&path);
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(SERVER_TOKEN_FILE_MODE)
+        .open(&path)
+        .and_then(|mut file| file.write_all(token.as_bytes()))
+        .map_err(|e| e.to_string())?;
+    Ok(token)
+}
+
+fn is_authorized(request: &Request, token: &str) -> bool {
+    let bearer = request.headers()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let query = request.uri()
+        .query()
+        .and_then(|q| q.split('&').find_map(|pair| pair.strip_prefix("token=")));
+    let matches = |given: Option<&str>| given.is_some_and(|given| constant_time_eq(given.as_bytes(), token.as_bytes()));
+    matches(bearer) | matches(query)
+}
+
+/// Compares two byte strings in time that depends only on their lengths, so the
+/// token can't be guessed byte by byte from how quickly a wrong one is rejected.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn serve_client(app: AppHandle, stream: TcpStream, token: &str, stop: Arc<AtomicBool>) {
+    let _ = stream.set_nonblocking(false);
+    // The error type is dictated by tungstenite.
+    #[allow(clippy::result_large_err)]
+    let callback = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        if is_authorized(request, token) {
+            return Ok(response);
+        }
+        let mut error = ErrorResponse::new(Some("Invalid or missing token".to_string()));
+        *error.status_mut() = StatusCode::UNAUTHORIZED;
+        Err(error)
+    };
+    let mut socket = match tungstenite::accept_hdr(stream, callback) {
+        Ok(socket) => socket,
+        Err(e) => {
+            log::warn!("Rejected inspection client: {}", e);
+            return;
+        }
+    };
+    // Wake up regularly to forward events between requests.
+    let _ = socket.get_ref().set_read_timeout(Some(Duration::from_millis(SERVER_POLL_MS)));
+
+    let (events, queue) = mpsc::channel();
+    if let Ok(mut clients) = app.state::<AppState>().server.clients.lock() {
+        clients.push(events);
+    }
+
+    while !stop.load(Ordering::Relaxed) {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let reply = handle_request(&app, &text);
+                if socket.send(Message::Text(reply)).is_err() {
+                    return;
+                }
+            }
+            Ok(Message::Close(_)) => return,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e)) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(_) => return,
+        }
+
+        while let Ok(event) = queue.try_recv() {
+            if socket.send(Message::Text(event)).is_err() {
+                return;
+            }
+        }
+    }
+    close(&mut socket);
+}
+
+fn close(socket: &mut WebSocket<TcpStream>) {
+    let _ = socket.close(None);
+    let _ = socket.flush();
+}
+
+/// Answers one request with a result or an error, echoing its `id`.
+fn handle_request(app: &AppHandle, text: &str) -> String {
+    let request: Value = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = RpcError { code: rpc::PARSE_ERROR, message: e.to_string() };
+            return json!({ "id": null, "error": error }).to_string();
+        }
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let Some(method) = request.get("method").and_then(Value::as_str) else {
+        let error = RpcError { code: rpc::INVALID_REQUEST, message: "Missing method".to_string() };
+        return json!({ "id": id, "error": error }).to_string();
+    };
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+    match rpc::call(app, method, params) {
+        Ok(result) => json!({ "id": id, "result": result }),
+        Err(error) => json!({ "id": id, "error": error }),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(uri: &str, authorization: Option<&str>) -> Request {
+        let mut builder = Request::builder().uri(uri);
+        if let Some(value) = authorization {
+            builder = builder.header("authorization", value);
+        }
+        builder.body(()).unwrap()
+    }
+
+    #[test]
+    fn accepts_the_token_in_a_header_or_query() {
+        let token = "0123456789abcdef";
+        assert!(is_authorized(&request("/", Some("Bearer 0123456789abcdef")), token));
+        assert!(is_authorized(&request("/?a=1&token=0123456789abcdef", None), token));
+    }
+
+    #[test]
+    fn rejects_missing_or_wrong_tokens() {
+        let token = "0123456789abcdef";
+        assert!(!is_authorized(&request("/", None), token));
+        assert!(!is_authorized(&request("/?token=0123456789abcdee", None), token));
+        assert!(!is_authorized(&request("/?token=0123456789abcdef0", None), token));
+        assert!(!is_authorized(&request("/?token=", None), token));
+        assert!(!is_authorized(&request("/", Some("Basic 0123456789abcdef")), token));
+        assert!(!is_authorized(&request("/", Some("Bearer 0123456789ABCDEF")), token));
+    }
+
+    #[test]
+    fn constant_time_eq_compares_whole_strings() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret!"));
+        assert!(!constant_time_eq(b"Secret", b"secret"));
+    }
+}
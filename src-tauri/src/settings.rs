@@ -0,0 +1,427 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use image::ImageFormat;
+use tauri::{AppHandle, Emitter, Manager, State};
+use crate::accessibility::DrillDown;
+use crate::element_format::ElementFormat;
+use crate::constants::{
+    DEFAULT_CAPTURE_DELAY_SECS, DEFAULT_RECORDING_FPS, EVENT_SETTINGS_CHANGED, SETTINGS_FILE, SETTINGS_INVALID_FILE,
+    SETTINGS_MAX_PADDING, SETTINGS_VERSION, SHORTCUT_CAPTURE_PARENT, SHORTCUT_CAPTURE_WINDOW, SHORTCUT_COPY_ELEMENT, SHORTCUT_RECAPTURE_LAST,
+    SHORTCUT_START_CAPTURE, SHORTCUT_TOGGLE_FREEZE, SHORTCUT_TOGGLE_RECORDING,
+};
+use crate::shortcuts::{self, ShortcutAction};
+use crate::{tray, AppState};
+
+/// The one-value file the clipboard preference was stored in before `settings.toml`.
+const LEGACY_PREF_FILE: &str = "copy_to_clipboard";
+
+/// Every user preference, persisted as `settings.toml` in the app config directory.
+///
+/// Missing keys take their defaults, so files written by older versions keep
+/// loading. The redaction config is kept separately because `xray-cli` reads it too.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    /// Whether captures are also copied to the clipboard.
+    pub copy_to_clipboard: bool,
+    /// Whether re-captures overwrite the original file instead of writing `name-2.png` etc.
+    pub overwrite_recapture: bool,
+    /// The countdown in seconds before a delayed capture is taken.
+    pub capture_delay_secs: u64,
+    /// Whether element captures inspect a snapshot of the screen taken before the overlay appears.
+    pub freeze_frame: bool,
+    /// The frame rate of element recordings.
+    pub recording_fps: u32,
+    /// Whether the inspection server starts with the app.
+    pub inspection_server: bool,
+    /// The folder save dialogs open in; the system default when unset.
+    pub save_directory: Option<String>,
+    /// The extension suggested for new captures, e.g. `png` or `jpg`.
+    pub image_format: String,
+    /// Points of surrounding screen added on every side of element captures.
+    pub padding: f64,
+    /// How far the hover descends into containers.
+    pub drill_down: DrillDown,
+    /// Apps, by name or bundle identifier, whose elements the hover highlights; all apps when empty.
+    pub inspect_apps: Vec<String>,
+    /// The format Option-click and the copy shortcut put elements on the clipboard in.
+    pub copy_format: ElementFormat,
+    pub shortcuts: ShortcutSettings,
+    pub overlay: OverlayColors,
+}
+
+/// The global shortcut of each [`ShortcutAction`], in the format of
+/// `tauri-plugin-global-shortcut` (e.g. `CommandOrControl+Shift+X`).
+/// An empty string leaves the action unbound.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ShortcutSettings {
+    pub start_capture: String,
+    pub capture_window: String,
+    pub capture_parent: String,
+    pub recapture_last: String,
+    pub toggle_recording: String,
+    pub toggle_freeze: String,
+    pub copy_element: String,
+}
+
+impl ShortcutSettings {
+    pub fn binding(&self, action: ShortcutAction) -> &str {
+        match action {
+            ShortcutAction::StartCapture => &self.start_capture,
+            ShortcutAction::CaptureWindow => &self.capture_window,
+            ShortcutAction::CaptureParent => &self.capture_parent,
+            ShortcutAction::RecaptureLast => &self.recapture_last,
+            ShortcutAction::ToggleRecording => &self.toggle_recording,
+            ShortcutAction::ToggleFreeze => &self.toggle_freeze,
+            ShortcutAction::CopyElement => &self.copy_element,
+        }
+    }
+
+    pub fn binding_mut(&mut self, action: ShortcutAction) -> &mut String {
+        match action {
+            ShortcutAction::StartCapture => &mut self.start_capture,
+            ShortcutAction::CaptureWindow => &mut self.capture_window,
+            ShortcutAction::CaptureParent => &mut self.capture_parent,
+            ShortcutAction::RecaptureLast => &mut self.recapture_last,
+            ShortcutAction::ToggleRecording => &mut self.toggle_recording,
+            ShortcutAction::ToggleFreeze => &mut self.toggle_freeze,
+            ShortcutAction::CopyElement => &mut self.copy_element,
+        }
+    }
+}
+
+/// CSS colors of the inspection overlay.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct OverlayColors {
+    /// The outline of the hovered element.
+    pub border: String,
+    /// The tint over the hovered element.
+    pub fill: String,
+    /// The background of the HUD label.
+    pub label: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            copy_to_clipboard: true,
+            overwrite_recapture: false,
+            capture_delay_secs: DEFAULT_CAPTURE_DELAY_SECS,
+            freeze_frame: true,
+            recording_fps: DEFAULT_RECORDING_FPS,
+            inspection_server: false,
+            save_directory: None,
+            image_format: "png".to_string(),
+            padding: 0.0,
+            drill_down: DrillDown::default(),
+            inspect_apps: Vec::new(),
+            copy_format: ElementFormat::default(),
+            shortcuts: ShortcutSettings::default(),
+            overlay: OverlayColors::default(),
+        }
+    }
+}
+
+impl Default for ShortcutSettings {
+    fn default() -> Self {
+        Self {
+            start_capture: SHORTCUT_START_CAPTURE.to_string(),
+            capture_window: SHORTCUT_CAPTURE_WINDOW.to_string(),
+            capture_parent: SHORTCUT_CAPTURE_PARENT.to_string(),
+            recapture_last: SHORTCUT_RECAPTURE_LAST.to_string(),
+            toggle_recording: SHORTCUT_TOGGLE_RECORDING.to_string(),
+            toggle_freeze: SHORTCUT_TOGGLE_FREEZE.to_string(),
+            copy_element: SHORTCUT_COPY_ELEMENT.to_string(),
+        }
+    }
+}
+
+impl Default for OverlayColors {
+    fn default() -> Self {
+        Self {
+            border: "red".to_string(),
+            fill: "rgba(255, 0, 0, 0.1)".to_string(),
+            label: "#cc0000".to_string(),
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the settings from `dir`, migrating the legacy pref file on first run.
+    ///
+    /// Values that can't be read or fail validation fall back to their defaults one
+    /// by one, and the file as it was is copied to `settings.toml.invalid` before the
+    /// next save can overwrite it.
+    pub fn load(dir: &Path) -> Self {
+        let path = dir.join(SETTINGS_FILE);
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            return Self::migrate_legacy(dir);
+        };
+        let (settings, problems) = Self::parse(&text);
+        if !problems.is_empty() {
+            for problem in &problems {
+                log::warn!("Ignoring invalid value in {}: {}", path.display(), problem);
+            }
+            let backup = dir.join(SETTINGS_INVALID_FILE);
+            match std::fs::copy(&path, &backup) {
+                Ok(_) => log::warn!("Kept a copy of the invalid settings as {}", backup.display()),
+                Err(e) => log::warn!("Could not back up {}: {}", path.display(), e),
+            }
+        }
+        if settings.version > SETTINGS_VERSION {
+            log::warn!("{} was written by a newer version of xray; unknown keys are ignored", path.display());
+        }
+        Settings { version: SETTINGS_VERSION, ..settings }
+    }
+
+    /// Parses a settings file, replacing every value that can't be read or is out of
+    /// range with its default, and describes each value replaced.
+    fn parse(text: &str) -> (Self, Vec<String>) {
+        let table = match text.parse::<toml::Table>() {
+            Ok(table) => table,
+            Err(e) => return (Self::default(), vec![e.to_string()]),
+        };
+
+        // Every field has a default, so each key can be tried on its own.
+        let mut problems = Vec::new();
+        let mut readable = toml::Table::new();
+        for (key, value) in table {
+            let single = toml::Table::from_iter([(key.clone(), value.clone())]);
+            match toml::Value::Table(single).try_into::<Settings>() {
+                Ok(_) => {
+                    readable.insert(key, value);
+                }
+                Err(e) => problems.push(format!("{}: {}", key, e.message())),
+            }
+        }
+        let mut settings = toml::Value::Table(readable).try_into::<Settings>().unwrap_or_default();
+        problems.extend(settings.sanitize());
+        (settings, problems)
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        let text = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        std::fs::write(dir.join(SETTINGS_FILE), text).map_err(|e| e.to_string())
+    }
+
+    /// Builds the settings from the pref file of earlier versions and replaces it
+    /// with `settings.toml`.
+    fn migrate_legacy(dir: &Path) -> Self {
+        let legacy = dir.join(LEGACY_PREF_FILE);
+        let Ok(text) = std::fs::read_to_string(&legacy) else {
+            return Self::default();
+        };
+        let defaults = Self::default();
+        let settings = Self {
+            copy_to_clipboard: text.trim().parse().unwrap_or(defaults.copy_to_clipboard),
+            ..defaults
+        };
+        match settings.save(dir) {
+            Ok(()) => {
+                log::info!("Migrated {} to {}", LEGACY_PREF_FILE, SETTINGS_FILE);
+                let _ = std::fs::remove_file(&legacy);
+            }
+            Err(e) => log::warn!("Failed to migrate the legacy preferences: {}", e),
+        }
+        settings
+    }
+
+    /// Checks that every value is usable, naming the first one that isn't.
+    pub fn validate(&self) -> Result<(), String> {
+        match self.problems().into_iter().next() {
+            Some((_, problem)) => Err(problem),
+            None => Ok(()),
+        }
+    }
+
+    /// Resets every unusable value to its default, describing each one reset.
+    fn sanitize(&mut self) -> Vec<String> {
+        let defaults = Self::default();
+        let problems = self.problems();
+        for (key, _) in &problems {
+            match *key {
+                "capture_delay_secs" => self.capture_delay_secs = defaults.capture_delay_secs,
+                "recording_fps" => self.recording_fps = defaults.recording_fps,
+                "image_format" => self.image_format = defaults.image_format.clone(),
+                "padding" => self.padding = defaults.padding,
+                "save_directory" => self.save_directory = defaults.save_directory.clone(),
+                "inspect_apps" => self.inspect_apps = defaults.inspect_apps.clone(),
+                "shortcuts" => self.shortcuts = defaults.shortcuts.clone(),
+                "overlay" => self.overlay = defaults.overlay.clone(),
+                _ => {}
+            }
+        }
+        problems.into_iter().map(|(_, problem)| problem).collect()
+    }
+
+    /// Describes every unusable value, along with the key it is stored under.
+    fn problems(&self) -> Vec<(&'static str, String)> {
+        let mut problems = Vec::new();
+        if !(1..=60).contains(&self.capture_delay_secs) {
+            problems.push(("capture_delay_secs", "capture_delay_secs must be between 1 and 60".to_string()));
+        }
+        if !(1..=60).contains(&self.recording_fps) {
+            problems.push(("recording_fps", "recording_fps must be between 1 and 60".to_string()));
+        }
+        if ImageFormat::from_extension(&self.image_format).is_none() {
+            problems.push(("image_format", format!("Unsupported image_format: {}", self.image_format)));
+        }
+        if !(0.0..=SETTINGS_MAX_PADDING).contains(&self.padding) {
+            problems.push(("padding", format!("padding must be between 0 and {}", SETTINGS_MAX_PADDING)));
+        }
+        if self.save_directory.as_ref().is_some_and(|dir| !Path::new(dir).is_absolute()) {
+            problems.push(("save_directory", "save_directory must be an absolute path".to_string()));
+        }
+        if self.inspect_apps.iter().any(|a| a.trim().is_empty()) {
+            problems.push(("inspect_apps", "inspect_apps must not contain empty names".to_string()));
+        }
+        if let Err(e) = shortcuts::check(&self.shortcuts) {
+            problems.push(("shortcuts", e));
+        }
+        let colors = [&self.overlay.border, &self.overlay.fill, &self.overlay.label];
+        if colors.iter().any(|c| c.trim().is_empty()) {
+            problems.push(("overlay", "Overlay colors must not be empty".to_string()));
+        }
+        problems
+    }
+}
+
+/// The current settings, with a revision that changes on every update.
+///
+/// Threads that read settings in a loop, like the polling thread, keep a copy
+/// and only refresh it when the revision moves on.
+pub struct SettingsStore {
+    current: Mutex<Settings>,
+    revision: AtomicU64,
+}
+
+impl SettingsStore {
+    pub fn new(settings: Settings) -> Self {
+        Self { current: Mutex::new(settings), revision: AtomicU64::new(0) }
+    }
+
+    pub fn get(&self) -> Settings {
+        self.current.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    /// Reads one value without copying the whole struct.
+    pub fn read<T>(&self, f: impl FnOnce(&Settings) -> T) -> T {
+        match self.current.lock() {
+            Ok(settings) => f(&settings),
+            Err(_) => f(&Settings::default()),
+        }
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision.load(Ordering::Relaxed)
+    }
+}
+
+/// Applies `change` to the settings, then validates, persists and announces the result.
+///
+/// The store stays locked from reading the old value until the new one is saved,
+/// so concurrent updates can't overwrite each other's changes.
+pub fn update(app: &AppHandle, change: impl FnOnce(&mut Settings)) -> Result<Settings, String> {
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    let state = app.state::<AppState>();
+    let (previous, settings) = {
+        let mut current = state.settings.current.lock().map_err(|e| e.to_string())?;
+        let mut settings = current.clone();
+        change(&mut settings);
+        settings.validate()?;
+        settings.save(&config_dir)?;
+        (std::mem::replace(&mut *current, settings.clone()), settings)
+    };
+    state.settings.revision.fetch_add(1, Ordering::Relaxed);
+
+    if previous.shortcuts != settings.shortcuts {
+        shortcuts::register(app, &settings.shortcuts);
+    }
+    let _ = app.emit(EVENT_SETTINGS_CHANGED, &settings);
+    tray::refresh_menu(app);
+    Ok(settings)
+}
+
+/// Returns the current settings.
+#[tauri::command]
+pub fn get_settings(state: State<AppState>) -> Settings {
+    state.settings.get()
+}
+
+/// Replaces and persists the settings, rejecting invalid values.
+#[tauri::command]
+pub fn set_settings(settings: Settings, app: AppHandle) -> Result<Settings, String> {
+    update(&app, |current| *current = settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("xray-settings-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn out_of_range_value_falls_back_alone() {
+        let (settings, problems) = Settings::parse("recording_fps = 500\ncapture_delay_secs = 5\n");
+        assert_eq!(problems.len(), 1);
+        assert_eq!(settings.recording_fps, DEFAULT_RECORDING_FPS);
+        assert_eq!(settings.capture_delay_secs, 5);
+    }
+
+    #[test]
+    fn unreadable_value_falls_back_alone() {
+        let (settings, problems) = Settings::parse("padding = \"wide\"\ncopy_to_clipboard = false\nimage_format = \"jpg\"\n");
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("padding"), "{:?}", problems);
+        assert_eq!(settings.padding, Settings::default().padding);
+        assert!(!settings.copy_to_clipboard);
+        assert_eq!(settings.image_format, "jpg");
+    }
+
+    #[test]
+    fn broken_file_uses_defaults() {
+        let (settings, problems) = Settings::parse("recording_fps = ");
+        assert_eq!(problems.len(), 1);
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn valid_file_has_no_problems() {
+        let text = toml::to_string_pretty(&Settings::default()).unwrap();
+        assert_eq!(Settings::parse(&text), (Settings::default(), Vec::new()));
+    }
+
+    #[test]
+    fn invalid_file_is_backed_up() {
+        let dir = temp_dir("invalid");
+        let text = "recording_fps = 0\ncopy_to_clipboard = false\n";
+        std::fs::write(dir.join(SETTINGS_FILE), text).unwrap();
+
+        let settings = Settings::load(&dir);
+        assert!(!settings.copy_to_clipboard);
+        assert_eq!(settings.recording_fps, DEFAULT_RECORDING_FPS);
+        assert_eq!(std::fs::read_to_string(dir.join(SETTINGS_INVALID_FILE)).unwrap(), text);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn valid_file_is_not_backed_up() {
+        let dir = temp_dir("valid");
+        Settings::default().save(&dir).unwrap();
+
+        assert_eq!(Settings::load(&dir), Settings::default());
+        assert!(!dir.join(SETTINGS_INVALID_FILE).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
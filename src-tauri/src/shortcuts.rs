@@ -0,0 +1,183 @@
+use std::thread;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+use crate::accessibility::{self, UIElementInfo};
+use crate::capture::{self, CaptureMode};
+use crate::constants::EVENT_SHORTCUTS_CHANGED;
+use crate::settings::{self, ShortcutSettings};
+use crate::{baseline, element_format, recording, start_capture_session, timed_capture, tray, AppState};
+
+/// Something a global shortcut can be bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ShortcutAction {
+    /// Shows the overlay to pick an element.
+    StartCapture,
+    /// Captures the window under the cursor right away.
+    CaptureWindow,
+    /// Captures the parent of the element under the cursor right away.
+    CaptureParent,
+    RecaptureLast,
+    ToggleRecording,
+    /// Turns freezing the screen while inspecting on or off.
+    ToggleFreeze,
+    /// Copies the element under the cursor as text in the configured format.
+    CopyElement,
+}
+
+impl ShortcutAction {
+    pub const ALL: [ShortcutAction; 7] = [
+        Self::StartCapture,
+        Self::CaptureWindow,
+        Self::CaptureParent,
+        Self::RecaptureLast,
+        Self::ToggleRecording,
+        Self::ToggleFreeze,
+        Self::CopyElement,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::StartCapture => "Start Inspection",
+            Self::CaptureWindow => "Capture Window",
+            Self::CaptureParent => "Capture Parent Element",
+            Self::RecaptureLast => "Re-capture Last Element",
+            Self::ToggleRecording => "Toggle Recording",
+            Self::ToggleFreeze => "Toggle Frozen Mode",
+            Self::CopyElement => "Copy Element Info",
+        }
+    }
+
+    fn run(self, app: &AppHandle) {
+        match self {
+            Self::StartCapture => start_capture_session(app, CaptureMode::Element),
+            Self::CaptureWindow => capture_under_cursor(app, accessibility::get_window_at),
+            Self::CaptureParent => capture_under_cursor(app, accessibility::get_parent_at),
+            Self::RecaptureLast => recapture_last(app),
+            Self::ToggleRecording => recording::toggle(app),
+            Self::CopyElement => element_format::copy_under_cursor(app),
+            Self::ToggleFreeze => {
+                if let Err(e) = settings::update(app, |s| s.freeze_frame = !s.freeze_frame) {
+                    log::error!("Failed to toggle frozen mode: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Whether the binding of an action is active.
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutStatus {
+    pub action: ShortcutAction,
+    /// The binding, or an empty string when the action is unbound.
+    pub shortcut: String,
+    pub registered: bool,
+    /// Why registering failed, e.g. because another app owns the shortcut.
+    pub error: Option<String>,
+}
+
+/// Checks that every binding parses and that no two actions share one.
+pub fn check(shortcuts: &ShortcutSettings) -> Result<(), String> {
+    let mut seen: Vec<(ShortcutAction, Shortcut)> = Vec::new();
+    for action in ShortcutAction::ALL {
+        let binding = shortcuts.binding(action);
+        if binding.trim().is_empty() {
+            continue;
+        }
+        let shortcut = binding
+            .parse::<Shortcut>()
+            .map_err(|e| format!("Invalid shortcut for {}: {}", action.label(), e))?;
+        if let Some((other, _)) = seen.iter().find(|(_, s)| *s == shortcut) {
+            return Err(format!("{} is already bound to {}", binding, other.label()));
+        }
+        seen.push((action, shortcut));
+    }
+    Ok(())
+}
+
+/// Replaces the registered global shortcuts with `shortcuts`.
+///
+/// A binding that can't be registered, e.g. because another app owns it, is
+/// reported in the status and skipped, so the other shortcuts keep working.
+pub fn register(app: &AppHandle, shortcuts: &ShortcutSettings) {
+    let global_shortcut = app.global_shortcut();
+    let _ = global_shortcut.unregister_all();
+
+    let status: Vec<ShortcutStatus> = ShortcutAction::ALL
+        .into_iter()
+        .map(|action| {
+            let shortcut = shortcuts.binding(action).trim().to_string();
+            let error = if shortcut.is_empty() {
+                None
+            } else {
+                global_shortcut.register(shortcut.as_str()).err().map(|e| e.to_string())
+            };
+            if let Some(e) = &error {
+                log::warn!("Shortcut {} for {} is unavailable: {}", shortcut, action.label(), e);
+            }
+            ShortcutStatus { action, registered: !shortcut.is_empty() && error.is_none(), shortcut, error }
+        })
+        .collect();
+
+    if let Ok(mut current) = app.state::<AppState>().shortcut_status.lock() {
+        *current = status.clone();
+    }
+    let _ = app.emit(EVENT_SHORTCUTS_CHANGED, status);
+    tray::refresh_menu(app);
+}
+
+/// Runs the action bound to a pressed `shortcut`.
+pub fn handle(app: &AppHandle, shortcut: &Shortcut) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let shortcuts = state.settings.read(|s| s.shortcuts.clone());
+    let action = ShortcutAction::ALL
+        .into_iter()
+        .find(|action| shortcuts.binding(*action).parse::<Shortcut>().ok().as_ref() == Some(shortcut));
+    if let Some(action) = action {
+        action.run(app);
+    }
+}
+
+/// Grabs the element `find` returns for the cursor position and asks where to save it.
+fn capture_under_cursor(app: &AppHandle, find: fn(f64, f64) -> Option<UIElementInfo>) {
+    let app = app.clone();
+    thread::spawn(move || {
+        let Some(element) = accessibility::mouse_location().and_then(|p| find(p.x, p.y)) else {
+            log::warn!("Found no element to capture under the cursor");
+            return;
+        };
+        let state = app.state::<AppState>();
+        match capture::grab_redacted(&state, &element) {
+            Ok(image) => timed_capture::offer_pending(&app, &state, element, image, CaptureMode::Element),
+            Err(e) => capture::report_error(&app, "Capture failed", &e),
+        }
+    });
+}
+
+/// Re-captures the newest history entry off the main thread.
+fn recapture_last(app: &AppHandle) {
+    let app = app.clone();
+    thread::spawn(move || {
+        if let Err(e) = baseline::recapture_last_and_compare(&app) {
+            capture::report_error(&app, "Re-capture failed", &e);
+        }
+    });
+}
+
+/// Returns which shortcuts are bound and whether each could be registered.
+#[tauri::command]
+pub fn get_shortcut_status(state: State<AppState>) -> Vec<ShortcutStatus> {
+    state.shortcut_status.lock().map(|s| s.clone()).unwrap_or_default()
+}
+
+/// Binds `action` to `shortcut`, or unbinds it when `shortcut` is empty.
+///
+/// Fails without changing anything if the shortcut is invalid or bound to another action.
+#[tauri::command]
+pub fn set_shortcut(action: ShortcutAction, shortcut: String, app: AppHandle) -> Result<Vec<ShortcutStatus>, String> {
+    settings::update(&app, |s| *s.shortcuts.binding_mut(action) = shortcut.trim().to_string())?;
+    Ok(get_shortcut_status(app.state::<AppState>()))
+}
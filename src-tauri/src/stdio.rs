@@ -0,0 +1,83 @@
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+use crate::rpc::{self, RpcError};
+
+/// The command-line flag that turns on the stdio mode.
+pub const STDIO_FLAG: &str = "--stdio";
+
+/// Whether stdout carries JSON-RPC, so notifications may be written to it.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Whether the app was started as `xray --stdio`.
+pub fn is_requested() -> bool {
+    std::env::args().skip(1).any(|arg| arg == STDIO_FLAG)
+}
+
+/// Serves JSON-RPC 2.0 over stdin and stdout, one message per line, for the
+/// editor or script that spawned the app.
+///
+/// The methods are those of [`rpc::call`]; notifications such as
+/// `elementPicked` are written to stdout as they happen. The app quits when
+/// stdin is closed, so it does not outlive its parent.
+pub fn spawn(app: &AppHandle) {
+    ACTIVE.store(true, Ordering::Relaxed);
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = handle_message(&app, &line) {
+                write_line(&response);
+            }
+        }
+        log::info!("stdin closed, quitting");
+        app.exit(0);
+    });
+}
+
+/// Writes a JSON-RPC notification to stdout when the stdio mode is active.
+pub fn notify(method: &str, params: &Value) {
+    if ACTIVE.load(Ordering::Relaxed) {
+        write_line(&json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+}
+
+fn write_line(message: &Value) {
+    // Locking keeps concurrent responses and notifications on separate lines.
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{}", message);
+    let _ = stdout.flush();
+}
+
+/// Answers one line of input. Notifications from the client get no response.
+fn handle_message(app: &AppHandle, line: &str) -> Option<Value> {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return Some(error_response(Value::Null, RpcError { code: rpc::PARSE_ERROR, message: e.to_string() })),
+    };
+    let id = request.get("id").cloned();
+
+    let method = request.get("method").and_then(Value::as_str);
+    let Some(method) = method.filter(|_| request.get("jsonrpc").and_then(Value::as_str) == Some("2.0")) else {
+        let error = RpcError { code: rpc::INVALID_REQUEST, message: "Expected a JSON-RPC 2.0 request".to_string() };
+        return Some(error_response(id.unwrap_or(Value::Null), error));
+    };
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = rpc::call(app, method, params);
+    let id = id?;
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => error_response(id, error),
+    })
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": error })
+}
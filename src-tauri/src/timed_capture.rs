@@ -0,0 +1,80 @@
+use std::thread;
+use std::time::Duration;
+use image::RgbaImage;
+use tauri::{AppHandle, Emitter, Manager, State};
+use crate::accessibility::{self, UIElementInfo};
+use crate::capture::{self, CaptureClick, CaptureError, CaptureMode};
+use crate::constants::{EVENT_CAPTURE_CLICK, TRAY_ID};
+use crate::{permissions, AppState};
+
+/// A capture grabbed before the user chose where to save it.
+pub struct PendingCapture {
+    pub element: UIElementInfo,
+    pub image: RgbaImage,
+}
+
+/// Grabs an element after the configured countdown and asks the frontend where to save it.
+///
+/// With `element` set, that element's rect is captured; otherwise whatever is
+/// under the cursor when the countdown ends. The remaining seconds are shown
+/// next to the tray icon. The image is grabbed before the save dialog opens,
+/// so menus and hover states are captured as they were.
+pub fn capture_after_delay(app: &AppHandle, element: Option<UIElementInfo>) {
+    if !permissions::ensure(app) {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || {
+        let state = app.state::<AppState>();
+        let secs = state.settings.read(|s| s.capture_delay_secs);
+        let tray = app.tray_by_id(TRAY_ID);
+
+        for remaining in (1..=secs).rev() {
+            if let Some(tray) = &tray {
+                let _ = tray.set_title(Some(remaining.to_string()));
+            }
+            thread::sleep(Duration::from_secs(1));
+        }
+        if let Some(tray) = &tray {
+            let _ = tray.set_title(None::<&str>);
+        }
+
+        let Some(element) = element.or_else(accessibility::get_located_element_at_mouse) else {
+            log::warn!("Delayed capture found no element under the cursor");
+            return;
+        };
+
+        match capture::grab_redacted(&state, &element) {
+            Ok(image) => offer_pending(&app, &state, element, image, CaptureMode::Delayed),
+            Err(e) => capture::report_error(&app, "Delayed capture failed", &e),
+        }
+    });
+}
+
+/// Keeps an already grabbed `image` as the pending capture and asks the frontend where to save it.
+pub fn offer_pending(app: &AppHandle, state: &AppState, element: UIElementInfo, image: RgbaImage, mode: CaptureMode) {
+    if let Ok(mut pending) = state.pending_capture.lock() {
+        *pending = Some(PendingCapture { element: element.clone(), image });
+    }
+    let _ = app.emit(EVENT_CAPTURE_CLICK, CaptureClick { element, mode, pending: true });
+}
+
+/// Saves the pending capture, grabbed by a delayed or frozen-frame capture, to `path`.
+#[tauri::command]
+pub fn save_pending_capture(path: String, app: AppHandle, state: State<AppState>) -> Result<(), CaptureError> {
+    let pending = state.pending_capture.lock()
+        .map_err(|e| CaptureError::Other(e.to_string()))?
+        .take()
+        .ok_or_else(|| "There is no pending capture".to_string())?;
+
+    capture::finish_capture(&app, &state, &pending.element, &pending.image, &path)
+        .inspect_err(|e| log::error!("Saving the pending capture of {} to {} failed: {}", pending.element.role, path, e))
+}
+
+/// Drops the pending capture, e.g. when the user cancels the save dialog.
+#[tauri::command]
+pub fn discard_pending_capture(state: State<AppState>) {
+    if let Ok(mut pending) = state.pending_capture.lock() {
+        pending.take();
+    }
+}
@@ -0,0 +1,414 @@
+use std::path::Path;
+use std::thread;
+use tauri::{
+    image::Image,
+    menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu},
+    tray::TrayIconBuilder,
+    AppHandle, Manager,
+};
+use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_opener::OpenerExt;
+use crate::constants::{CAPTURE_DELAY_CHOICES, RECENT_CAPTURES_LIMIT, RECORDING_FPS_CHOICES, TRAY_ID, TRAY_THUMBNAIL_SIZE};
+use crate::capture::{CaptureError, CaptureMode};
+use crate::diff::DiffOptions;
+use crate::element_format::ElementFormat;
+use crate::history::HistoryEntry;
+use crate::permissions::Permission;
+use crate::{
+    annotation, baseline, capture, clipboard, manifest, permissions, recording, script, server, settings, start_capture_session, timed_capture,
+    window_picker, AppState,
+};
+
+/// Menu id prefixes for the per-capture actions in the "Recent Captures" submenu.
+const RECENT_COPY_PREFIX: &str = "recent-copy:";
+const RECENT_REVEAL_PREFIX: &str = "recent-reveal:";
+const RECENT_RECAPTURE_PREFIX: &str = "recent-recapture:";
+const RECENT_ANNOTATE_PREFIX: &str = "recent-annotate:";
+const RECENT_BASELINE_PREFIX: &str = "recent-baseline:";
+const RECENT_COMPARE_PREFIX: &str = "recent-compare:";
+
+/// Menu id prefix for the choices in the "Capture Delay" submenu.
+const DELAY_PREFIX: &str = "delay:";
+
+/// Menu id prefix for the choices in the "Recording Frame Rate" submenu.
+const FPS_PREFIX: &str = "fps:";
+
+/// Menu id prefix for the choices in the "Copy Elements As" submenu.
+const COPY_FORMAT_PREFIX: &str = "copy-format:";
+
+/// Creates the tray icon with its initial menu.
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let menu = build_menu(app)?;
+
+    TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&menu)
+        .icon(app.default_window_icon().unwrap().clone())
+        .on_menu_event(handle_menu_event)
+        .build(app)?;
+
+    Ok(())
+}
+
+/// Rebuilds the tray menu so the "Recent Captures" submenu reflects the current history.
+pub fn refresh_menu(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    match build_menu(app) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(e) => log::warn!("Failed to rebuild tray menu: {}", e),
+    }
+}
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    let state = app.state::<AppState>();
+    let prefs = state.settings.get();
+    let (copy_enabled, overwrite_enabled, freeze_enabled) = (prefs.copy_to_clipboard, prefs.overwrite_recapture, prefs.freeze_frame);
+    let redact_enabled = state.redaction.lock().map(|c| c.enabled).unwrap_or(false);
+    let (delay, fps) = (prefs.capture_delay_secs, prefs.recording_fps);
+    let server_running = state.server.is_running();
+    let record_label = if recording::is_recording(&state) { "Stop Recording" } else { "Record Element…" };
+    let recent = state.history.lock()
+        .map(|h| h.entries().into_iter().take(RECENT_CAPTURES_LIMIT).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let snip_i = MenuItem::with_id(app, "snip", "Snip Screen", true, None::<&str>)?;
+    let snip_scroll_i = MenuItem::with_id(app, "snip_scroll", "Snip Scrolling Area", true, None::<&str>)?;
+    let pick_window_i = MenuItem::with_id(app, "pick_window", "Pick Window…", true, None::<&str>)?;
+    let snip_delayed_i = MenuItem::with_id(app, "snip_delayed", format!("Snip with {}s Delay", delay), true, None::<&str>)?;
+    let cursor_delayed_i = MenuItem::with_id(app, "capture_cursor_delayed", format!("Capture Under Cursor in {}s", delay), true, None::<&str>)?;
+    let delay_i = build_delay_submenu(app, delay)?;
+    let record_i = MenuItem::with_id(app, "record", record_label, true, None::<&str>)?;
+    let manifest_i = MenuItem::with_id(app, "run_manifest", "Run Screenshot Manifest…", true, None::<&str>)?;
+    let script_label = if script::is_recording(&state) { "Stop Recording Interactions" } else { "Record Interactions" };
+    let record_script_i = MenuItem::with_id(app, "record_script", script_label, true, None::<&str>)?;
+    let replay_script_i = MenuItem::with_id(app, "replay_script", "Replay Interaction Script…", true, None::<&str>)?;
+    let export_script_i = MenuItem::with_id(app, "export_script", "Export Interaction Script…", true, None::<&str>)?;
+    let fps_i = build_fps_submenu(app, fps)?;
+    let copy_format_i = build_copy_format_submenu(app, prefs.copy_format)?;
+    let recapture_i = MenuItem::with_id(app, "recapture_last", "Re-capture Last Element", !recent.is_empty(), None::<&str>)?;
+    let recent_i = build_recent_submenu(app, &recent)?;
+    let copy_i = CheckMenuItem::with_id(app, "copy_to_clipboard", "Copy to Clipboard", true, copy_enabled, None::<&str>)?;
+    let overwrite_i = CheckMenuItem::with_id(app, "overwrite_recapture", "Overwrite on Re-capture", true, overwrite_enabled, None::<&str>)?;
+    let redact_i = CheckMenuItem::with_id(app, "redact", "Redact Sensitive Fields", true, redact_enabled, None::<&str>)?;
+    let freeze_i = CheckMenuItem::with_id(app, "freeze_frame", "Freeze Screen While Inspecting", true, freeze_enabled, None::<&str>)?;
+    let server_i = CheckMenuItem::with_id(app, "inspection_server", "Inspection Server", true, server_running, None::<&str>)?;
+    let server_url_i = MenuItem::with_id(app, "copy_server_url", "Copy Inspection Server URL", server_running, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(app)?;
+    let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &snip_i, &snip_scroll_i, &pick_window_i, &snip_delayed_i, &cursor_delayed_i, &record_i, &manifest_i, &record_script_i,
+            &replay_script_i, &export_script_i, &recapture_i, &recent_i,
+            &copy_i, &overwrite_i, &redact_i, &freeze_i, &delay_i, &fps_i, &copy_format_i, &server_i, &server_url_i, &separator,
+        ],
+    )?;
+    // Missing permissions come first, since nothing else works without them.
+    let missing = state.permissions.lock().map(|p| p.missing()).unwrap_or_default();
+    if !missing.is_empty() {
+        menu.prepend(&PredefinedMenuItem::separator(app)?)?;
+    }
+    for permission in missing.into_iter().rev() {
+        let label = format!("⚠ Grant {} Access…", permission.label());
+        menu.prepend(&MenuItem::with_id(app, permission_menu_id(permission), label, true, None::<&str>)?)?;
+    }
+    // Shortcuts another app owns are listed so the user knows to rebind them.
+    let unavailable = state.shortcut_status.lock().map(|s| s.clone()).unwrap_or_default();
+    for status in unavailable.iter().filter(|s| s.error.is_some()) {
+        let label = format!("⚠ {} ({}) is unavailable", status.action.label(), status.shortcut);
+        menu.append(&MenuItem::new(app, label, false, None::<&str>)?)?;
+    }
+    menu.append(&quit_i)?;
+    Ok(menu)
+}
+
+fn permission_menu_id(permission: Permission) -> &'static str {
+    match permission {
+        Permission::Accessibility => "grant_accessibility",
+        Permission::ScreenRecording => "grant_screen_recording",
+    }
+}
+
+fn build_delay_submenu(app: &AppHandle, current: u64) -> tauri::Result<Submenu<tauri::Wry>> {
+    let submenu = Submenu::with_id(app, "delay", "Capture Delay", true)?;
+    for secs in CAPTURE_DELAY_CHOICES {
+        let item = CheckMenuItem::with_id(
+            app,
+            format!("{}{}", DELAY_PREFIX, secs),
+            format!("{} Seconds", secs),
+            true,
+            *secs == current,
+            None::<&str>,
+        )?;
+        submenu.append(&item)?;
+    }
+    Ok(submenu)
+}
+
+fn build_fps_submenu(app: &AppHandle, current: u32) -> tauri::Result<Submenu<tauri::Wry>> {
+    let submenu = Submenu::with_id(app, "fps", "Recording Frame Rate", true)?;
+    for fps in RECORDING_FPS_CHOICES {
+        let item = CheckMenuItem::with_id(
+            app,
+            format!("{}{}", FPS_PREFIX, fps),
+            format!("{} FPS", fps),
+            true,
+            *fps == current,
+            None::<&str>,
+        )?;
+        submenu.append(&item)?;
+    }
+    Ok(submenu)
+}
+
+/// Lists the formats Option-click and the copy shortcut can put elements on the clipboard in.
+fn build_copy_format_submenu(app: &AppHandle, current: ElementFormat) -> tauri::Result<Submenu<tauri::Wry>> {
+    let submenu = Submenu::with_id(app, "copy_format", "Copy Elements As", true)?;
+    for format in ElementFormat::ALL {
+        let item = CheckMenuItem::with_id(
+            app,
+            format!("{}{:?}", COPY_FORMAT_PREFIX, format).to_lowercase(),
+            format.label(),
+            true,
+            format == current,
+            None::<&str>,
+        )?;
+        submenu.append(&item)?;
+    }
+    Ok(submenu)
+}
+
+fn build_recent_submenu(app: &AppHandle, entries: &[HistoryEntry]) -> tauri::Result<Submenu<tauri::Wry>> {
+    let submenu = Submenu::with_id(app, "recent", "Recent Captures", !entries.is_empty())?;
+
+    for entry in entries {
+        let copy_i = MenuItem::with_id(app, format!("{}{}", RECENT_COPY_PREFIX, entry.id), "Copy to Clipboard", true, None::<&str>)?;
+        let reveal_i = MenuItem::with_id(app, format!("{}{}", RECENT_REVEAL_PREFIX, entry.id), "Reveal in Finder", true, None::<&str>)?;
+        let recapture_i = MenuItem::with_id(app, format!("{}{}", RECENT_RECAPTURE_PREFIX, entry.id), "Capture Again", true, None::<&str>)?;
+        let annotate_i = MenuItem::with_id(app, format!("{}{}", RECENT_ANNOTATE_PREFIX, entry.id), "Annotate…", true, None::<&str>)?;
+
+        let item = Submenu::with_id_and_icon(
+            app,
+            format!("recent:{}", entry.id),
+            recent_label(entry),
+            true,
+            load_thumbnail(entry),
+        )?;
+        let baseline_i = MenuItem::with_id(app, format!("{}{}", RECENT_BASELINE_PREFIX, entry.id), "Set as Baseline", true, None::<&str>)?;
+        let compare_i = MenuItem::with_id(app, format!("{}{}", RECENT_COMPARE_PREFIX, entry.id), "Compare with Baseline", true, None::<&str>)?;
+        item.append_items(&[&copy_i, &reveal_i, &recapture_i, &annotate_i, &baseline_i, &compare_i])?;
+        submenu.append(&item)?;
+    }
+
+    Ok(submenu)
+}
+
+/// Formats a history entry as "file name — app".
+fn recent_label(entry: &HistoryEntry) -> String {
+    let name = Path::new(&entry.path)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| entry.path.clone());
+
+    match &entry.app_name {
+        Some(app_name) => format!("{} — {}", name, app_name),
+        None => name,
+    }
+}
+
+/// Loads the entry's history thumbnail scaled down to menu icon size.
+fn load_thumbnail(entry: &HistoryEntry) -> Option<Image<'static>> {
+    let thumb = image::open(entry.thumbnail.as_ref()?)
+        .ok()?
+        .thumbnail(TRAY_THUMBNAIL_SIZE, TRAY_THUMBNAIL_SIZE)
+        .to_rgba8();
+    let (width, height) = thumb.dimensions();
+    Some(Image::new_owned(thumb.into_raw(), width, height))
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id.as_ref() {
+        "quit" => app.exit(0),
+        "snip" => start_capture_session(app, CaptureMode::Element),
+        "snip_scroll" => start_capture_session(app, CaptureMode::ScrollArea),
+        "pick_window" => {
+            if let Err(e) = window_picker::open(app) {
+                log::error!("Failed to open the window picker: {}", e);
+            }
+        }
+        "snip_delayed" => start_capture_session(app, CaptureMode::Delayed),
+        "capture_cursor_delayed" => timed_capture::capture_after_delay(app, None),
+        "record" => recording::toggle(app),
+        "run_manifest" => {
+            let handle = app.clone();
+            app.dialog()
+                .file()
+                .add_filter("Screenshot Manifest", &["toml"])
+                .pick_file(move |path| {
+                    if let Some(path) = path.and_then(|p| p.into_path().ok()) {
+                        manifest::run_in_background(&handle, path);
+                    }
+                });
+        }
+        "grant_accessibility" => open_permission_settings(app, Permission::Accessibility),
+        "grant_screen_recording" => open_permission_settings(app, Permission::ScreenRecording),
+        "record_script" => script::toggle(app),
+        "replay_script" => {
+            let handle = app.clone();
+            app.dialog()
+                .file()
+                .add_filter("Interaction Script", &["yaml", "yml", "json"])
+                .pick_file(move |path| {
+                    if let Some(path) = path.and_then(|p| p.into_path().ok()) {
+                        script::replay_in_background(&handle, path);
+                    }
+                });
+        }
+        "export_script" => export_script(app),
+        "recapture_last" => {
+            let app = app.clone();
+            thread::spawn(move || {
+                if let Err(e) = baseline::recapture_last_and_compare(&app) {
+                    capture::report_error(&app, "Re-capture failed", &e);
+                }
+            });
+        }
+        "copy_to_clipboard" => update_settings(app, |s| s.copy_to_clipboard = !s.copy_to_clipboard),
+        "overwrite_recapture" => update_settings(app, |s| s.overwrite_recapture = !s.overwrite_recapture),
+        "freeze_frame" => update_settings(app, |s| s.freeze_frame = !s.freeze_frame),
+        "redact" => toggle_redaction(app),
+        "inspection_server" => toggle_server(app),
+        "copy_server_url" => {
+            let copied = server::connection_url(app)
+                .and_then(|url| arboard::Clipboard::new().and_then(|mut c| c.set_text(url)).map_err(|e| e.to_string()));
+            if let Err(e) = copied {
+                log::error!("Failed to copy the inspection server URL: {}", e);
+            }
+        }
+        id => {
+            if let Some(secs) = id.strip_prefix(DELAY_PREFIX).and_then(|n| n.parse::<u64>().ok()) {
+                update_settings(app, |s| s.capture_delay_secs = secs);
+            } else if let Some(fps) = id.strip_prefix(FPS_PREFIX).and_then(|n| n.parse::<u32>().ok()) {
+                update_settings(app, |s| s.recording_fps = fps);
+            } else if let Some(format) = id.strip_prefix(COPY_FORMAT_PREFIX).and_then(|f| f.parse::<ElementFormat>().ok()) {
+                update_settings(app, |s| s.copy_format = format);
+            } else {
+                handle_recent_event(app, id);
+            }
+        }
+    }
+}
+
+/// Asks for a script, then for where to write it as XCUITest or Appium code.
+fn export_script(app: &AppHandle) {
+    let handle = app.clone();
+    app.dialog()
+        .file()
+        .add_filter("Interaction Script", &["yaml", "yml", "json"])
+        .pick_file(move |path| {
+            let Some(path) = path.and_then(|p| p.into_path().ok()) else {
+                return;
+            };
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("interaction").to_string();
+            handle.dialog()
+                .file()
+                .add_filter("XCUITest", &["swift"])
+                .add_filter("Appium (Python)", &["py"])
+                .set_file_name(format!("{}.swift", stem))
+                .save_file(move |output| {
+                    let Some(output) = output.and_then(|p| p.into_path().ok()) else {
+                        return;
+                    };
+                    let exported = script::Script::load(&path).and_then(|s| script::export_to_file(&s, &output));
+                    if let Err(e) = exported {
+                        log::error!("Failed to export {}: {}", path.display(), e);
+                    }
+                });
+        });
+}
+
+fn open_permission_settings(app: &AppHandle, permission: Permission) {
+    if let Err(e) = permissions::open_settings(app, permission) {
+        log::error!("Failed to open System Settings: {}", e);
+    }
+}
+
+fn update_settings(app: &AppHandle, change: impl FnOnce(&mut settings::Settings)) {
+    if let Err(e) = settings::update(app, change) {
+        log::error!("Failed to update settings: {}", e);
+    }
+}
+
+fn toggle_server(app: &AppHandle) {
+    let enable = !app.state::<AppState>().server.is_running();
+    if enable {
+        if let Err(e) = server::start(app) {
+            log::error!("Failed to start the inspection server: {}", e);
+        }
+    } else {
+        server::stop(app);
+    }
+    let running = app.state::<AppState>().server.is_running();
+    update_settings(app, |s| s.inspection_server = running);
+}
+
+fn toggle_redaction(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let Ok(mut config) = state.redaction.lock() else {
+        return;
+    };
+    config.enabled = !config.enabled;
+    if let Ok(dir) = app.path().app_config_dir() {
+        if let Err(e) = config.save(&dir) {
+            log::warn!("Failed to save redaction config: {}", e);
+        }
+    }
+}
+
+fn handle_recent_event(app: &AppHandle, id: &str) {
+    let parse = |prefix: &str| id.strip_prefix(prefix).and_then(|n| n.parse::<u64>().ok());
+
+    let (action, entry_id) = if let Some(n) = parse(RECENT_COPY_PREFIX) {
+        (RECENT_COPY_PREFIX, n)
+    } else if let Some(n) = parse(RECENT_REVEAL_PREFIX) {
+        (RECENT_REVEAL_PREFIX, n)
+    } else if let Some(n) = parse(RECENT_RECAPTURE_PREFIX) {
+        (RECENT_RECAPTURE_PREFIX, n)
+    } else if let Some(n) = parse(RECENT_ANNOTATE_PREFIX) {
+        (RECENT_ANNOTATE_PREFIX, n)
+    } else if let Some(n) = parse(RECENT_BASELINE_PREFIX) {
+        (RECENT_BASELINE_PREFIX, n)
+    } else if let Some(n) = parse(RECENT_COMPARE_PREFIX) {
+        (RECENT_COMPARE_PREFIX, n)
+    } else {
+        return;
+    };
+
+    let Some(entry) = app.state::<AppState>().history.lock().ok().and_then(|h| h.get(entry_id).cloned()) else {
+        return;
+    };
+
+    let app = app.clone();
+    thread::spawn(move || {
+        let (context, result) = match action {
+            RECENT_COPY_PREFIX => ("Copy failed", clipboard::copy_file(Path::new(&entry.path))),
+            RECENT_REVEAL_PREFIX => ("Reveal failed", app.opener().reveal_item_in_dir(&entry.path).map_err(|e| e.to_string().into())),
+            RECENT_ANNOTATE_PREFIX => ("Opening the annotation editor failed", annotation::open_editor(&app, &entry.path).map_err(CaptureError::from)),
+            RECENT_BASELINE_PREFIX => ("Setting the baseline failed", baseline::set_baseline(&app, &entry).map_err(CaptureError::from)),
+            RECENT_COMPARE_PREFIX => (
+                "Comparison failed",
+                baseline::compare_with_baseline(&app, &entry, &entry.path, &DiffOptions::default())
+                    .and_then(|report| report.map(|_| ()).ok_or_else(|| "This element has no baseline".to_string()))
+                    .map_err(CaptureError::from),
+            ),
+            _ => ("Re-capture failed", baseline::recapture_and_compare(&app, &entry).map(|_| ())),
+        };
+        if let Err(e) = result {
+            capture::report_error(&app, context, &e);
+        }
+    });
+}
@@ -0,0 +1,59 @@
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use crate::accessibility::{self, UIElementInfo};
+use crate::capture::CaptureMode;
+use crate::constants::{ACCESSIBILITY_RECURSION_LIMIT, WINDOW_HIDE_DELAY_MS, WINDOW_LABEL_PICKER};
+use crate::window_list::{self, AppWindows};
+use crate::{actions, start_scoped_capture_session, tree};
+
+/// Opens the window picker, or brings it to the front if it is already open.
+pub fn open(app: &AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL_PICKER) {
+        return window.set_focus().map_err(|e| e.to_string());
+    }
+
+    WebviewWindowBuilder::new(app, WINDOW_LABEL_PICKER, WebviewUrl::App("index.html?picker".into()))
+        .title("xray — Windows")
+        .inner_size(480.0, 600.0)
+        .build()
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Lists the on-screen windows grouped by app, keeping those matching `query`.
+#[tauri::command]
+pub fn list_app_windows(query: Option<String>) -> Vec<AppWindows> {
+    window_list::list_app_windows(query.as_deref().unwrap_or(""))
+}
+
+/// Describes the window with window server number `window_id` for capturing it.
+#[tauri::command]
+pub fn get_window_element(window_id: u32) -> Result<UIElementInfo, String> {
+    accessibility::get_window(window_id).ok_or_else(|| format!("Window {} is no longer on screen", window_id))
+}
+
+/// Writes the accessibility tree of the window with window server number `window_id` to `path` as JSON.
+#[tauri::command(async)]
+pub fn export_window_tree(window_id: u32, path: String) -> Result<(), String> {
+    let root = tree::snapshot_window(window_id, ACCESSIBILITY_RECURSION_LIMIT as usize)
+        .ok_or_else(|| format!("Window {} cannot be inspected", window_id))?;
+    let json = serde_json::to_string_pretty(&root).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| e.to_string())
+}
+
+/// Brings the window to the front and starts an element capture session limited to it.
+#[tauri::command]
+pub fn inspect_window(window_id: u32, app: AppHandle) -> Result<(), String> {
+    actions::raise_window(window_id)?;
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL_PICKER) {
+        let _ = window.close();
+    }
+
+    // Let the picker close and the raised window come forward before the screen is frozen.
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(WINDOW_HIDE_DELAY_MS));
+        start_scoped_capture_session(&app, CaptureMode::Element, Some(window_id));
+    });
+    Ok(())
+}
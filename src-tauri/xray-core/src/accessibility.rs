@@ -0,0 +1,641 @@
+use crate::app_info::{self, AppInfo};
+use crate::constants::{ax_attributes, ax_roles, ACCESSIBILITY_RECURSION_LIMIT, WINDOW_DETAILS_REFRESH_MS};
+use crate::locator::{self, ElementLocator};
+use crate::window_list::{self, WindowInfo};
+use crate::tree;
+use accessibility_sys::{
+    kAXErrorSuccess, AXUIElementCopyAttributeValue, AXUIElementCopyElementAtPosition,
+    AXUIElementCreateSystemWide, AXUIElementGetPid, AXUIElementRef, AXUIElementSetAttributeValue,
+    AXValueGetValue, AXValueRef,
+};
+use core_foundation::base::TCFType;
+use core_foundation::number::CFNumber;
+use core_foundation::string::CFString;
+use core_graphics::geometry::{CGPoint, CGSize};
+use std::ffi::c_void;
+use std::ptr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Represents the geometry and metadata of a UI element found via accessibility APIs.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UIElementInfo {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub global_x: f64,
+    pub global_y: f64,
+    pub window_id: u32,
+    pub role: String,
+    /// The element's `AXTitle`, falling back to `AXDescription`.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// A stable description of where the element lives in its app's tree.
+    /// Only computed when the element is captured, not on every hover.
+    #[serde(default)]
+    pub locator: Option<ElementLocator>,
+    /// The process id of the app owning the element.
+    #[serde(default)]
+    pub pid: i32,
+    #[serde(default)]
+    pub app_name: Option<String>,
+    #[serde(default)]
+    pub bundle_id: Option<String>,
+    #[serde(default)]
+    pub executable_path: Option<String>,
+    /// The title of the window `window_id` as the window server reports it.
+    /// Only filled in for elements found by hit testing, not for tree snapshots.
+    #[serde(default)]
+    pub window_title: Option<String>,
+    #[serde(default)]
+    pub window_layer: Option<i32>,
+}
+
+impl UIElementInfo {
+    /// The owning app, if its pid is known.
+    pub fn app(&self) -> Option<AppInfo> {
+        (self.pid > 0).then(|| AppInfo {
+            pid: self.pid,
+            name: self.app_name.clone().unwrap_or_default(),
+            bundle_id: self.bundle_id.clone(),
+            executable_path: self.executable_path.clone(),
+        })
+    }
+
+    /// Fills in `window_title` and `window_layer` from the window list.
+    pub fn add_window_details(&mut self) {
+        if let Some(window) = find_window_cached(self.window_id) {
+            self.window_title = Some(window.title).filter(|t| !t.is_empty());
+            self.window_layer = Some(window.layer);
+        }
+    }
+}
+
+/// Looks up a window like [`window_list::find_window`], reusing the last answer while it's
+/// for the same window and fresh, since hover polling asks about the same window every tick.
+fn find_window_cached(window_id: u32) -> Option<WindowInfo> {
+    static LAST: Mutex<Option<(u32, Instant, Option<WindowInfo>)>> = Mutex::new(None);
+    let Ok(mut last) = LAST.lock() else {
+        return window_list::find_window(window_id);
+    };
+    if let Some((id, at, window)) = last.as_ref() {
+        if *id == window_id && at.elapsed() < Duration::from_millis(WINDOW_DETAILS_REFRESH_MS) {
+            return window.clone();
+        }
+    }
+    let window = window_list::find_window(window_id);
+    *last = Some((window_id, Instant::now(), window.clone()));
+    window
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGEventSourceButtonState(stateID: u32, button: u32) -> bool;
+    fn CGEventSourceFlagsState(stateID: u32) -> u64;
+}
+
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    /// A private macOS API to get the window ID from an AXElement.
+    fn _AXUIElementGetWindow(element: AXUIElementRef, id: *mut u32) -> i32;
+}
+
+#[link(name = "CoreFoundation", kind = "framework")]
+extern "C" {
+    fn CFArrayGetCount(theArray: *const c_void) -> isize;
+    fn CFArrayGetValueAtIndex(theArray: *const c_void, idx: isize) -> *const c_void;
+}
+
+/// Checks if the left mouse button is currently pressed.
+///
+/// Uses `CGEventSourceButtonState` to query the HID system state.
+pub fn is_mouse_left_down() -> bool {
+    unsafe {
+        // kCGEventSourceStateHIDSystemState = 1, kCGMouseButtonLeft = 0
+        CGEventSourceButtonState(1, 0)
+    }
+}
+
+/// Checks if an Option key is currently held down.
+pub fn is_option_key_down() -> bool {
+    // kCGEventSourceStateHIDSystemState = 1, kCGEventFlagMaskAlternate = 0x80000
+    unsafe { CGEventSourceFlagsState(1) & 0x80000 != 0 }
+}
+
+/// Finds the UI element at the current mouse cursor position.
+///
+/// This function performs the following steps:
+/// 1. Gets the current mouse location.
+/// 2. Queries the system-wide accessibility object for the element at that location.
+/// 3. Drills down into the element hierarchy to find the most specific leaf node.
+/// 4. Extracts position, size, and role information.
+pub fn get_element_at_mouse() -> Option<UIElementInfo> {
+    get_element_at_mouse_with(DrillDown::Deepest, false)
+}
+
+/// Like [`get_element_at_mouse`], but also builds the element's [`ElementLocator`].
+///
+/// Building the locator walks the whole parent chain, so this is meant for the
+/// moment of capture rather than for hover polling.
+pub fn get_located_element_at_mouse() -> Option<UIElementInfo> {
+    get_element_at_mouse_with(DrillDown::Deepest, true)
+}
+
+/// How far hit testing descends below the element the system reports at a point.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DrillDown {
+    /// Descend past containers to the most specific element under the point.
+    #[default]
+    Deepest,
+    /// Keep the element the system hit test reports.
+    HitTest,
+}
+
+/// Finds the UI element under the mouse cursor with the given drill-down policy.
+pub fn get_element_at_mouse_with(drill_down: DrillDown, with_locator: bool) -> Option<UIElementInfo> {
+    let mouse_loc = mouse_location()?;
+    get_element_at_with(mouse_loc.x, mouse_loc.y, drill_down, with_locator)
+}
+
+/// Returns the current mouse position in global screen coordinates.
+pub fn mouse_location() -> Option<CGPoint> {
+    let source = core_graphics::event_source::CGEventSource::new(
+        core_graphics::event_source::CGEventSourceStateID::HIDSystemState,
+    )
+    .ok()?;
+    let event = core_graphics::event::CGEvent::new(source).ok()?;
+    Some(event.location())
+}
+
+/// Finds the most specific UI element at a global screen position, like the hover does.
+///
+/// With `with_locator`, the element's [`ElementLocator`] is built as well.
+pub fn get_element_at(x: f64, y: f64, with_locator: bool) -> Option<UIElementInfo> {
+    get_element_at_with(x, y, DrillDown::Deepest, with_locator)
+}
+
+/// Finds the UI element at a global screen position with the given drill-down policy.
+pub fn get_element_at_with(x: f64, y: f64, drill_down: DrillDown, with_locator: bool) -> Option<UIElementInfo> {
+    unsafe {
+        let element_ref = match drill_down {
+            DrillDown::Deepest => copy_deepest_element_at(x, y)?,
+            DrillDown::HitTest => copy_element_at_position(x, y)?,
+        };
+        let info = element_info(element_ref).map(|mut info| {
+            if with_locator {
+                info.locator = Some(locator::build(element_ref));
+            }
+            info.add_window_details();
+            info
+        });
+
+        core_foundation::base::CFRelease(element_ref as *const c_void);
+        info
+    }
+}
+
+/// Finds the window containing the element at a global screen position, with its locator.
+pub fn get_window_at(x: f64, y: f64) -> Option<UIElementInfo> {
+    get_related_element_at(x, y, ax_attributes::WINDOW)
+}
+
+/// Describes the window with window server number `window_id`, with its locator.
+///
+/// Windows the app doesn't expose to accessibility are described from the
+/// window list alone, without a locator.
+pub fn get_window(window_id: u32) -> Option<UIElementInfo> {
+    let window = window_list::find_window(window_id)?;
+    if let Some(element) = tree::copy_window(window_id) {
+        let info = unsafe {
+            let info = element_info(element).map(|mut info| {
+                info.locator = Some(locator::build(element));
+                info
+            });
+            core_foundation::base::CFRelease(element as *const c_void);
+            info
+        };
+        if let Some(mut info) = info {
+            info.add_window_details();
+            return Some(info);
+        }
+    }
+
+    let app = app_info::app_info(window.owner_pid).unwrap_or_default();
+    Some(UIElementInfo {
+        x: window.x,
+        y: window.y,
+        width: window.width,
+        height: window.height,
+        global_x: window.x,
+        global_y: window.y,
+        window_id,
+        role: ax_roles::WINDOW.to_string(),
+        title: Some(window.title.clone()).filter(|t| !t.is_empty()),
+        locator: None,
+        pid: window.owner_pid,
+        app_name: Some(app.name).filter(|n| !n.is_empty()).or(Some(window.owner_name)),
+        bundle_id: app.bundle_id,
+        executable_path: app.executable_path,
+        window_title: Some(window.title).filter(|t| !t.is_empty()),
+        window_layer: Some(window.layer),
+    })
+}
+
+/// Finds the parent of the element at a global screen position, with its locator.
+pub fn get_parent_at(x: f64, y: f64) -> Option<UIElementInfo> {
+    get_related_element_at(x, y, ax_attributes::PARENT)
+}
+
+/// Follows an element-valued `attribute` from the element at a point.
+fn get_related_element_at(x: f64, y: f64, attribute: &str) -> Option<UIElementInfo> {
+    unsafe {
+        let element = copy_deepest_element_at(x, y)?;
+        let related = copy_element_attribute(element, attribute);
+        core_foundation::base::CFRelease(element as *const c_void);
+        let related = related?;
+        let info = element_info(related).map(|mut info| {
+            info.locator = Some(locator::build(related));
+            info.add_window_details();
+            info
+        });
+        core_foundation::base::CFRelease(related as *const c_void);
+        info
+    }
+}
+
+/// Reads position, size, role, title, window id and owning app of `element` into a [`UIElementInfo`].
+///
+/// Both `x/y` and `global_x/global_y` are set to the global position; callers
+/// that display the element translate `x/y` into their own coordinate space.
+///
+/// # Safety
+/// `element` must be a valid accessibility element.
+pub unsafe fn element_info(element: AXUIElementRef) -> Option<UIElementInfo> {
+    let (x, y) = get_position(element)?;
+    let (width, height) = get_size(element)?;
+    let role = get_role(element).unwrap_or_else(|| "Unknown".to_string());
+    let title = get_title(element);
+
+    let mut window_id: u32 = 0;
+    let _ = _AXUIElementGetWindow(element, &mut window_id);
+    let pid = get_pid(element);
+    let app = app_info::app_info(pid).unwrap_or_default();
+
+    Some(UIElementInfo {
+        x,
+        y,
+        width,
+        height,
+        global_x: x,
+        global_y: y,
+        window_id,
+        role,
+        title,
+        locator: None,
+        pid,
+        app_name: Some(app.name).filter(|n| !n.is_empty()),
+        bundle_id: app.bundle_id,
+        executable_path: app.executable_path,
+        window_title: None,
+        window_layer: None,
+    })
+}
+
+/// Drills down into a container element to find a more specific child under the mouse coordinates.
+unsafe fn drill_down(element: AXUIElementRef, mx: f64, my: f64) -> Option<AXUIElementRef> {
+    let attr_name = CFString::new(ax_attributes::CHILDREN);
+    let mut value_ref: *const c_void = ptr::null();
+
+    let result =
+        AXUIElementCopyAttributeValue(element, attr_name.as_concrete_TypeRef(), &mut value_ref);
+
+    if result == kAXErrorSuccess && !value_ref.is_null() {
+        let count = CFArrayGetCount(value_ref);
+
+        let mut best_child: Option<AXUIElementRef> = None;
+        let mut min_area = f64::MAX;
+
+        // Iterate through all children to find the best fit.
+        for i in 0..count {
+            let child_ptr = CFArrayGetValueAtIndex(value_ref, i) as AXUIElementRef;
+
+            if let (Some((cx, cy)), Some((cw, ch))) = (get_position(child_ptr), get_size(child_ptr))
+            {
+                // Hit Test: Check if mouse is within bounds
+                if mx >= cx && mx < cx + cw && my >= cy && my < cy + ch {
+                    let area = cw * ch;
+                    let mut should_update = false;
+
+                    // Strategy: Prefer smaller areas (more specific elements)
+                    if area < min_area {
+                        should_update = true;
+                    }
+                    // Tie-breaking: If areas are similar, use role priority
+                    else if (area - min_area).abs() < f64::EPSILON {
+                        should_update = should_update_based_on_role(best_child, child_ptr);
+                    }
+
+                    if should_update {
+                        min_area = area;
+                        best_child = Some(child_ptr);
+                    }
+                }
+            }
+        }
+
+        // Retain the best child before releasing the array
+        if let Some(child) = best_child {
+            core_foundation::base::CFRetain(child as *const c_void);
+            core_foundation::base::CFRelease(value_ref);
+            return Some(child);
+        }
+
+        core_foundation::base::CFRelease(value_ref);
+    }
+    None
+}
+
+/// Helper function to decide if we should switch to the new child based on AXRole priority.
+unsafe fn should_update_based_on_role(
+    current_best: Option<AXUIElementRef>,
+    new_candidate: AXUIElementRef,
+) -> bool {
+    let Some(best) = current_best else {
+        return true;
+    };
+
+    let best_role = get_role(best).unwrap_or_default();
+    let new_role = get_role(new_candidate).unwrap_or_default();
+
+    // Tier 1: Visual final elements (Images, Checkboxes)
+    let is_tier_1 = |r: &str| {
+        matches!(
+            r,
+            ax_roles::IMAGE | ax_roles::CHECKBOX | ax_roles::RADIO_BUTTON
+        )
+    };
+
+    // Tier 2: Text or Interactive elements (Text, Buttons)
+    let is_tier_2 = |r: &str| {
+        matches!(
+            r,
+            ax_roles::STATIC_TEXT | ax_roles::HEADING | ax_roles::BUTTON
+        )
+    };
+
+    // Tier 3: Containers (Groups, Areas)
+    let is_tier_3 = |r: &str| {
+        matches!(
+            r,
+            ax_roles::LINK | ax_roles::GROUP | ax_roles::WEB_AREA | ax_roles::SCROLL_AREA
+        )
+    };
+
+    if is_tier_1(&new_role) && !is_tier_1(&best_role) {
+        return true;
+    }
+    if is_tier_2(&new_role) && is_tier_3(&best_role) {
+        return true;
+    }
+    if is_tier_1(&new_role) && is_tier_1(&best_role) {
+        return true;
+    }
+
+    false
+}
+
+/// Returns the `AXRole` of an AX element.
+///
+/// # Safety
+/// `element` must be a valid accessibility element.
+pub unsafe fn get_role(element: AXUIElementRef) -> Option<String> {
+    get_string_attribute(element, ax_attributes::ROLE)
+}
+
+/// Returns the element's title, or its description when the title is empty.
+unsafe fn get_title(element: AXUIElementRef) -> Option<String> {
+    get_string_attribute(element, ax_attributes::TITLE)
+        .filter(|s| !s.is_empty())
+        .or_else(|| get_string_attribute(element, ax_attributes::DESCRIPTION))
+        .filter(|s| !s.is_empty())
+}
+
+/// Returns the global position of an AX element in points.
+///
+/// # Safety
+/// `element` must be a valid accessibility element.
+pub unsafe fn get_position(element: AXUIElementRef) -> Option<(f64, f64)> {
+    let attr_name = CFString::new(ax_attributes::POSITION);
+    let mut value_ref: *const c_void = ptr::null();
+
+    let result =
+        AXUIElementCopyAttributeValue(element, attr_name.as_concrete_TypeRef(), &mut value_ref);
+
+    if result == kAXErrorSuccess && !value_ref.is_null() {
+        let val = value_ref as AXValueRef;
+        let mut point = CGPoint::default();
+        // kAXValueCGPointType = 1
+        let success = AXValueGetValue(val, 1, &mut point as *mut _ as *mut c_void);
+        core_foundation::base::CFRelease(value_ref);
+        if success {
+            return Some((point.x, point.y));
+        }
+    }
+    None
+}
+
+/// Returns the size of an AX element in points.
+///
+/// # Safety
+/// `element` must be a valid accessibility element.
+pub unsafe fn get_size(element: AXUIElementRef) -> Option<(f64, f64)> {
+    let attr_name = CFString::new(ax_attributes::SIZE);
+    let mut value_ref: *const c_void = ptr::null();
+
+    let result =
+        AXUIElementCopyAttributeValue(element, attr_name.as_concrete_TypeRef(), &mut value_ref);
+
+    if result == kAXErrorSuccess && !value_ref.is_null() {
+        let val = value_ref as AXValueRef;
+        let mut size = CGSize::default();
+        // kAXValueCGSizeType = 2
+        let success = AXValueGetValue(val, 2, &mut size as *mut _ as *mut c_void);
+        core_foundation::base::CFRelease(value_ref);
+        if success {
+            return Some((size.width, size.height));
+        }
+    }
+    None
+}
+
+/// Helper to get a string attribute from an AX element.
+///
+/// # Safety
+/// `element` must be a valid accessibility element.
+pub unsafe fn get_string_attribute(element: AXUIElementRef, attribute: &str) -> Option<String> {
+    let attr_name = CFString::new(attribute);
+    let mut value_ref: *const c_void = ptr::null();
+
+    let result =
+        AXUIElementCopyAttributeValue(element, attr_name.as_concrete_TypeRef(), &mut value_ref);
+
+    if result == kAXErrorSuccess && !value_ref.is_null() {
+        // Attributes like `AXValue` are not always strings.
+        let value = core_foundation::base::CFType::wrap_under_create_rule(value_ref);
+        return value.downcast::<CFString>().map(|s| s.to_string());
+    }
+    None
+}
+
+/// Returns the process id of the application owning `element`, or 0 if unknown.
+///
+/// # Safety
+/// `element` must be a valid accessibility element.
+pub unsafe fn get_pid(element: AXUIElementRef) -> i32 {
+    let mut pid: i32 = 0;
+    if AXUIElementGetPid(element, &mut pid) != kAXErrorSuccess {
+        return 0;
+    }
+    pid
+}
+
+/// Helper to get an element-valued attribute (e.g. `AXParent`) from an AX element.
+///
+/// The returned element is retained and must be released by the caller.
+///
+/// # Safety
+/// `element` must be a valid accessibility element.
+pub unsafe fn copy_element_attribute(
+    element: AXUIElementRef,
+    attribute: &str,
+) -> Option<AXUIElementRef> {
+    let attr_name = CFString::new(attribute);
+    let mut value_ref: *const c_void = ptr::null();
+
+    let result =
+        AXUIElementCopyAttributeValue(element, attr_name.as_concrete_TypeRef(), &mut value_ref);
+
+    if result == kAXErrorSuccess && !value_ref.is_null() {
+        return Some(value_ref as AXUIElementRef);
+    }
+    None
+}
+
+/// Returns the children of an AX element.
+///
+/// Every returned element is retained and must be released by the caller,
+/// e.g. with [`release_all`].
+///
+/// # Safety
+/// `element` must be a valid accessibility element.
+pub unsafe fn copy_children(element: AXUIElementRef) -> Vec<AXUIElementRef> {
+    let attr_name = CFString::new(ax_attributes::CHILDREN);
+    let mut value_ref: *const c_void = ptr::null();
+
+    let result =
+        AXUIElementCopyAttributeValue(element, attr_name.as_concrete_TypeRef(), &mut value_ref);
+
+    if result != kAXErrorSuccess || value_ref.is_null() {
+        return Vec::new();
+    }
+
+    let count = CFArrayGetCount(value_ref);
+    let children = (0..count)
+        .map(|i| {
+            let child = CFArrayGetValueAtIndex(value_ref, i);
+            core_foundation::base::CFRetain(child);
+            child as AXUIElementRef
+        })
+        .collect();
+
+    core_foundation::base::CFRelease(value_ref);
+    children
+}
+
+/// Releases every element in `elements`.
+///
+/// # Safety
+/// Every element must be a valid accessibility element retained by the caller,
+/// and none of them may be used afterwards.
+pub unsafe fn release_all(elements: Vec<AXUIElementRef>) {
+    for element in elements {
+        core_foundation::base::CFRelease(element as *const c_void);
+    }
+}
+
+/// Returns the most specific element at a global screen position, drilling down
+/// past containers that report a larger element than the one visible there.
+///
+/// The returned element is retained and must be released by the caller.
+///
+/// # Safety
+/// The returned element must be released exactly once.
+pub unsafe fn copy_deepest_element_at(x: f64, y: f64) -> Option<AXUIElementRef> {
+    let mut element_ref = copy_element_at_position(x, y)?;
+
+    // Deep Drill Down: Search as deep as possible to find leaf nodes like <img>.
+    for _ in 0..ACCESSIBILITY_RECURSION_LIMIT {
+        if let Some(child) = drill_down(element_ref, x, y) {
+            // Release the parent and move to the child.
+            core_foundation::base::CFRelease(element_ref as *const c_void);
+            element_ref = child;
+        } else {
+            break; // No more children found, stop recursion.
+        }
+    }
+    Some(element_ref)
+}
+
+/// Returns the element at a global screen position without drilling down.
+///
+/// The returned element is retained and must be released by the caller.
+///
+/// # Safety
+/// The returned element must be released exactly once.
+pub unsafe fn copy_element_at_position(x: f64, y: f64) -> Option<AXUIElementRef> {
+    let system_wide = AXUIElementCreateSystemWide();
+    if system_wide.is_null() {
+        return None;
+    }
+
+    let mut element_ref: AXUIElementRef = ptr::null_mut();
+    let result = AXUIElementCopyElementAtPosition(system_wide, x as f32, y as f32, &mut element_ref);
+    core_foundation::base::CFRelease(system_wide as *const c_void);
+
+    if result == kAXErrorSuccess && !element_ref.is_null() {
+        return Some(element_ref);
+    }
+    None
+}
+
+/// Helper to get a numeric attribute (e.g. a scroll bar's `AXValue`) from an AX element.
+///
+/// # Safety
+/// `element` must be a valid accessibility element.
+pub unsafe fn get_number_attribute(element: AXUIElementRef, attribute: &str) -> Option<f64> {
+    let attr_name = CFString::new(attribute);
+    let mut value_ref: *const c_void = ptr::null();
+
+    let result =
+        AXUIElementCopyAttributeValue(element, attr_name.as_concrete_TypeRef(), &mut value_ref);
+
+    if result == kAXErrorSuccess && !value_ref.is_null() {
+        let number = core_foundation::base::CFType::wrap_under_create_rule(value_ref);
+        return number.downcast::<CFNumber>().and_then(|n| n.to_f64());
+    }
+    None
+}
+
+/// Helper to set a numeric attribute on an AX element. Returns whether the app accepted it.
+///
+/// # Safety
+/// `element` must be a valid accessibility element.
+pub unsafe fn set_number_attribute(element: AXUIElementRef, attribute: &str, value: f64) -> bool {
+    let attr_name = CFString::new(attribute);
+    let number = CFNumber::from(value);
+    AXUIElementSetAttributeValue(element, attr_name.as_concrete_TypeRef(), number.as_CFTypeRef())
+        == kAXErrorSuccess
+}
@@ -0,0 +1,130 @@
+use std::ffi::c_void;
+use std::ptr;
+use accessibility_sys::{
+    kAXErrorAPIDisabled, kAXErrorActionUnsupported, kAXErrorAttributeUnsupported, kAXErrorCannotComplete,
+    kAXErrorInvalidUIElement, kAXErrorSuccess, AXError, AXUIElementCopyActionNames, AXUIElementPerformAction,
+    AXUIElementCreateApplication, AXUIElementRef, AXUIElementSetAttributeValue,
+};
+use core_foundation::array::{CFArray, CFArrayRef};
+use core_foundation::base::{CFType, TCFType};
+use core_foundation::boolean::CFBoolean;
+use core_foundation::number::CFNumber;
+use core_foundation::string::CFString;
+use crate::accessibility::{copy_deepest_element_at, copy_element_attribute, get_pid, get_position, get_size, UIElementInfo};
+use crate::constants::{ax_actions, ax_attributes, ACCESSIBILITY_RECURSION_LIMIT};
+use crate::{locator, tree};
+
+/// A value to write to an element attribute, e.g. `"hello"` for `AXValue` or `true` for `AXFocused`.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum AttributeValue {
+    Bool(bool),
+    Number(f64),
+    Text(String),
+}
+
+/// Lists the actions `element` supports, such as `AXPress` or `AXShowMenu`.
+pub fn list_actions(element: &UIElementInfo) -> Result<Vec<String>, String> {
+    with_element(element, |element| unsafe {
+        let mut names: CFArrayRef = ptr::null();
+        check(AXUIElementCopyActionNames(element, &mut names), "Listing actions")?;
+        if names.is_null() {
+            return Ok(Vec::new());
+        }
+        let names = CFArray::<CFString>::wrap_under_create_rule(names);
+        Ok(names.iter().map(|name| name.to_string()).collect())
+    })
+}
+
+/// Performs `action` (e.g. `AXPress`) on `element`.
+pub fn perform_action(element: &UIElementInfo, action: &str) -> Result<(), String> {
+    with_element(element, |element| unsafe {
+        let name = CFString::new(action);
+        check(AXUIElementPerformAction(element, name.as_concrete_TypeRef()), action)
+    })
+}
+
+/// Writes `value` to `attribute` of `element`, e.g. `AXValue` of a text field or `AXFocused`.
+pub fn set_attribute(element: &UIElementInfo, attribute: &str, value: &AttributeValue) -> Result<(), String> {
+    let value: CFType = match value {
+        AttributeValue::Bool(b) => CFBoolean::from(*b).as_CFType(),
+        AttributeValue::Number(n) => CFNumber::from(*n).as_CFType(),
+        AttributeValue::Text(s) => CFString::new(s).as_CFType(),
+    };
+    with_element(element, |element| unsafe {
+        let name = CFString::new(attribute);
+        check(AXUIElementSetAttributeValue(element, name.as_concrete_TypeRef(), value.as_CFTypeRef()), attribute)
+    })
+}
+
+/// Brings the window with window server number `window_id` and its app to the front.
+pub fn raise_window(window_id: u32) -> Result<(), String> {
+    let window = tree::copy_window(window_id).ok_or_else(|| format!("Window {} cannot be inspected", window_id))?;
+    unsafe {
+        let pid = get_pid(window);
+        let raised = check(AXUIElementPerformAction(window, CFString::new(ax_actions::RAISE).as_concrete_TypeRef()), ax_actions::RAISE);
+        core_foundation::base::CFRelease(window as *const c_void);
+        raised?;
+
+        let app = AXUIElementCreateApplication(pid);
+        let name = CFString::new(ax_attributes::FRONTMOST);
+        let result = AXUIElementSetAttributeValue(app, name.as_concrete_TypeRef(), CFBoolean::true_value().as_CFTypeRef());
+        core_foundation::base::CFRelease(app as *const c_void);
+        check(result, ax_attributes::FRONTMOST)
+    }
+}
+
+/// Finds the live element `info` describes: through its locator when it has one,
+/// otherwise by hit-testing the center of its rect.
+///
+/// The returned element is retained and must be released by the caller.
+pub fn copy_element(info: &UIElementInfo) -> Option<AXUIElementRef> {
+    unsafe {
+        if let Some(element) = info.locator.as_ref().and_then(|l| locator::copy_resolved(l)) {
+            return Some(element);
+        }
+
+        let mut element = copy_deepest_element_at(info.global_x + info.width / 2.0, info.global_y + info.height / 2.0)?;
+        // The hit test may land on a descendant, so climb to the element with the same rect.
+        for _ in 0..ACCESSIBILITY_RECURSION_LIMIT {
+            if has_rect(element, info) {
+                return Some(element);
+            }
+            let parent = copy_element_attribute(element, ax_attributes::PARENT);
+            core_foundation::base::CFRelease(element as *const c_void);
+            element = parent?;
+        }
+        core_foundation::base::CFRelease(element as *const c_void);
+        None
+    }
+}
+
+unsafe fn has_rect(element: AXUIElementRef, info: &UIElementInfo) -> bool {
+    let close = |a: f64, b: f64| (a - b).abs() < 1.0;
+    match (get_position(element), get_size(element)) {
+        (Some((x, y)), Some((width, height))) => {
+            close(x, info.global_x) && close(y, info.global_y) && close(width, info.width) && close(height, info.height)
+        }
+        _ => false,
+    }
+}
+
+fn with_element<T>(info: &UIElementInfo, f: impl FnOnce(AXUIElementRef) -> Result<T, String>) -> Result<T, String> {
+    let element = copy_element(info).ok_or_else(|| "The element no longer exists".to_string())?;
+    let result = f(element);
+    unsafe { core_foundation::base::CFRelease(element as *const c_void) };
+    result
+}
+
+/// Turns an accessibility error into a message naming what was attempted.
+#[allow(non_upper_case_globals)]
+fn check(result: AXError, what: &str) -> Result<(), String> {
+    match result {
+        kAXErrorSuccess => Ok(()),
+        kAXErrorActionUnsupported | kAXErrorAttributeUnsupported => Err(format!("{} is not supported by this element", what)),
+        kAXErrorInvalidUIElement => Err("The element no longer exists".to_string()),
+        kAXErrorAPIDisabled => Err("Accessibility access is not enabled".to_string()),
+        kAXErrorCannotComplete => Err(format!("{} failed: the app did not respond", what)),
+        code => Err(format!("{} failed with accessibility error {}", what, code)),
+    }
+}
@@ -0,0 +1,86 @@
+use core_foundation::bundle::CFBundle;
+use core_foundation::string::CFString;
+use core_foundation::url::CFURL;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+/// `PROC_PIDPATHINFO_MAXSIZE` from `<libproc.h>`.
+const PROC_PIDPATH_MAX: usize = 4 * 1024;
+
+extern "C" {
+    fn proc_pidpath(pid: i32, buffer: *mut c_void, buffersize: u32) -> i32;
+}
+
+/// The running app that owns an element.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppInfo {
+    pub pid: i32,
+    /// The bundle's display name, falling back to the executable's file name.
+    pub name: String,
+    pub bundle_id: Option<String>,
+    pub executable_path: Option<String>,
+}
+
+impl AppInfo {
+    /// Whether `filter` names this app, by name or bundle identifier, ignoring case.
+    pub fn matches(&self, filter: &str) -> bool {
+        self.name.eq_ignore_ascii_case(filter)
+            || self.bundle_id.as_deref().is_some_and(|id| id.eq_ignore_ascii_case(filter))
+    }
+}
+
+/// Looks up the app with process id `pid`.
+///
+/// Results are cached per pid, since hover polling asks for the same few apps over and over.
+/// The executable is checked on every lookup, so a pid reused by another process isn't
+/// reported as the app that had it before.
+pub fn app_info(pid: i32) -> Option<AppInfo> {
+    if pid <= 0 {
+        return None;
+    }
+    let executable = executable_path(pid)?;
+    static CACHE: OnceLock<Mutex<HashMap<i32, AppInfo>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(Default::default);
+    if let Some(info) = cache.lock().ok()?.get(&pid).filter(|info| info.executable_path.as_ref() == Some(&executable)) {
+        return Some(info.clone());
+    }
+
+    let info = read_app_info(pid, executable);
+    if let Ok(mut cache) = cache.lock() {
+        cache.insert(pid, info.clone());
+    }
+    Some(info)
+}
+
+fn read_app_info(pid: i32, executable: String) -> AppInfo {
+    let bundle = Path::new(&executable)
+        .ancestors()
+        .find(|p| p.extension().is_some_and(|e| e == "app"))
+        .and_then(|p| CFURL::from_path(p, true))
+        .and_then(CFBundle::new);
+
+    let plist = |key: &str| -> Option<String> {
+        let value = bundle.as_ref()?.info_dictionary().find(CFString::new(key))?.clone();
+        value.downcast::<CFString>().map(|s| s.to_string())
+    };
+
+    let name = plist("CFBundleDisplayName")
+        .or_else(|| plist("CFBundleName"))
+        .or_else(|| Path::new(&executable).file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_default();
+
+    AppInfo { pid, name, bundle_id: plist("CFBundleIdentifier"), executable_path: Some(executable) }
+}
+
+fn executable_path(pid: i32) -> Option<String> {
+    let mut buffer = vec![0u8; PROC_PIDPATH_MAX];
+    let len = unsafe { proc_pidpath(pid, buffer.as_mut_ptr() as *mut c_void, buffer.len() as u32) };
+    if len <= 0 {
+        return None;
+    }
+    buffer.truncate(len as usize);
+    String::from_utf8(buffer).ok()
+}
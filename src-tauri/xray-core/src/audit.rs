@@ -0,0 +1,80 @@
+use crate::constants::{ax_roles, AUDIT_MIN_TARGET_SIZE};
+use crate::tree::ElementNode;
+
+/// Roles users interact with, which need a label and a usable size.
+const INTERACTIVE_ROLES: &[&str] = &[
+    ax_roles::BUTTON,
+    ax_roles::CHECKBOX,
+    ax_roles::RADIO_BUTTON,
+    ax_roles::POP_UP_BUTTON,
+    ax_roles::MENU_BUTTON,
+    ax_roles::LINK,
+    ax_roles::TEXT_FIELD,
+    ax_roles::TEXT_AREA,
+    ax_roles::SLIDER,
+];
+
+/// What is wrong with an element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditIssueKind {
+    /// An interactive element or image has neither a title nor a description for VoiceOver.
+    MissingLabel,
+    /// An interactive element is smaller than the minimum target size.
+    SmallTarget,
+}
+
+/// An accessibility problem found by [`audit`].
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditIssue {
+    pub kind: AuditIssueKind,
+    pub role: String,
+    pub title: Option<String>,
+    /// Roles from the snapshot root down to the element, e.g. `AXWindow > AXGroup > AXButton`.
+    pub path: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Checks every element of a tree snapshot for missing labels and undersized targets.
+pub fn audit(root: &ElementNode) -> Vec<AuditIssue> {
+    let mut issues = Vec::new();
+    let mut path = Vec::new();
+    visit(root, &mut path, &mut issues);
+    issues
+}
+
+fn visit<'a>(node: &'a ElementNode, path: &mut Vec<&'a str>, issues: &mut Vec<AuditIssue>) {
+    let info = &node.info;
+    path.push(&info.role);
+
+    let interactive = INTERACTIVE_ROLES.contains(&info.role.as_str());
+    let visible = info.width > 0.0 && info.height > 0.0;
+    let mut report = |kind| {
+        issues.push(AuditIssue {
+            kind,
+            role: info.role.clone(),
+            title: info.title.clone(),
+            path: path.join(" > "),
+            x: info.global_x,
+            y: info.global_y,
+            width: info.width,
+            height: info.height,
+        })
+    };
+
+    if visible && (interactive || info.role == ax_roles::IMAGE) && info.title.is_none() {
+        report(AuditIssueKind::MissingLabel);
+    }
+    if visible && interactive && (info.width < AUDIT_MIN_TARGET_SIZE || info.height < AUDIT_MIN_TARGET_SIZE) {
+        report(AuditIssueKind::SmallTarget);
+    }
+
+    for child in &node.children {
+        visit(child, path, issues);
+    }
+    path.pop();
+}
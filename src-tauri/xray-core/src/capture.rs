@@ -0,0 +1,163 @@
+use std::path::{Path, PathBuf};
+use core_graphics::display::CGDisplay;
+use core_graphics::geometry::{CGPoint, CGRect, CGSize};
+use core_graphics::image::CGImage;
+use core_graphics::window::{
+    kCGNullWindowID, kCGWindowImageBestResolution, kCGWindowImageBoundsIgnoreFraming,
+    kCGWindowListOptionIncludingWindow, kCGWindowListOptionOnScreenOnly,
+};
+use image::{DynamicImage, ImageError, ImageFormat, RgbaImage};
+use crate::accessibility::UIElementInfo;
+use crate::error::CaptureError;
+use crate::permissions::Permission;
+use crate::window_list;
+
+/// A rectangle in global screen coordinates (points, not pixels).
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ScreenRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl ScreenRect {
+    pub fn of_element(element: &UIElementInfo) -> Self {
+        Self {
+            x: element.global_x,
+            y: element.global_y,
+            width: element.width,
+            height: element.height,
+        }
+    }
+
+    pub fn intersects(&self, other: &ScreenRect) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+
+    fn to_cg(self) -> CGRect {
+        CGRect::new(&CGPoint::new(self.x, self.y), &CGSize::new(self.width, self.height))
+    }
+}
+
+/// Grabs the pixels of `element` into memory, returning the image and the screen area it covers.
+///
+/// Windows are captured by id so overlapping windows don't show through;
+/// any other element is captured by its on-screen rect.
+pub fn grab_element(element: &UIElementInfo) -> Result<(RgbaImage, ScreenRect), CaptureError> {
+    if element.role.contains("Window") && element.window_id > 0 {
+        check_screen_recording()?;
+        let window = window_list::find_window(element.window_id).ok_or(CaptureError::ElementVanished)?;
+        let frame = ScreenRect { x: window.x, y: window.y, width: window.width, height: window.height };
+        let image = CGDisplay::screenshot(
+            frame.to_cg(),
+            kCGWindowListOptionIncludingWindow,
+            element.window_id,
+            kCGWindowImageBoundsIgnoreFraming | kCGWindowImageBestResolution,
+        )
+        .ok_or(CaptureError::ElementVanished)?;
+        return Ok((cg_image_to_rgba(&image)?, frame));
+    }
+
+    let frame = ScreenRect::of_element(element);
+    Ok((grab_rect(&frame)?, frame))
+}
+
+/// Grabs everything visible inside `rect` at the display's native resolution.
+pub fn grab_rect(rect: &ScreenRect) -> Result<RgbaImage, CaptureError> {
+    let valid = [rect.x, rect.y, rect.width, rect.height].iter().all(|v| v.is_finite());
+    if !valid || rect.width < 1.0 || rect.height < 1.0 {
+        return Err(CaptureError::InvalidRect { x: rect.x, y: rect.y, width: rect.width, height: rect.height });
+    }
+    check_screen_recording()?;
+    let image = CGDisplay::screenshot(
+        rect.to_cg(),
+        kCGWindowListOptionOnScreenOnly,
+        kCGNullWindowID,
+        kCGWindowImageBestResolution,
+    )
+    .ok_or_else(|| CaptureError::Other("Failed to capture screen region".to_string()))?;
+    cg_image_to_rgba(&image)
+}
+
+/// Without Screen Recording the window server returns only the wallpaper and
+/// the caller's own windows instead of failing, so it is checked up front.
+fn check_screen_recording() -> Result<(), CaptureError> {
+    if Permission::ScreenRecording.is_granted() {
+        Ok(())
+    } else {
+        Err(CaptureError::PermissionDenied(Permission::ScreenRecording))
+    }
+}
+
+/// Converts a 32-bit BGRA `CGImage` (the format returned by window server captures) to RGBA.
+fn cg_image_to_rgba(image: &CGImage) -> Result<RgbaImage, CaptureError> {
+    if image.bits_per_pixel() != 32 {
+        return Err(CaptureError::Other(format!("Unsupported capture format: {} bits per pixel", image.bits_per_pixel())));
+    }
+
+    let width = image.width();
+    let height = image.height();
+    let bytes_per_row = image.bytes_per_row();
+    let data = image.data();
+    let bytes = data.bytes();
+
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for row in bytes.chunks(bytes_per_row).take(height) {
+        for px in row[..width * 4].chunks_exact(4) {
+            rgba.extend_from_slice(&[px[2], px[1], px[0], px[3]]);
+        }
+    }
+
+    RgbaImage::from_raw(width as u32, height as u32, rgba)
+        .ok_or_else(|| CaptureError::Other("Captured image has an unexpected size".to_string()))
+}
+
+/// Encodes `image` in the format implied by `path`'s extension (PNG by default) and writes it.
+pub fn save_image(image: &RgbaImage, path: &str) -> Result<(), CaptureError> {
+    let format = ImageFormat::from_path(path).unwrap_or(ImageFormat::Png);
+
+    // JPEG has no alpha channel.
+    let saved = if format == ImageFormat::Jpeg {
+        DynamicImage::ImageRgba8(image.clone()).to_rgb8().save_with_format(path, format)
+    } else {
+        image.save_with_format(path, format)
+    };
+    saved.map_err(|e| match e {
+        ImageError::IoError(e) => CaptureError::WriteFailed { path: path.to_string(), message: e.to_string() },
+        e => CaptureError::EncoderFailed(e.to_string()),
+    })
+}
+
+/// Returns the first path derived from `path` that does not exist yet.
+///
+/// `shot.png` becomes `shot-2.png`, `shot-2.png` becomes `shot-3.png`, and so on.
+pub fn next_available_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let ext = path.extension().map(|e| e.to_string_lossy().into_owned());
+
+    // Continue an existing small "-N" suffix instead of appending a new one.
+    // Larger numbers are left alone so timestamped names like `capture-1718000000000` stay intact.
+    let (base, mut counter) = match stem.rsplit_once('-') {
+        Some((base, n)) if !base.is_empty() => match n.parse::<u32>() {
+            Ok(n) if n < 1000 => (base.to_string(), n + 1),
+            _ => (stem.clone(), 2),
+        },
+        _ => (stem.clone(), 2),
+    };
+
+    loop {
+        let name = match &ext {
+            Some(ext) => format!("{}-{}.{}", base, counter, ext),
+            None => format!("{}-{}", base, counter),
+        };
+        let candidate = path.with_file_name(name);
+        if !candidate.exists() {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
@@ -0,0 +1,109 @@
+/// The bundle identifier of the app, which names its config directory; must match `identifier` in tauri.conf.json.
+pub const APP_IDENTIFIER: &str = "com.xray.app";
+
+/// The maximum number of elements visited when searching an app's tree for an identifier.
+pub const LOCATOR_SEARCH_LIMIT: usize = 5000;
+
+/// The file name of the redaction config inside the app config directory.
+pub const REDACTION_CONFIG_FILE: &str = "redaction.json";
+
+/// The maximum number of elements inspected when searching a capture for sensitive fields.
+///
+/// Only subtrees inside the capture count, but a whole window of a large app can hold many thousands.
+pub const REDACTION_SEARCH_LIMIT: usize = 20000;
+
+/// The time in milliseconds to let content settle after each scroll step of a scrolling capture.
+pub const SCROLL_SETTLE_MS: u64 = 250;
+
+/// The maximum number of viewport frames grabbed for one scrolling capture.
+pub const SCROLL_CAPTURE_MAX_FRAMES: usize = 60;
+
+/// The fraction of the viewport scrolled per step, leaving the rest as overlap for stitching.
+pub const SCROLL_STEP_FRACTION: f64 = 0.8;
+
+/// The maximum number of elements copied by a tree snapshot, e.g. for `xray-cli dump`.
+pub const SNAPSHOT_NODE_LIMIT: usize = 20000;
+
+/// Interactive elements smaller than this many points in either direction are reported by the audit.
+pub const AUDIT_MIN_TARGET_SIZE: f64 = 24.0;
+
+/// The maximum number of elements visited per search step when resolving a selector.
+pub const SELECTOR_SEARCH_LIMIT: usize = 5000;
+
+/// The version written to recorded interaction scripts.
+pub const SCRIPT_VERSION: u32 = 1;
+
+/// How long a replayed step waits in milliseconds for its element to appear.
+pub const SCRIPT_STEP_TIMEOUT_MS: u64 = 5000;
+
+/// The interval in milliseconds between attempts to find a replayed step's element.
+pub const SCRIPT_POLL_MS: u64 = 100;
+
+/// The pause in milliseconds after each replayed step, so the app can react before the next one.
+pub const SCRIPT_STEP_DELAY_MS: u64 = 300;
+
+/// The maximum depth to drill down into accessibility elements.
+pub const ACCESSIBILITY_RECURSION_LIMIT: i32 = 50;
+
+/// How long the title and layer of the hovered window are reused before the window list is asked again.
+pub const WINDOW_DETAILS_REFRESH_MS: u64 = 1000;
+
+/// How many levels below the element a SwiftUI or HTML skeleton includes.
+pub const SKELETON_MAX_DEPTH: usize = 8;
+
+/// The maximum number of elements included in a SwiftUI or HTML skeleton.
+pub const SKELETON_NODE_LIMIT: usize = 500;
+
+/// Accessibility attribute names.
+pub mod ax_attributes {
+    pub const CHILDREN: &str = "AXChildren";
+    pub const ROLE: &str = "AXRole";
+    pub const POSITION: &str = "AXPosition";
+    pub const SIZE: &str = "AXSize";
+    pub const TITLE: &str = "AXTitle";
+    pub const DESCRIPTION: &str = "AXDescription";
+    pub const IDENTIFIER: &str = "AXIdentifier";
+    pub const PARENT: &str = "AXParent";
+    pub const SUBROLE: &str = "AXSubrole";
+    pub const VALUE: &str = "AXValue";
+    pub const VERTICAL_SCROLL_BAR: &str = "AXVerticalScrollBar";
+    pub const FOCUSED: &str = "AXFocused";
+    pub const FRONTMOST: &str = "AXFrontmost";
+    pub const WINDOW: &str = "AXWindow";
+}
+
+/// Accessibility actions.
+pub mod ax_actions {
+    pub const PRESS: &str = "AXPress";
+    pub const SHOW_MENU: &str = "AXShowMenu";
+    pub const INCREMENT: &str = "AXIncrement";
+    pub const DECREMENT: &str = "AXDecrement";
+    pub const CONFIRM: &str = "AXConfirm";
+    pub const CANCEL: &str = "AXCancel";
+    pub const RAISE: &str = "AXRaise";
+}
+
+/// Accessibility roles.
+pub mod ax_roles {
+    pub const IMAGE: &str = "AXImage";
+    pub const CHECKBOX: &str = "AXCheckBox";
+    pub const RADIO_BUTTON: &str = "AXRadioButton";
+    pub const STATIC_TEXT: &str = "AXStaticText";
+    pub const HEADING: &str = "AXHeading";
+    pub const BUTTON: &str = "AXButton";
+    pub const LINK: &str = "AXLink";
+    pub const GROUP: &str = "AXGroup";
+    pub const WEB_AREA: &str = "AXWebArea";
+    pub const SCROLL_AREA: &str = "AXScrollArea";
+    pub const APPLICATION: &str = "AXApplication";
+    pub const WINDOW: &str = "AXWindow";
+    pub const VALUE_INDICATOR: &str = "AXValueIndicator";
+    pub const POP_UP_BUTTON: &str = "AXPopUpButton";
+    pub const MENU_BUTTON: &str = "AXMenuButton";
+    pub const TEXT_FIELD: &str = "AXTextField";
+    pub const TEXT_AREA: &str = "AXTextArea";
+    pub const COMBO_BOX: &str = "AXComboBox";
+    pub const SLIDER: &str = "AXSlider";
+    /// Reported as the subrole of password fields (their role is `AXTextField`).
+    pub const SECURE_TEXT_FIELD: &str = "AXSecureTextField";
+}
@@ -0,0 +1,353 @@
+use image::{Rgba, RgbaImage};
+
+/// The largest possible YIQ color delta between two pixels.
+const MAX_YIQ_DELTA: f64 = 35215.0;
+
+/// Edge length of the windows the perceptual score is computed over.
+const SSIM_WINDOW: u32 = 8;
+
+/// Tuning for [`compare`].
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiffOptions {
+    /// Per-pixel color tolerance from 0 (exact) to 1 (anything matches), on a perceptual YIQ scale.
+    pub threshold: f64,
+    /// Count anti-aliased edge pixels as differences instead of ignoring them.
+    pub include_anti_aliasing: bool,
+    /// The largest fraction of differing pixels for which the comparison still passes.
+    pub max_diff_ratio: f64,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self { threshold: 0.1, include_anti_aliasing: false, max_diff_ratio: 0.0 }
+    }
+}
+
+/// The outcome of comparing two images.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffResult {
+    pub width: u32,
+    pub height: u32,
+    /// Pixels that differ beyond the tolerance, including pixels only one image covers.
+    pub diff_pixels: u64,
+    /// Differing pixels that were ignored as anti-aliasing.
+    pub anti_aliased_pixels: u64,
+    pub diff_ratio: f64,
+    /// Mean structural similarity of the luminance, from 0 (unrelated) to 1 (identical).
+    pub perceptual_score: f64,
+    /// Whether the images have different dimensions.
+    pub size_mismatch: bool,
+    pub passed: bool,
+}
+
+/// Compares `candidate` against `baseline` and renders a diff image.
+///
+/// The diff image shows the baseline faded to gray with differing pixels in
+/// red and ignored anti-aliasing in yellow. Images of different sizes are
+/// compared over the larger of both; pixels only one of them covers count as
+/// differences.
+pub fn compare(baseline: &RgbaImage, candidate: &RgbaImage, options: &DiffOptions) -> (DiffResult, RgbaImage) {
+    let width = baseline.width().max(candidate.width());
+    let height = baseline.height().max(candidate.height());
+    let max_delta = MAX_YIQ_DELTA * options.threshold * options.threshold;
+
+    let mut output = RgbaImage::new(width, height);
+    let mut diff_pixels = 0u64;
+    let mut anti_aliased_pixels = 0u64;
+
+    for y in 0..height {
+        for x in 0..width {
+            let (Some(a), Some(b)) = (pixel(baseline, x, y), pixel(candidate, x, y)) else {
+                diff_pixels += 1;
+                output.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+                continue;
+            };
+
+            let delta = color_delta(a, b, false);
+            if delta.abs() <= max_delta {
+                output.put_pixel(x, y, faded(a));
+            } else if !options.include_anti_aliasing
+                && (anti_aliased(baseline, x, y, candidate) || anti_aliased(candidate, x, y, baseline))
+            {
+                anti_aliased_pixels += 1;
+                output.put_pixel(x, y, Rgba([255, 255, 0, 255]));
+            } else {
+                diff_pixels += 1;
+                output.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+            }
+        }
+    }
+
+    let total = (width as u64 * height as u64).max(1);
+    let diff_ratio = diff_pixels as f64 / total as f64;
+    let size_mismatch = baseline.dimensions() != candidate.dimensions();
+    let result = DiffResult {
+        width,
+        height,
+        diff_pixels,
+        anti_aliased_pixels,
+        diff_ratio,
+        perceptual_score: perceptual_score(baseline, candidate),
+        size_mismatch,
+        passed: !size_mismatch && diff_ratio <= options.max_diff_ratio,
+    };
+    (result, output)
+}
+
+fn pixel(image: &RgbaImage, x: u32, y: u32) -> Option<Rgba<u8>> {
+    (x < image.width() && y < image.height()).then(|| *image.get_pixel(x, y))
+}
+
+/// Blends a pixel over white, so translucent pixels compare by how they look.
+fn blend(pixel: Rgba<u8>) -> [f64; 3] {
+    let alpha = pixel[3] as f64 / 255.0;
+    [0, 1, 2].map(|i| 255.0 + (pixel[i] as f64 - 255.0) * alpha)
+}
+
+fn yiq(rgb: [f64; 3]) -> [f64; 3] {
+    let [r, g, b] = rgb;
+    [
+        r * 0.29889531 + g * 0.58662247 + b * 0.11448223,
+        r * 0.59597799 - g * 0.27417610 - b * 0.32180189,
+        r * 0.21147017 - g * 0.52261711 + b * 0.31114694,
+    ]
+}
+
+/// The squared perceptual distance between two pixels, signed by whether `b` is darker.
+/// With `luma_only`, only the brightness difference is returned.
+fn color_delta(a: Rgba<u8>, b: Rgba<u8>, luma_only: bool) -> f64 {
+    if a == b {
+        return 0.0;
+    }
+    let [y1, i1, q1] = yiq(blend(a));
+    let [y2, i2, q2] = yiq(blend(b));
+    let y = y1 - y2;
+    if luma_only {
+        return y;
+    }
+
+    let (i, q) = (i1 - i2, q1 - q2);
+    let delta = 0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q;
+    if y1 > y2 { -delta } else { delta }
+}
+
+/// Detects whether the pixel at `(x, y)` of `image` is part of an anti-aliased edge.
+///
+/// An anti-aliased pixel has both a darkest and a brightest neighbour that
+/// lie in flat areas of either image, and at most two identical neighbours.
+fn anti_aliased(image: &RgbaImage, x: u32, y: u32, other: &RgbaImage) -> bool {
+    let center = *image.get_pixel(x, y);
+    let (x0, y0) = (x.saturating_sub(1), y.saturating_sub(1));
+    let x1 = (x + 1).min(image.width() - 1);
+    let y1 = (y + 1).min(image.height() - 1);
+    let on_edge = x == x0 || x == x1 || y == y0 || y == y1;
+
+    let mut zeroes = if on_edge { 1 } else { 0 };
+    let (mut min, mut max) = (0.0, 0.0);
+    let (mut min_at, mut max_at) = ((0, 0), (0, 0));
+
+    for ny in y0..=y1 {
+        for nx in x0..=x1 {
+            if nx == x && ny == y {
+                continue;
+            }
+            let delta = color_delta(center, *image.get_pixel(nx, ny), true);
+            if delta == 0.0 {
+                zeroes += 1;
+                if zeroes > 2 {
+                    return false;
+                }
+            } else if delta < min {
+                min = delta;
+                min_at = (nx, ny);
+            } else if delta > max {
+                max = delta;
+                max_at = (nx, ny);
+            }
+        }
+    }
+
+    if min == 0.0 || max == 0.0 {
+        return false;
+    }
+
+    let flat = |(px, py): (u32, u32)| has_many_siblings(image, px, py) || has_many_siblings(other, px, py);
+    flat(min_at) && flat(max_at)
+}
+
+/// Whether at least three neighbours of `(x, y)` have exactly its color.
+fn has_many_siblings(image: &RgbaImage, x: u32, y: u32) -> bool {
+    if x >= image.width() || y >= image.height() {
+        return false;
+    }
+    let center = image.get_pixel(x, y);
+    let (x0, y0) = (x.saturating_sub(1), y.saturating_sub(1));
+    let x1 = (x + 1).min(image.width() - 1);
+    let y1 = (y + 1).min(image.height() - 1);
+
+    let mut zeroes = if x == x0 || x == x1 || y == y0 || y == y1 { 1 } else { 0 };
+    for ny in y0..=y1 {
+        for nx in x0..=x1 {
+            if (nx, ny) != (x, y) && image.get_pixel(nx, ny) == center {
+                zeroes += 1;
+                if zeroes > 2 {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+/// Renders an unchanged pixel as a light gray so changes stand out.
+fn faded(pixel: Rgba<u8>) -> Rgba<u8> {
+    let [y, _, _] = yiq(blend(pixel));
+    let v = (255.0 + (y - 255.0) * 0.1).clamp(0.0, 255.0) as u8;
+    Rgba([v, v, v, 255])
+}
+
+/// Computes the mean SSIM of the luminance over non-overlapping windows of the shared area.
+fn perceptual_score(a: &RgbaImage, b: &RgbaImage) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    let width = a.width().min(b.width());
+    let height = a.height().min(b.height());
+    if width == 0 || height == 0 {
+        return 0.0;
+    }
+
+    let luma = |image: &RgbaImage, x: u32, y: u32| yiq(blend(*image.get_pixel(x, y)))[0];
+    let mut total = 0.0;
+    let mut windows = 0u64;
+
+    for wy in (0..height).step_by(SSIM_WINDOW as usize) {
+        for wx in (0..width).step_by(SSIM_WINDOW as usize) {
+            let (ww, wh) = (SSIM_WINDOW.min(width - wx), SSIM_WINDOW.min(height - wy));
+            let n = (ww * wh) as f64;
+
+            let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) = (0.0, 0.0, 0.0, 0.0, 0.0);
+            for y in wy..wy + wh {
+                for x in wx..wx + ww {
+                    let (la, lb) = (luma(a, x, y), luma(b, x, y));
+                    sum_a += la;
+                    sum_b += lb;
+                    sum_aa += la * la;
+                    sum_bb += lb * lb;
+                    sum_ab += la * lb;
+                }
+            }
+
+            let (mean_a, mean_b) = (sum_a / n, sum_b / n);
+            let var_a = sum_aa / n - mean_a * mean_a;
+            let var_b = sum_bb / n - mean_b * mean_b;
+            let cov = sum_ab / n - mean_a * mean_b;
+
+            total += ((2.0 * mean_a * mean_b + C1) * (2.0 * cov + C2))
+                / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2));
+            windows += 1;
+        }
+    }
+
+    (total / windows as f64).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(width: u32, height: u32, shade: u8) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba([shade, shade, shade, 255]))
+    }
+
+    /// Black on the left, white from column `split` on.
+    fn edge(split: u32) -> RgbaImage {
+        RgbaImage::from_fn(8, 8, |x, _| if x < split { Rgba([0, 0, 0, 255]) } else { Rgba([255, 255, 255, 255]) })
+    }
+
+    fn checkerboard(invert: bool) -> RgbaImage {
+        RgbaImage::from_fn(16, 16, |x, y| {
+            let v = if ((x + y) % 2 == 0) != invert { 255 } else { 0 };
+            Rgba([v, v, v, 255])
+        })
+    }
+
+    #[test]
+    fn identical_images_pass() {
+        let image = checkerboard(false);
+        let (result, _) = compare(&image, &image, &DiffOptions::default());
+        assert_eq!(result.diff_pixels, 0);
+        assert_eq!(result.anti_aliased_pixels, 0);
+        assert!(result.passed);
+        assert!(result.perceptual_score > 0.999);
+    }
+
+    #[test]
+    fn single_changed_pixel_is_counted() {
+        let baseline = gray(8, 8, 128);
+        let mut candidate = baseline.clone();
+        candidate.put_pixel(3, 3, Rgba([255, 0, 0, 255]));
+
+        let (result, output) = compare(&baseline, &candidate, &DiffOptions::default());
+        assert_eq!(result.diff_pixels, 1);
+        assert!(!result.passed);
+        assert_eq!(*output.get_pixel(3, 3), Rgba([255, 0, 0, 255]));
+
+        let lenient = DiffOptions { max_diff_ratio: 1.0 / 64.0, ..DiffOptions::default() };
+        assert!(compare(&baseline, &candidate, &lenient).0.passed);
+    }
+
+    #[test]
+    fn threshold_tolerates_small_color_changes() {
+        let (baseline, candidate) = (gray(4, 4, 128), gray(4, 4, 131));
+        assert_eq!(compare(&baseline, &candidate, &DiffOptions::default()).0.diff_pixels, 0);
+
+        let exact = DiffOptions { threshold: 0.0, ..DiffOptions::default() };
+        assert_eq!(compare(&baseline, &candidate, &exact).0.diff_pixels, 16);
+    }
+
+    #[test]
+    fn anti_aliased_edge_is_ignored() {
+        let baseline = edge(4);
+        let mut candidate = baseline.clone();
+        for y in 0..8 {
+            candidate.put_pixel(4, y, Rgba([128, 128, 128, 255]));
+        }
+
+        let (result, output) = compare(&baseline, &candidate, &DiffOptions::default());
+        assert_eq!(result.diff_pixels, 0);
+        assert_eq!(result.anti_aliased_pixels, 8);
+        assert!(result.passed);
+        assert_eq!(*output.get_pixel(4, 0), Rgba([255, 255, 0, 255]));
+
+        let strict = DiffOptions { include_anti_aliasing: true, ..DiffOptions::default() };
+        let (result, _) = compare(&baseline, &candidate, &strict);
+        assert_eq!(result.diff_pixels, 8);
+        assert_eq!(result.anti_aliased_pixels, 0);
+    }
+
+    #[test]
+    fn size_mismatch_fails() {
+        let (baseline, candidate) = (gray(4, 4, 128), gray(4, 6, 128));
+        let options = DiffOptions { max_diff_ratio: 1.0, ..DiffOptions::default() };
+        let (result, output) = compare(&baseline, &candidate, &options);
+        assert!(result.size_mismatch);
+        assert!(!result.passed);
+        assert_eq!((result.width, result.height), (4, 6));
+        assert_eq!(result.diff_pixels, 8);
+        assert_eq!(output.dimensions(), (4, 6));
+    }
+
+    #[test]
+    fn perceptual_score_stays_in_bounds() {
+        let score = perceptual_score(&checkerboard(false), &checkerboard(true));
+        assert!((0.0..0.1).contains(&score), "{}", score);
+
+        let score = perceptual_score(&gray(16, 16, 100), &gray(16, 16, 140));
+        assert!((0.0..1.0).contains(&score), "{}", score);
+
+        assert_eq!(perceptual_score(&gray(0, 0, 0), &gray(4, 4, 0)), 0.0);
+    }
+}
@@ -0,0 +1,295 @@
+use std::ffi::c_void;
+use crate::accessibility::UIElementInfo;
+use crate::constants::{SKELETON_MAX_DEPTH, SKELETON_NODE_LIMIT};
+use crate::tree::{self, ElementNode};
+use crate::{actions, locator, script};
+
+/// A text form of an element for pasting into code, tickets or docs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ElementFormat {
+    /// The element's `UIElementInfo` as JSON.
+    #[default]
+    Json,
+    /// A two-column Markdown table of the element's properties.
+    Markdown,
+    /// A selector that finds the element again, e.g. for `xray-cli capture --selector`.
+    Locator,
+    /// The element's screen rect as CSS `left/top/width/height` declarations.
+    Bounds,
+    /// A SwiftUI view hierarchy mirroring the element's subtree.
+    SwiftUi,
+    /// An HTML fragment mirroring the element's subtree.
+    Html,
+}
+
+impl ElementFormat {
+    pub const ALL: [ElementFormat; 6] =
+        [Self::Json, Self::Markdown, Self::Locator, Self::Bounds, Self::SwiftUi, Self::Html];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::Markdown => "Markdown Table",
+            Self::Locator => "Locator",
+            Self::Bounds => "CSS Bounds",
+            Self::SwiftUi => "SwiftUI Skeleton",
+            Self::Html => "HTML Skeleton",
+        }
+    }
+}
+
+impl std::str::FromStr for ElementFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "markdown" | "md" => Ok(Self::Markdown),
+            "locator" | "selector" => Ok(Self::Locator),
+            "bounds" | "css" => Ok(Self::Bounds),
+            "swiftui" | "swift" => Ok(Self::SwiftUi),
+            "html" => Ok(Self::Html),
+            _ => Err(format!("Unknown element format '{}', expected json, markdown, locator, bounds, swiftui or html", s)),
+        }
+    }
+}
+
+/// Accessibility roles with the SwiftUI view and HTML tag standing in for them.
+/// Roles not listed become a `VStack` or `<div>`.
+const SKELETON_TYPES: &[(&str, &str, &str)] = &[
+    ("AXButton", "Button", "button"),
+    ("AXMenuButton", "Menu", "button"),
+    ("AXPopUpButton", "Picker", "select"),
+    ("AXCheckBox", "Toggle", "input type=\"checkbox\""),
+    ("AXRadioButton", "Toggle", "input type=\"radio\""),
+    ("AXTextField", "TextField", "input type=\"text\""),
+    ("AXComboBox", "TextField", "input type=\"text\""),
+    ("AXTextArea", "TextEditor", "textarea"),
+    ("AXStaticText", "Text", "span"),
+    ("AXHeading", "Text", "h2"),
+    ("AXLink", "Link", "a"),
+    ("AXImage", "Image", "img"),
+    ("AXSlider", "Slider", "input type=\"range\""),
+    ("AXProgressIndicator", "ProgressView", "progress"),
+    ("AXScrollArea", "ScrollView", "div"),
+    ("AXList", "List", "ul"),
+    ("AXOutline", "List", "ul"),
+    ("AXTable", "List", "table"),
+    ("AXRow", "HStack", "tr"),
+    ("AXCell", "VStack", "td"),
+    ("AXToolbar", "HStack", "nav"),
+    ("AXTabGroup", "TabView", "div"),
+    ("AXSplitGroup", "HSplitView", "div"),
+    ("AXWindow", "VStack", "main"),
+];
+
+/// Renders `info` in `format`.
+///
+/// The locator and skeleton formats read the live element, so it must still be on screen.
+pub fn format_element(info: &UIElementInfo, format: ElementFormat) -> Result<String, String> {
+    match format {
+        ElementFormat::Json => serde_json::to_string_pretty(info).map_err(|e| e.to_string()),
+        ElementFormat::Markdown => Ok(to_markdown(info)),
+        ElementFormat::Locator => selector(info).ok_or_else(|| "The element is no longer on screen".to_string()),
+        ElementFormat::Bounds => Ok(to_bounds(info)),
+        ElementFormat::SwiftUi => subtree(info).map(|node| {
+            let mut out = String::new();
+            write_swiftui(&node, 0, &mut out);
+            out
+        }),
+        ElementFormat::Html => subtree(info).map(|node| {
+            let mut out = String::new();
+            write_html(&node, 0, &mut out);
+            out
+        }),
+    }
+}
+
+/// A selector for `info`, preferring its identifier, title or description over its path.
+fn selector(info: &UIElementInfo) -> Option<String> {
+    let located;
+    let info = if info.locator.is_some() {
+        info
+    } else {
+        located = with_locator(info)?;
+        &located
+    };
+    script::click_step(info).map(|step| step.selector)
+}
+
+/// A copy of `info` with the locator it was hovered without.
+fn with_locator(info: &UIElementInfo) -> Option<UIElementInfo> {
+    let element = actions::copy_element(info)?;
+    let locator = unsafe {
+        let locator = locator::build(element);
+        core_foundation::base::CFRelease(element as *const c_void);
+        locator
+    };
+    Some(UIElementInfo { locator: Some(locator), ..info.clone() })
+}
+
+fn subtree(info: &UIElementInfo) -> Result<ElementNode, String> {
+    let element = actions::copy_element(info).ok_or_else(|| "The element is no longer on screen".to_string())?;
+    unsafe {
+        let node = tree::snapshot(element, SKELETON_MAX_DEPTH, SKELETON_NODE_LIMIT);
+        core_foundation::base::CFRelease(element as *const c_void);
+        Ok(node)
+    }
+}
+
+fn to_markdown(info: &UIElementInfo) -> String {
+    let mut rows = vec![("Role", info.role.clone())];
+    if let Some(title) = &info.title {
+        rows.push(("Title", title.clone()));
+    }
+    rows.push(("Position", format!("{}, {}", info.global_x.round(), info.global_y.round())));
+    rows.push(("Size", format!("{} × {}", info.width.round(), info.height.round())));
+    if let Some(app_name) = &info.app_name {
+        rows.push(("App", app_name.clone()));
+    }
+    if let Some(bundle_id) = &info.bundle_id {
+        rows.push(("Bundle ID", bundle_id.clone()));
+    }
+    if info.window_id > 0 {
+        rows.push(("Window", info.window_id.to_string()));
+    }
+    if let Some(window_title) = &info.window_title {
+        rows.push(("Window Title", window_title.clone()));
+    }
+    let pid = info.locator.as_ref().map_or(info.pid, |l| l.pid);
+    if pid > 0 {
+        rows.push(("Process", pid.to_string()));
+    }
+    if let Some(identifier) = info.locator.as_ref().and_then(|l| l.identifier.as_ref()) {
+        rows.push(("Identifier", identifier.clone()));
+    }
+    if let Some(selector) = selector(info) {
+        rows.push(("Selector", format!("`{}`", selector)));
+    }
+
+    let mut out = String::from("| Property | Value |\n| --- | --- |\n");
+    for (name, value) in rows {
+        out.push_str(&format!("| {} | {} |\n", name, value.replace('|', "\\|").replace('\n', " ")));
+    }
+    out
+}
+
+fn to_bounds(info: &UIElementInfo) -> String {
+    format!(
+        "left: {}px;\ntop: {}px;\nwidth: {}px;\nheight: {}px;\n",
+        info.global_x.round(),
+        info.global_y.round(),
+        info.width.round(),
+        info.height.round()
+    )
+}
+
+fn skeleton_type(role: &str) -> Option<(&'static str, &'static str)> {
+    SKELETON_TYPES.iter().find(|(r, _, _)| *r == role).map(|(_, view, tag)| (*view, *tag))
+}
+
+/// A double-quoted Swift string literal.
+fn literal(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+fn write_swiftui(node: &ElementNode, depth: usize, out: &mut String) {
+    let indent = "    ".repeat(depth);
+    let title = literal(node.info.title.as_deref().unwrap_or(""));
+    let view = skeleton_type(&node.info.role).map(|(view, _)| view).unwrap_or("VStack");
+    let frame = format!(".frame(width: {}, height: {})", node.info.width.round(), node.info.height.round());
+
+    let leaf = match view {
+        "Button" => Some(format!("Button({}) {{}}", title)),
+        "Toggle" => Some(format!("Toggle({}, isOn: .constant(false))", title)),
+        "TextField" => Some(format!("TextField({}, text: .constant(\"\"))", title)),
+        "TextEditor" => Some("TextEditor(text: .constant(\"\"))".to_string()),
+        "Text" => Some(format!("Text({})", title)),
+        "Link" => Some(format!("Link({}, destination: URL(string: \"about:blank\")!)", title)),
+        "Image" => Some("Image(systemName: \"photo\")".to_string()),
+        "Slider" => Some("Slider(value: .constant(0.5))".to_string()),
+        "ProgressView" => Some("ProgressView()".to_string()),
+        _ => None,
+    };
+    if let Some(leaf) = leaf.filter(|_| node.children.is_empty()) {
+        out.push_str(&format!("{}{}\n{}    {}\n", indent, leaf, indent, frame));
+        return;
+    }
+
+    let open = match view {
+        "Picker" => format!("Picker({}, selection: .constant(0))", title),
+        "Menu" => format!("Menu({})", title),
+        // Containers that take a title, or have children despite being a control, become stacks.
+        "Button" | "Toggle" | "TextField" | "TextEditor" | "Text" | "Link" | "Image" | "Slider" | "ProgressView" => {
+            "VStack".to_string()
+        }
+        view => view.to_string(),
+    };
+    out.push_str(&format!("{}{} {{ // {}\n", indent, open, node.info.role));
+    for child in &node.children {
+        write_swiftui(child, depth + 1, out);
+    }
+    out.push_str(&format!("{}}}\n{}{}\n", indent, indent, frame));
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn write_html(node: &ElementNode, depth: usize, out: &mut String) {
+    let indent = "  ".repeat(depth);
+    let (tag, known) = match skeleton_type(&node.info.role) {
+        Some((_, tag)) => (tag, true),
+        None => ("div", false),
+    };
+    let name = tag.split(' ').next().unwrap_or(tag);
+    let title = node.info.title.as_deref().map(escape_html);
+
+    let mut attributes = String::new();
+    if !known {
+        attributes.push_str(&format!(" data-role=\"{}\"", escape_html(&node.info.role)));
+    }
+    attributes.push_str(&format!(
+        " style=\"width: {}px; height: {}px\"",
+        node.info.width.round(),
+        node.info.height.round()
+    ));
+
+    match name {
+        "input" | "img" => {
+            let label = match (name, &title) {
+                ("img", Some(title)) => format!(" alt=\"{}\"", title),
+                ("input", Some(title)) => format!(" aria-label=\"{}\"", title),
+                _ => String::new(),
+            };
+            out.push_str(&format!("{}<{}{}{}>\n", indent, tag, label, attributes));
+        }
+        _ if node.children.is_empty() => {
+            let href = if name == "a" { " href=\"#\"" } else { "" };
+            out.push_str(&format!(
+                "{}<{}{}{}>{}</{}>\n",
+                indent,
+                tag,
+                href,
+                attributes,
+                title.unwrap_or_default(),
+                name
+            ));
+        }
+        _ => {
+            let label = title.map(|t| format!(" aria-label=\"{}\"", t)).unwrap_or_default();
+            out.push_str(&format!("{}<{}{}{}>\n", indent, tag, label, attributes));
+            for child in &node.children {
+                if name == "ul" {
+                    out.push_str(&format!("{}  <li>\n", indent));
+                    write_html(child, depth + 2, out);
+                    out.push_str(&format!("{}  </li>\n", indent));
+                } else {
+                    write_html(child, depth + 1, out);
+                }
+            }
+            out.push_str(&format!("{}</{}>\n", indent, name));
+        }
+    }
+}
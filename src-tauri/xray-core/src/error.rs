@@ -0,0 +1,69 @@
+use serde::ser::SerializeStruct;
+use crate::permissions::Permission;
+
+/// Why grabbing, encoding or saving a capture failed.
+///
+/// Serialized as `{ kind, message, permission }` so the frontend can tell the
+/// cases apart, e.g. to offer opening System Settings when a permission is missing.
+#[derive(Debug, thiserror::Error)]
+pub enum CaptureError {
+    #[error("xray doesn't have the {} permission", .0.label())]
+    PermissionDenied(Permission),
+    #[error("The element is no longer on screen")]
+    ElementVanished,
+    #[error("Can't capture a {width} × {height} area at ({x}, {y})")]
+    InvalidRect { x: f64, y: f64, width: f64, height: f64 },
+    #[error("Failed to write {path}: {message}")]
+    WriteFailed { path: String, message: String },
+    #[error("Failed to encode the image: {0}")]
+    EncoderFailed(String),
+    #[error("Failed to copy to the clipboard: {0}")]
+    ClipboardFailed(String),
+    #[error("Not saved because sensitive fields couldn't be redacted: {0}")]
+    RedactionFailed(String),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl CaptureError {
+    /// The camelCase name of the variant, as sent to the frontend.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::PermissionDenied(_) => "permissionDenied",
+            Self::ElementVanished => "elementVanished",
+            Self::InvalidRect { .. } => "invalidRect",
+            Self::WriteFailed { .. } => "writeFailed",
+            Self::EncoderFailed(_) => "encoderFailed",
+            Self::ClipboardFailed(_) => "clipboardFailed",
+            Self::RedactionFailed(_) => "redactionFailed",
+            Self::Other(_) => "other",
+        }
+    }
+}
+
+impl serde::Serialize for CaptureError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let permission = match self {
+            Self::PermissionDenied(permission) => Some(permission),
+            _ => None,
+        };
+        let mut error = serializer.serialize_struct("CaptureError", 3)?;
+        error.serialize_field("kind", self.kind())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.serialize_field("permission", &permission)?;
+        error.end()
+    }
+}
+
+impl From<String> for CaptureError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+// Most callers only report errors, so they keep returning `Result<_, String>`.
+impl From<CaptureError> for String {
+    fn from(error: CaptureError) -> Self {
+        error.to_string()
+    }
+}
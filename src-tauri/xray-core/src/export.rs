@@ -0,0 +1,247 @@
+use std::path::Path;
+use crate::script::{Script, ScriptStep, StepAction};
+
+/// A kind of test code a [`Script`] can be turned into.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    /// A Swift `XCTestCase` using XCUITest.
+    Xcuitest,
+    /// A Python script for Appium's Mac2 driver.
+    Appium,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "xcuitest" | "swift" => Ok(Self::Xcuitest),
+            "appium" | "py" => Ok(Self::Appium),
+            _ => Err(format!("Unknown export format '{}', expected xcuitest or appium", s)),
+        }
+    }
+}
+
+impl ExportFormat {
+    /// Picks the format from a file extension: `.swift` or `.py`.
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        match ext.to_ascii_lowercase().as_str() {
+            "swift" => Ok(Self::Xcuitest),
+            "py" => Ok(Self::Appium),
+            _ => Err(format!("Unsupported export format: .{}", ext)),
+        }
+    }
+}
+
+/// Accessibility roles with their XCUITest query and element type.
+const ELEMENT_TYPES: &[(&str, &str, &str)] = &[
+    ("AXButton", "buttons", "Button"),
+    ("AXCheckBox", "checkBoxes", "CheckBox"),
+    ("AXRadioButton", "radioButtons", "RadioButton"),
+    ("AXPopUpButton", "popUpButtons", "PopUpButton"),
+    ("AXMenuButton", "menuButtons", "MenuButton"),
+    ("AXComboBox", "comboBoxes", "ComboBox"),
+    ("AXTextField", "textFields", "TextField"),
+    ("AXTextArea", "textViews", "TextView"),
+    ("AXStaticText", "staticTexts", "StaticText"),
+    ("AXLink", "links", "Link"),
+    ("AXImage", "images", "Image"),
+    ("AXMenuItem", "menuItems", "MenuItem"),
+    ("AXMenuBarItem", "menuBarItems", "MenuBarItem"),
+    ("AXSlider", "sliders", "Slider"),
+    ("AXIncrementor", "steppers", "Stepper"),
+    ("AXDisclosureTriangle", "disclosureTriangles", "DisclosureTriangle"),
+    ("AXRow", "tableRows", "TableRow"),
+    ("AXCell", "cells", "Cell"),
+    ("AXTabGroup", "tabGroups", "TabGroup"),
+    ("AXWindow", "windows", "Window"),
+];
+
+/// Renders `script` as test code in `format`.
+pub fn export(script: &Script, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Xcuitest => to_xcuitest(script),
+        ExportFormat::Appium => to_appium(script),
+    }
+}
+
+/// Writes `script` as test code, picking the format from the extension of `path`.
+pub fn export_to_file(script: &Script, path: &Path) -> Result<(), String> {
+    let code = export(script, ExportFormat::from_path(path)?);
+    std::fs::write(path, code).map_err(|e| e.to_string())
+}
+
+fn element_type(role: &str) -> Option<(&'static str, &'static str)> {
+    ELEMENT_TYPES.iter().find(|(r, _, _)| *r == role).map(|(_, query, name)| (*query, *name))
+}
+
+/// A double-quoted string literal, valid in both Swift and Python.
+fn literal(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+/// Makes `s` safe to put in a `//` or `#` line comment by escaping line breaks and other control characters.
+fn comment(s: &str) -> String {
+    s.chars().map(|c| if c.is_control() { c.escape_default().to_string() } else { c.to_string() }).collect()
+}
+
+fn to_xcuitest(script: &Script) -> String {
+    let mut out = String::from("import XCTest\n\nfinal class RecordedTests: XCTestCase {\n    func testRecordedInteraction() {\n");
+    match &script.bundle_id {
+        Some(id) => out.push_str(&format!("        let app = XCUIApplication(bundleIdentifier: {})\n", literal(id))),
+        None => out.push_str(&format!(
+            "        // Recorded in {}; set its bundle identifier.\n        let app = XCUIApplication()\n",
+            comment(&script.app)
+        )),
+    }
+    out.push_str("        app.activate()\n");
+
+    for step in &script.steps {
+        out.push_str(&format!("\n        // {}\n", comment(&step.selector)));
+        let element = xcuitest_query(step);
+        match step.action {
+            StepAction::Click => out.push_str(&format!("        {}.click()\n", element)),
+            StepAction::Type => {
+                let text = literal(step.text.as_deref().unwrap_or(""));
+                out.push_str(&format!("        {}.click()\n        {}.typeText({})\n", element, element, text));
+            }
+        }
+    }
+    out.push_str("    }\n}\n");
+    out
+}
+
+fn xcuitest_query(step: &ScriptStep) -> String {
+    let query = element_type(&step.role).map(|(query, _)| query).unwrap_or("descendants(matching: .any)");
+    match step.identifier.as_ref().or(step.label.as_ref()) {
+        Some(key) => format!("app.{}[{}]", query, literal(key)),
+        None => format!("app.{}.firstMatch", query),
+    }
+}
+
+fn to_appium(script: &Script) -> String {
+    let mut out = String::from(
+        "from appium import webdriver\nfrom appium.options.mac import Mac2Options\nfrom appium.webdriver.common.appiumby import AppiumBy\n\n",
+    );
+    out.push_str("options = Mac2Options()\n");
+    match &script.bundle_id {
+        Some(id) => out.push_str(&format!("options.bundle_id = {}\n", literal(id))),
+        None => out.push_str(&format!(
+            "# Recorded in {}; set its bundle identifier.\noptions.bundle_id = \"\"\n",
+            comment(&script.app)
+        )),
+    }
+    out.push_str("driver = webdriver.Remote(\"http://127.0.0.1:4723\", options=options)\n\ntry:\n");
+
+    for step in &script.steps {
+        out.push_str(&format!("    # {}\n", comment(&step.selector)));
+        let find = appium_find(step);
+        match step.action {
+            StepAction::Click => out.push_str(&format!("    {}.click()\n", find)),
+            StepAction::Type => {
+                let text = literal(step.text.as_deref().unwrap_or(""));
+                out.push_str(&format!("    element = {}\n    element.clear()\n    element.send_keys({})\n", find, text));
+            }
+        }
+    }
+    if script.steps.is_empty() {
+        out.push_str("    pass\n");
+    }
+    out.push_str("finally:\n    driver.quit()\n");
+    out
+}
+
+fn appium_find(step: &ScriptStep) -> String {
+    if let Some(identifier) = &step.identifier {
+        return format!("driver.find_element(AppiumBy.ACCESSIBILITY_ID, {})", literal(identifier));
+    }
+    let element_type = element_type(&step.role).map(|(_, name)| format!("XCUIElementType{}", name));
+    let mut xpath = format!("//{}", element_type.as_deref().unwrap_or("*"));
+    if let Some(label) = step.label.as_ref().filter(|l| !l.contains('"')) {
+        xpath.push_str(&format!("[@title=\"{0}\" or @label=\"{0}\"]", label));
+    }
+    format!("driver.find_element(AppiumBy.XPATH, {})", literal(&xpath))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(action: StepAction, role: &str, label: Option<&str>, identifier: Option<&str>, text: Option<&str>) -> ScriptStep {
+        ScriptStep {
+            action,
+            selector: format!("{}[title=\"{}\"]", role, label.unwrap_or("")),
+            role: role.to_string(),
+            label: label.map(str::to_string),
+            identifier: identifier.map(str::to_string),
+            text: text.map(str::to_string),
+        }
+    }
+
+    fn script(bundle_id: Option<&str>) -> Script {
+        let mut script = Script::new("Notes".to_string(), bundle_id.map(str::to_string));
+        script.steps.push(step(StepAction::Click, "AXButton", Some("New Note"), None, None));
+        script.steps.push(step(StepAction::Type, "AXTextArea", None, Some("body"), Some("say \"hi\"\n")));
+        script
+    }
+
+    #[test]
+    fn format_from_name_and_path() {
+        assert_eq!("XCUITest".parse::<ExportFormat>(), Ok(ExportFormat::Xcuitest));
+        assert_eq!("py".parse::<ExportFormat>(), Ok(ExportFormat::Appium));
+        assert!("ruby".parse::<ExportFormat>().is_err());
+        assert_eq!(ExportFormat::from_path(Path::new("t.SWIFT")), Ok(ExportFormat::Xcuitest));
+        assert!(ExportFormat::from_path(Path::new("t.rb")).is_err());
+    }
+
+    #[test]
+    fn xcuitest_queries_by_identifier_then_label() {
+        let code = export(&script(Some("com.apple.Notes")), ExportFormat::Xcuitest);
+        assert!(code.contains("let app = XCUIApplication(bundleIdentifier: \"com.apple.Notes\")\n"), "{}", code);
+        assert!(code.contains("        app.buttons[\"New Note\"].click()\n"), "{}", code);
+        assert!(code.contains("        app.textViews[\"body\"].typeText(\"say \\\"hi\\\"\\n\")\n"), "{}", code);
+        assert!(code.ends_with("    }\n}\n"));
+    }
+
+    #[test]
+    fn appium_finds_by_identifier_then_xpath() {
+        let code = export(&script(Some("com.apple.Notes")), ExportFormat::Appium);
+        assert!(code.contains("options.bundle_id = \"com.apple.Notes\"\n"), "{}", code);
+        assert!(code.contains(
+            "    driver.find_element(AppiumBy.XPATH, \"//XCUIElementTypeButton[@title=\\\"New Note\\\" or @label=\\\"New Note\\\"]\").click()\n"
+        ), "{}", code);
+        assert!(code.contains("    element = driver.find_element(AppiumBy.ACCESSIBILITY_ID, \"body\")\n"), "{}", code);
+        assert!(code.contains("    element.send_keys(\"say \\\"hi\\\"\\n\")\n"), "{}", code);
+        assert!(code.ends_with("finally:\n    driver.quit()\n"));
+    }
+
+    #[test]
+    fn appium_script_without_steps_is_valid() {
+        let code = export(&Script::new("Notes".to_string(), None), ExportFormat::Appium);
+        assert!(code.contains("try:\n    pass\nfinally:\n"), "{}", code);
+    }
+
+    #[test]
+    fn unknown_role_and_quoted_label_fall_back() {
+        let mut script = Script::new("Notes".to_string(), None);
+        script.steps.push(step(StepAction::Click, "AXGroup", Some("a \"b\""), None, None));
+        let swift = export(&script, ExportFormat::Xcuitest);
+        assert!(swift.contains("app.descendants(matching: .any)[\"a \\\"b\\\"\"].click()"), "{}", swift);
+        let python = export(&script, ExportFormat::Appium);
+        assert!(python.contains("driver.find_element(AppiumBy.XPATH, \"//*\").click()"), "{}", python);
+    }
+
+    #[test]
+    fn comments_cannot_break_out_of_their_line() {
+        let mut script = Script::new("Evil\nimport os\r\nos.system('x')".to_string(), None);
+        script.steps.push(step(StepAction::Click, "AXButton", Some("a\nb"), None, None));
+        for format in [ExportFormat::Xcuitest, ExportFormat::Appium] {
+            let code = export(&script, format);
+            assert!(!code.lines().any(|l| l.trim_start().starts_with("import os")), "{}", code);
+            assert!(!code.lines().any(|l| l.starts_with("b\"]")), "{}", code);
+            assert!(code.contains("Evil\\nimport os\\r\\nos.system('x')"), "{}", code);
+        }
+    }
+}
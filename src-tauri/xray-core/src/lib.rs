@@ -0,0 +1,31 @@
+//! The parts of xray that don't need the app: reading the accessibility tree,
+//! hit testing, capturing and redacting element images, and comparing them.
+//!
+//! Everything here works from any process that has the Accessibility and
+//! Screen Recording permissions, such as `xray-cli` or a test harness.
+//!
+//! Functions taking a raw `AXUIElementRef` are `unsafe`: the element must be
+//! valid for the duration of the call. Functions named `copy_*` return
+//! retained elements that the caller must release.
+
+pub mod accessibility;
+pub mod actions;
+pub mod app_info;
+pub mod audit;
+pub mod capture;
+pub mod constants;
+pub mod diff;
+pub mod element_format;
+pub mod error;
+pub mod export;
+pub mod hash;
+pub mod locator;
+pub mod manifest;
+pub mod permissions;
+pub mod redaction;
+pub mod script;
+pub mod scroll_capture;
+pub mod selector;
+pub mod stitch;
+pub mod tree;
+pub mod window_list;
@@ -0,0 +1,178 @@
+use crate::accessibility::{
+    copy_children, copy_element_attribute, element_info, get_pid, get_role, get_string_attribute,
+    release_all, UIElementInfo,
+};
+use crate::constants::{ax_attributes, ax_roles, ACCESSIBILITY_RECURSION_LIMIT, LOCATOR_SEARCH_LIMIT};
+use crate::{app_info, tree, window_list};
+use accessibility_sys::{AXUIElementCreateApplication, AXUIElementRef};
+use std::ffi::c_void;
+
+/// Identifies a UI element independently of its on-screen coordinates, so it
+/// can be found again after the app re-renders or the window moves.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElementLocator {
+    /// The process id of the owning application.
+    pub pid: i32,
+    /// The owning application's bundle identifier, for finding it again after it restarts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_id: Option<String>,
+    /// The element's `AXIdentifier`, when the app provides one.
+    pub identifier: Option<String>,
+    /// Steps from the application element down to the element.
+    pub path: Vec<PathStep>,
+}
+
+/// One step of an [`ElementLocator`] path: the `index`-th child with `role`.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathStep {
+    pub role: String,
+    pub index: usize,
+}
+
+/// Builds a locator for `element` by walking its `AXParent` chain up to the application.
+///
+/// # Safety
+/// `element` must be a valid accessibility element.
+pub unsafe fn build(element: AXUIElementRef) -> ElementLocator {
+    let pid = get_pid(element);
+    let identifier = get_string_attribute(element, ax_attributes::IDENTIFIER).filter(|s| !s.is_empty());
+    let mut path = Vec::new();
+
+    core_foundation::base::CFRetain(element as *const c_void);
+    let mut current = element;
+
+    for _ in 0..ACCESSIBILITY_RECURSION_LIMIT {
+        let role = get_role(current).unwrap_or_default();
+        if role == ax_roles::APPLICATION {
+            break;
+        }
+        let Some(parent) = copy_element_attribute(current, ax_attributes::PARENT) else {
+            break;
+        };
+
+        let siblings = copy_children(parent);
+        let index = siblings
+            .iter()
+            .filter(|s| get_role(**s).unwrap_or_default() == role)
+            .position(|s| core_foundation::base::CFEqual(*s as *const c_void, current as *const c_void) != 0)
+            .unwrap_or(0);
+        release_all(siblings);
+
+        path.push(PathStep { role, index });
+        core_foundation::base::CFRelease(current as *const c_void);
+        current = parent;
+    }
+
+    core_foundation::base::CFRelease(current as *const c_void);
+    path.reverse();
+
+    let bundle_id = app_info::app_info(pid).and_then(|app| app.bundle_id);
+    ElementLocator { pid, bundle_id, identifier, path }
+}
+
+/// Finds the element described by `locator` again and returns its current geometry.
+///
+/// The path is followed first; if it no longer leads to an element (or leads to
+/// one with a different identifier), the app's tree is searched for the identifier.
+/// The returned locator carries the app's current process id.
+pub fn resolve(locator: &ElementLocator) -> Option<UIElementInfo> {
+    unsafe {
+        let element = copy_resolved(locator)?;
+        let info = element_info(element).map(|mut info| {
+            let pid = if info.pid > 0 { info.pid } else { locator.pid };
+            info.locator = Some(ElementLocator { pid, ..locator.clone() });
+            info
+        });
+        core_foundation::base::CFRelease(element as *const c_void);
+        info
+    }
+}
+
+/// Finds the element described by `locator` like [`resolve`], returning the element itself.
+///
+/// The returned element is retained and must be released by the caller.
+///
+/// # Safety
+/// The returned element must be released exactly once.
+pub unsafe fn copy_resolved(locator: &ElementLocator) -> Option<AXUIElementRef> {
+    let app = AXUIElementCreateApplication(current_pid(locator)?);
+    if app.is_null() {
+        return None;
+    }
+
+    let by_path = follow_path(app, &locator.path).filter(|&element| {
+        let matches = locator.identifier.is_none()
+            || get_string_attribute(element, ax_attributes::IDENTIFIER) == locator.identifier;
+        if !matches {
+            core_foundation::base::CFRelease(element as *const c_void);
+        }
+        matches
+    });
+
+    let found = by_path.or_else(|| {
+        locator.identifier.as_deref().and_then(|id| find_by_identifier(app, id))
+    });
+    core_foundation::base::CFRelease(app as *const c_void);
+    found
+}
+
+/// The process the locator's app runs as now: the recorded pid while it still
+/// belongs to that app, otherwise the app with the same bundle identifier that
+/// has a window on screen, e.g. after the app was restarted.
+fn current_pid(locator: &ElementLocator) -> Option<i32> {
+    let Some(bundle_id) = locator.bundle_id.as_deref() else {
+        return Some(locator.pid);
+    };
+    let runs_app = |pid: i32| app_info::app_info(pid).is_some_and(|app| app.bundle_id.as_deref() == Some(bundle_id));
+    if runs_app(locator.pid) {
+        return Some(locator.pid);
+    }
+    window_list::list_windows().into_iter().map(|w| w.owner_pid).find(|pid| runs_app(*pid))
+}
+
+/// Follows `path` from `root`. The returned element is retained.
+unsafe fn follow_path(root: AXUIElementRef, path: &[PathStep]) -> Option<AXUIElementRef> {
+    core_foundation::base::CFRetain(root as *const c_void);
+    let mut current = root;
+
+    for step in path {
+        let children = copy_children(current);
+        let next = children
+            .iter()
+            .filter(|c| get_role(**c).unwrap_or_default() == step.role)
+            .nth(step.index)
+            .copied();
+
+        if let Some(next) = next {
+            core_foundation::base::CFRetain(next as *const c_void);
+        }
+        release_all(children);
+        core_foundation::base::CFRelease(current as *const c_void);
+
+        current = next?;
+    }
+
+    Some(current)
+}
+
+/// Breadth-first search below `root` for an element whose `AXIdentifier` equals `identifier`.
+/// The returned element is retained.
+unsafe fn find_by_identifier(root: AXUIElementRef, identifier: &str) -> Option<AXUIElementRef> {
+    let mut found = None;
+
+    tree::walk_descendants(root, LOCATOR_SEARCH_LIMIT, |element, _| {
+        if found.is_some() {
+            return false;
+        }
+        if get_string_attribute(element, ax_attributes::IDENTIFIER).as_deref() == Some(identifier) {
+            core_foundation::base::CFRetain(element as *const c_void);
+            found = Some(element);
+            return false;
+        }
+        true
+    });
+
+    found
+}
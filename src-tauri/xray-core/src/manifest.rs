@@ -0,0 +1,188 @@
+use std::ffi::c_void;
+use std::path::{Path, PathBuf};
+use accessibility_sys::{AXUIElementCreateApplication, AXUIElementRef};
+use image::ImageFormat;
+use crate::accessibility::{copy_children, element_info, get_role, get_string_attribute, release_all, UIElementInfo};
+use crate::capture::{self, ScreenRect};
+use crate::constants::{ax_attributes, ax_roles};
+use crate::redaction::{self, RedactionConfig};
+use crate::selector::Selector;
+use crate::window_list;
+
+/// A list of screenshots to regenerate, read from a TOML file.
+///
+/// ```toml
+/// output_dir = "docs/images"
+/// padding = 4
+///
+/// [[shot]]
+/// app = "Finder"
+/// window = "Downloads"
+/// selector = "Toolbar Button[description=\"Back\"]"
+/// output = "finder-back.png"
+/// ```
+///
+/// Paths are relative to the manifest. `padding` (in points) and `format`
+/// (an image extension) set defaults that each shot may override.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub output_dir: Option<String>,
+    #[serde(default)]
+    pub padding: f64,
+    #[serde(default)]
+    pub format: Option<String>,
+    #[serde(default, rename = "shot")]
+    pub shots: Vec<ShotSpec>,
+}
+
+/// One screenshot of a [`Manifest`].
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ShotSpec {
+    /// The name of the owning application, as shown in the menu bar.
+    pub app: String,
+    /// Restricts the search to windows whose title contains this text.
+    #[serde(default)]
+    pub window: Option<String>,
+    /// A [`Selector`] for the element to capture.
+    pub selector: String,
+    #[serde(default)]
+    pub padding: Option<f64>,
+    #[serde(default)]
+    pub format: Option<String>,
+    pub output: String,
+}
+
+/// What happened to one shot of a manifest run.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShotResult {
+    pub output: String,
+    pub selector: String,
+    /// Why the shot failed, e.g. because the selector matched nothing.
+    pub error: Option<String>,
+}
+
+/// The outcome of capturing every shot of a manifest.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestReport {
+    pub captured: usize,
+    pub failed: usize,
+    pub results: Vec<ShotResult>,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        toml::from_str(&text).map_err(|e| e.to_string())
+    }
+}
+
+/// Captures every shot of the manifest at `path`, continuing past failures.
+pub fn run(path: &Path, redaction: &RedactionConfig) -> Result<ManifestReport, String> {
+    let manifest = Manifest::load(path)?;
+    let base = path.parent().unwrap_or(Path::new("."));
+    let output_dir = match &manifest.output_dir {
+        Some(dir) => base.join(dir),
+        None => base.to_path_buf(),
+    };
+
+    let results: Vec<ShotResult> = manifest.shots
+        .iter()
+        .map(|shot| {
+            let output = output_path(&output_dir, shot, &manifest);
+            let error = capture_shot(shot, &output, manifest.padding, redaction).err();
+            if let Some(e) = &error {
+                log::warn!("Manifest shot {} failed: {}", shot.output, e);
+            }
+            ShotResult { output: output.to_string_lossy().into_owned(), selector: shot.selector.clone(), error }
+        })
+        .collect();
+
+    let failed = results.iter().filter(|r| r.error.is_some()).count();
+    Ok(ManifestReport { captured: results.len() - failed, failed, results })
+}
+
+fn output_path(output_dir: &Path, shot: &ShotSpec, manifest: &Manifest) -> PathBuf {
+    let path = output_dir.join(&shot.output);
+    match shot.format.as_ref().or(manifest.format.as_ref()) {
+        Some(format) => path.with_extension(format),
+        None => path,
+    }
+}
+
+/// Resolves and captures a single shot to `output`; `default_padding` applies when the shot sets none.
+pub fn capture_shot(shot: &ShotSpec, output: &Path, default_padding: f64, redaction: &RedactionConfig) -> Result<(), String> {
+    let selector: Selector = shot.selector.parse()?;
+    let ext = output.extension().and_then(|e| e.to_str()).unwrap_or("png");
+    if ImageFormat::from_extension(ext).is_none() {
+        return Err(format!("Unsupported format: {}", ext));
+    }
+
+    let element = resolve_shot(shot, &selector)?;
+    let padding = shot.padding.unwrap_or(default_padding);
+
+    let (mut image, frame) = if padding > 0.0 {
+        let frame = ScreenRect {
+            x: element.global_x - padding,
+            y: element.global_y - padding,
+            width: element.width + padding * 2.0,
+            height: element.height + padding * 2.0,
+        };
+        (capture::grab_rect(&frame)?, frame)
+    } else {
+        capture::grab_element(&element)?
+    };
+    if redaction.enabled {
+        redaction::redact(&mut image, &frame, &element, redaction)?;
+    }
+
+    if let Some(dir) = output.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    capture::save_image(&image, &output.to_string_lossy()).map_err(String::from)
+}
+
+/// Finds the shot's element in its app, searching only the matching windows if one is named.
+fn resolve_shot(shot: &ShotSpec, selector: &Selector) -> Result<UIElementInfo, String> {
+    let pid = window_list::find_app_pid(&shot.app)
+        .ok_or_else(|| format!("{} has no window on screen", shot.app))?;
+
+    let Some(title) = &shot.window else {
+        return selector.find_in_app(pid).ok_or_else(|| "Selector matched nothing".to_string());
+    };
+
+    unsafe {
+        let app = AXUIElementCreateApplication(pid);
+        if app.is_null() {
+            return Err(format!("Cannot access {}", shot.app));
+        }
+        let windows: Vec<AXUIElementRef> = copy_children(app);
+        core_foundation::base::CFRelease(app as *const c_void);
+
+        let mut found = None;
+        let mut window_found = false;
+        for &window in &windows {
+            let is_match = get_role(window).as_deref() == Some(ax_roles::WINDOW)
+                && get_string_attribute(window, ax_attributes::TITLE).is_some_and(|t| t.contains(title.as_str()));
+            if !is_match {
+                continue;
+            }
+            window_found = true;
+            let matches = selector.select_all(window);
+            found = matches.first().and_then(|e| element_info(*e));
+            release_all(matches);
+            if found.is_some() {
+                break;
+            }
+        }
+        release_all(windows);
+
+        match (found, window_found) {
+            (Some(info), _) => Ok(info),
+            (None, true) => Err("Selector matched nothing".to_string()),
+            (None, false) => Err(format!("{} has no window titled \"{}\"", shot.app, title)),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use accessibility_sys::{kAXTrustedCheckOptionPrompt, AXIsProcessTrusted, AXIsProcessTrustedWithOptions};
+use core_foundation::base::TCFType;
+use core_foundation::boolean::CFBoolean;
+use core_foundation::dictionary::CFDictionary;
+use core_foundation::string::CFString;
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGPreflightScreenCaptureAccess() -> bool;
+    fn CGRequestScreenCaptureAccess() -> bool;
+}
+
+/// A macOS privacy permission xray needs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Permission {
+    /// Reading other apps' UI elements; without it nothing is highlighted.
+    Accessibility,
+    /// Reading the screen; without it captures only show the wallpaper and xray's own windows.
+    ScreenRecording,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 2] = [Self::Accessibility, Self::ScreenRecording];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Accessibility => "Accessibility",
+            Self::ScreenRecording => "Screen Recording",
+        }
+    }
+
+    /// The System Settings pane where the permission is granted.
+    pub fn settings_url(self) -> &'static str {
+        match self {
+            Self::Accessibility => "x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility",
+            Self::ScreenRecording => "x-apple.systempreferences:com.apple.preference.security?Privacy_ScreenCapture",
+        }
+    }
+
+    pub fn is_granted(self) -> bool {
+        unsafe {
+            match self {
+                Self::Accessibility => AXIsProcessTrusted(),
+                Self::ScreenRecording => CGPreflightScreenCaptureAccess(),
+            }
+        }
+    }
+
+    /// Shows the system prompt for the permission, which also adds the app to
+    /// the list in System Settings. Returns whether it is already granted.
+    pub fn request(self) -> bool {
+        unsafe {
+            match self {
+                Self::Accessibility => {
+                    let key = CFString::wrap_under_get_rule(kAXTrustedCheckOptionPrompt);
+                    let options = CFDictionary::from_CFType_pairs(&[(key.as_CFType(), CFBoolean::true_value().as_CFType())]);
+                    AXIsProcessTrustedWithOptions(options.as_concrete_TypeRef())
+                }
+                Self::ScreenRecording => CGRequestScreenCaptureAccess(),
+            }
+        }
+    }
+}
+
+/// Which permissions the current process has.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionState {
+    pub accessibility: bool,
+    pub screen_recording: bool,
+}
+
+impl PermissionState {
+    pub fn check() -> Self {
+        Self {
+            accessibility: Permission::Accessibility.is_granted(),
+            screen_recording: Permission::ScreenRecording.is_granted(),
+        }
+    }
+
+    pub fn missing(&self) -> Vec<Permission> {
+        Permission::ALL.into_iter().filter(|p| !self.has(*p)).collect()
+    }
+
+    pub fn has(&self, permission: Permission) -> bool {
+        match permission {
+            Permission::Accessibility => self.accessibility,
+            Permission::ScreenRecording => self.screen_recording,
+        }
+    }
+}
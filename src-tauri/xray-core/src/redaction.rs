@@ -0,0 +1,405 @@
+use std::path::Path;
+use image::{imageops, Rgba, RgbaImage};
+use accessibility_sys::AXUIElementCreateApplication;
+use crate::accessibility::{get_position, get_role, get_size, get_string_attribute, UIElementInfo};
+use crate::capture::ScreenRect;
+use crate::constants::{ax_attributes, ax_roles, REDACTION_CONFIG_FILE, REDACTION_SEARCH_LIMIT};
+use crate::{tree, window_list};
+
+/// Matches elements that must be hidden. Every field that is set must match;
+/// `identifier` may contain `*` wildcards.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionRule {
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub subrole: Option<String>,
+    #[serde(default)]
+    pub identifier: Option<String>,
+}
+
+impl RedactionRule {
+    fn matches(&self, role: &str, subrole: Option<&str>, identifier: Option<&str>) -> bool {
+        if self.role.is_none() && self.subrole.is_none() && self.identifier.is_none() {
+            return false;
+        }
+        self.role.as_deref().is_none_or(|r| r == role)
+            && self.subrole.as_deref().is_none_or(|s| Some(s) == subrole)
+            && self.identifier.as_deref().is_none_or(|pattern| {
+                identifier.is_some_and(|id| wildcard_match(pattern, id))
+            })
+    }
+}
+
+/// How redacted areas are rendered.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RedactionStyle {
+    /// Gaussian blur with the given sigma in pixels.
+    Blur { radius: f32 },
+    /// Mosaic of square blocks with the given edge length in pixels.
+    Pixelate { block: u32 },
+    /// Solid fill with an RGBA color.
+    Fill { color: [u8; 4] },
+}
+
+/// What to redact and how, persisted in the app config directory.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionConfig {
+    pub enabled: bool,
+    pub style: RedactionStyle,
+    /// Elements inside the captured area matching any rule are redacted.
+    pub rules: Vec<RedactionRule>,
+    /// Fixed screen areas that are always redacted when they overlap a capture.
+    pub regions: Vec<ScreenRect>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            style: RedactionStyle::Pixelate { block: 12 },
+            rules: vec![RedactionRule {
+                subrole: Some(ax_roles::SECURE_TEXT_FIELD.to_string()),
+                ..Default::default()
+            }],
+            regions: Vec::new(),
+        }
+    }
+}
+
+impl RedactionConfig {
+    /// Loads the config from `dir`, falling back to the defaults.
+    pub fn load(dir: &Path) -> Self {
+        std::fs::read_to_string(dir.join(REDACTION_CONFIG_FILE))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(dir.join(REDACTION_CONFIG_FILE), json).map_err(|e| e.to_string())
+    }
+}
+
+/// Hides sensitive areas of a captured `image` covering `frame`.
+///
+/// Searches the accessibility trees of the app owning `element` and of the
+/// other apps with windows in `frame` for elements matching the configured
+/// rules, and adds the configured fixed regions. Fails if the search could be
+/// incomplete, so a capture is never saved with sensitive fields showing.
+pub fn redact(image: &mut RgbaImage, frame: &ScreenRect, element: &UIElementInfo, config: &RedactionConfig) -> Result<(), String> {
+    let rects = find_redacted_rects(frame, element, config)?;
+    redact_rects(image, frame, &rects, config);
+    Ok(())
+}
+
+/// Collects the screen areas inside `frame` that [`redact`] would hide.
+///
+/// Useful when many images of the same area are taken, e.g. while recording.
+pub fn find_redacted_rects(frame: &ScreenRect, element: &UIElementInfo, config: &RedactionConfig) -> Result<Vec<ScreenRect>, String> {
+    let mut rects = find_matching_rects(element, frame, &config.rules)?;
+    rects.extend(config.regions.iter().filter(|r| r.intersects(frame)).copied());
+    Ok(rects)
+}
+
+/// Renders the configured style over each of `rects` in an `image` covering `frame`.
+pub fn redact_rects(image: &mut RgbaImage, frame: &ScreenRect, rects: &[ScreenRect], config: &RedactionConfig) {
+    for rect in rects {
+        apply_style(image, frame, rect, &config.style);
+    }
+}
+
+/// Collects the rects of elements matching `rules` that overlap `frame`, in the
+/// app owning `element` and every other app with a window in `frame`.
+fn find_matching_rects(element: &UIElementInfo, frame: &ScreenRect, rules: &[RedactionRule]) -> Result<Vec<ScreenRect>, String> {
+    if rules.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let owner = [element.pid, element.locator.as_ref().map_or(0, |l| l.pid)]
+        .into_iter()
+        .find(|pid| *pid > 0)
+        .or_else(|| window_list::find_window(element.window_id).map(|w| w.owner_pid).filter(|pid| *pid > 0));
+    let Some(owner) = owner else {
+        log::warn!("Can't tell which app owns the {} being captured, so it can't be redacted", element.role);
+        return Err("Can't tell which app owns the element, so sensitive fields can't be found".to_string());
+    };
+
+    // Padding or a window-sized capture can take in windows of other apps.
+    let own_pid = std::process::id() as i32;
+    let mut pids = vec![owner];
+    for window in window_list::list_windows() {
+        let bounds = ScreenRect { x: window.x, y: window.y, width: window.width, height: window.height };
+        if window.owner_pid > 0 && window.owner_pid != own_pid && bounds.intersects(frame) && !pids.contains(&window.owner_pid) {
+            pids.push(window.owner_pid);
+        }
+    }
+
+    let mut rects = Vec::new();
+    for pid in pids {
+        rects.extend(unsafe { find_matching_rects_in_app(pid, frame, rules)? });
+    }
+    Ok(rects)
+}
+
+unsafe fn find_matching_rects_in_app(pid: i32, frame: &ScreenRect, rules: &[RedactionRule]) -> Result<Vec<ScreenRect>, String> {
+    let app = AXUIElementCreateApplication(pid);
+    if app.is_null() {
+        return Err(format!("Can't inspect process {} for sensitive fields", pid));
+    }
+
+    let mut rects = Vec::new();
+    let complete = tree::walk_descendants(app, REDACTION_SEARCH_LIMIT, |child, _| {
+        let rect = match (get_position(child), get_size(child)) {
+            (Some((x, y)), Some((width, height))) => Some(ScreenRect { x, y, width, height }),
+            _ => None,
+        };
+
+        // Skip subtrees that lie entirely outside the capture.
+        if let Some(rect) = rect {
+            if !rect.intersects(frame) {
+                return false;
+            }
+        }
+
+        let role = get_role(child).unwrap_or_default();
+        let subrole = get_string_attribute(child, ax_attributes::SUBROLE);
+        let identifier = get_string_attribute(child, ax_attributes::IDENTIFIER);
+
+        if rules.iter().any(|r| r.matches(&role, subrole.as_deref(), identifier.as_deref())) {
+            if let Some(rect) = rect {
+                rects.push(rect);
+            }
+            return false;
+        }
+        true
+    });
+    core_foundation::base::CFRelease(app as *const std::ffi::c_void);
+
+    if !complete {
+        log::warn!("Process {} has more than {} elements in the capture, so it can't be redacted", pid, REDACTION_SEARCH_LIMIT);
+        return Err(format!(
+            "Process {} has more than {} elements in the captured area, so sensitive fields may be missed. \
+             Turn off redaction (\"Redact Sensitive Fields\" in the menu, or --no-redact) to capture it anyway",
+            pid, REDACTION_SEARCH_LIMIT
+        ));
+    }
+    Ok(rects)
+}
+
+/// Renders `style` over the part of `image` showing `rect`.
+fn apply_style(image: &mut RgbaImage, frame: &ScreenRect, rect: &ScreenRect, style: &RedactionStyle) {
+    let scale_x = image.width() as f64 / frame.width;
+    let scale_y = image.height() as f64 / frame.height;
+
+    let left = (((rect.x - frame.x) * scale_x).floor().max(0.0) as u32).min(image.width());
+    let top = (((rect.y - frame.y) * scale_y).floor().max(0.0) as u32).min(image.height());
+    let right = (((rect.x + rect.width - frame.x) * scale_x).ceil().max(0.0) as u32).min(image.width());
+    let bottom = (((rect.y + rect.height - frame.y) * scale_y).ceil().max(0.0) as u32).min(image.height());
+
+    if right <= left || bottom <= top {
+        return;
+    }
+    let (width, height) = (right - left, bottom - top);
+
+    match style {
+        RedactionStyle::Fill { color } => {
+            for y in top..bottom {
+                for x in left..right {
+                    image.put_pixel(x, y, Rgba(*color));
+                }
+            }
+        }
+        RedactionStyle::Blur { radius } => {
+            let region = imageops::crop_imm(image, left, top, width, height).to_image();
+            let blurred = imageops::blur(&region, radius.max(1.0));
+            imageops::replace(image, &blurred, left as i64, top as i64);
+        }
+        RedactionStyle::Pixelate { block } => {
+            let block = (*block).max(1);
+            for by in (top..bottom).step_by(block as usize) {
+                for bx in (left..right).step_by(block as usize) {
+                    let bw = block.min(right - bx);
+                    let bh = block.min(bottom - by);
+                    let avg = average(image, bx, by, bw, bh);
+                    for y in by..by + bh {
+                        for x in bx..bx + bw {
+                            image.put_pixel(x, y, avg);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn average(image: &RgbaImage, x: u32, y: u32, width: u32, height: u32) -> Rgba<u8> {
+    let mut sum = [0u64; 4];
+    for py in y..y + height {
+        for px in x..x + width {
+            let p = image.get_pixel(px, py);
+            for (s, c) in sum.iter_mut().zip(p.0) {
+                *s += c as u64;
+            }
+        }
+    }
+    let n = (width as u64 * height as u64).max(1);
+    Rgba(sum.map(|s| (s / n) as u8))
+}
+
+/// Matches `text` against `pattern`, where `*` matches any run of characters.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !text.starts_with(first) || !text[first.len()..].ends_with(last) {
+        return false;
+    }
+
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcard_match_without_stars_is_exact() {
+        assert!(wildcard_match("password", "password"));
+        assert!(!wildcard_match("password", "password2"));
+        assert!(!wildcard_match("password", ""));
+    }
+
+    #[test]
+    fn wildcard_match_with_stars() {
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("login.*", "login.password"));
+        assert!(wildcard_match("*.password", "login.password"));
+        assert!(wildcard_match("*pass*", "login.password.field"));
+        assert!(wildcard_match("a*b*c", "a-b-c"));
+        assert!(wildcard_match("a*b*c", "abc"));
+        assert!(!wildcard_match("a*b*c", "a-c-b"));
+        assert!(!wildcard_match("login.*", "signup.password"));
+        // The prefix and suffix may not share characters.
+        assert!(!wildcard_match("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn empty_rule_matches_nothing() {
+        assert!(!RedactionRule::default().matches("AXTextField", None, None));
+    }
+
+    #[test]
+    fn rule_fields_must_all_match() {
+        let rule = RedactionRule {
+            role: Some("AXTextField".to_string()),
+            subrole: Some(ax_roles::SECURE_TEXT_FIELD.to_string()),
+            identifier: None,
+        };
+        assert!(rule.matches("AXTextField", Some(ax_roles::SECURE_TEXT_FIELD), None));
+        assert!(!rule.matches("AXTextField", None, None));
+        assert!(!rule.matches("AXStaticText", Some(ax_roles::SECURE_TEXT_FIELD), None));
+    }
+
+    #[test]
+    fn identifier_rule_needs_an_identifier() {
+        let rule = RedactionRule { identifier: Some("card-*".to_string()), ..Default::default() };
+        assert!(rule.matches("AXTextField", None, Some("card-number")));
+        assert!(!rule.matches("AXTextField", None, Some("name")));
+        assert!(!rule.matches("AXTextField", None, None));
+    }
+
+    #[test]
+    fn default_config_redacts_secure_fields() {
+        let config = RedactionConfig::default();
+        assert!(config.enabled);
+        assert!(config.rules.iter().any(|r| r.matches("AXTextField", Some(ax_roles::SECURE_TEXT_FIELD), None)));
+    }
+
+    /// A 10x10 point frame captured at 2x, so each point covers 2x2 pixels.
+    const FRAME: ScreenRect = ScreenRect { x: 100.0, y: 50.0, width: 10.0, height: 10.0 };
+    /// Covers pixels 4..12 on both axes of an image of [`FRAME`].
+    const RECT: ScreenRect = ScreenRect { x: 102.0, y: 52.0, width: 4.0, height: 4.0 };
+
+    fn checkerboard() -> RgbaImage {
+        RgbaImage::from_fn(20, 20, |x, y| if (x + y) % 2 == 0 { Rgba([0, 0, 0, 255]) } else { Rgba([255, 255, 255, 255]) })
+    }
+
+    fn inside(x: u32, y: u32) -> bool {
+        (4..12).contains(&x) && (4..12).contains(&y)
+    }
+
+    fn assert_outside_untouched(before: &RgbaImage, after: &RgbaImage) {
+        for (x, y, pixel) in after.enumerate_pixels() {
+            if !inside(x, y) {
+                assert_eq!(pixel, before.get_pixel(x, y), "pixel {},{} changed", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn fill_covers_the_scaled_rect() {
+        let before = checkerboard();
+        let mut image = before.clone();
+        apply_style(&mut image, &FRAME, &RECT, &RedactionStyle::Fill { color: [255, 0, 0, 255] });
+        for (x, y, pixel) in image.enumerate_pixels() {
+            if inside(x, y) {
+                assert_eq!(*pixel, Rgba([255, 0, 0, 255]), "pixel {},{} not filled", x, y);
+            }
+        }
+        assert_outside_untouched(&before, &image);
+    }
+
+    #[test]
+    fn pixelate_averages_each_block() {
+        let before = checkerboard();
+        let mut image = before.clone();
+        apply_style(&mut image, &FRAME, &RECT, &RedactionStyle::Pixelate { block: 4 });
+        // Every 4x4 block of a checkerboard averages to the same grey.
+        for y in 4..12 {
+            for x in 4..12 {
+                assert_eq!(*image.get_pixel(x, y), Rgba([127, 127, 127, 255]));
+            }
+        }
+        assert_outside_untouched(&before, &image);
+    }
+
+    #[test]
+    fn blur_softens_only_the_rect() {
+        let before = checkerboard();
+        let mut image = before.clone();
+        apply_style(&mut image, &FRAME, &RECT, &RedactionStyle::Blur { radius: 2.0 });
+        for y in 4..12 {
+            for x in 4..12 {
+                let value = image.get_pixel(x, y).0[0];
+                assert!((32..=223).contains(&value), "pixel {},{} is still {}", x, y, value);
+            }
+        }
+        assert_outside_untouched(&before, &image);
+    }
+
+    #[test]
+    fn rect_outside_the_frame_changes_nothing() {
+        let before = checkerboard();
+        let mut image = before.clone();
+        let rect = ScreenRect { x: 200.0, y: 50.0, width: 4.0, height: 4.0 };
+        apply_style(&mut image, &FRAME, &rect, &RedactionStyle::Fill { color: [255, 0, 0, 255] });
+        assert_eq!(image, before);
+    }
+}
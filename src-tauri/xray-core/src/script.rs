@@ -0,0 +1,335 @@
+use std::ffi::c_void;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::accessibility::{get_string_attribute, UIElementInfo};
+use crate::actions::{self, AttributeValue};
+use crate::capture::{self, ScreenRect};
+use crate::constants::{
+    ax_actions, ax_attributes, ax_roles, SCRIPT_POLL_MS, SCRIPT_STEP_DELAY_MS, SCRIPT_STEP_TIMEOUT_MS, SCRIPT_VERSION,
+};
+use crate::selector::Selector;
+use crate::window_list;
+
+/// A recorded sequence of clicks and typing in one app, saved as YAML or JSON.
+///
+/// ```yaml
+/// version: 1
+/// app: Finder
+/// steps:
+///   - action: click
+///     selector: AXButton[description="Back"]
+///     role: AXButton
+///     label: Back
+///   - action: type
+///     selector: AXTextField[identifier="search"]
+///     role: AXTextField
+///     text: invoices
+/// ```
+///
+/// Each step finds its element with a [`Selector`]; `role`, `label` and
+/// `identifier` are kept for the exporters.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Script {
+    #[serde(default = "default_version")]
+    pub version: u32,
+    /// The name of the application, as shown in the menu bar.
+    pub app: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_id: Option<String>,
+    #[serde(default)]
+    pub steps: Vec<ScriptStep>,
+}
+
+/// One interaction of a [`Script`].
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptStep {
+    pub action: StepAction,
+    pub selector: String,
+    pub role: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+    /// The text a `type` step enters; it replaces the field's value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StepAction {
+    Click,
+    Type,
+}
+
+/// The step a replay stopped at.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepFailure {
+    /// The 1-based position of the step in the script.
+    pub index: usize,
+    pub step: ScriptStep,
+    pub error: String,
+    /// A capture of the app's window at the time of the failure.
+    pub capture: Option<String>,
+}
+
+/// The outcome of replaying a script.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayReport {
+    pub passed: usize,
+    pub total: usize,
+    pub failure: Option<StepFailure>,
+}
+
+fn default_version() -> u32 {
+    SCRIPT_VERSION
+}
+
+impl Script {
+    pub fn new(app: String, bundle_id: Option<String>) -> Self {
+        Self { version: SCRIPT_VERSION, app, bundle_id, steps: Vec::new() }
+    }
+
+    /// Reads a script; the extension picks YAML (`.yaml`, `.yml`) or JSON.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let script: Script = match extension(path).as_str() {
+            "yaml" | "yml" => serde_yaml::from_str(&text).map_err(|e| e.to_string())?,
+            "json" => serde_json::from_str(&text).map_err(|e| e.to_string())?,
+            ext => return Err(format!("Unsupported script format: .{}", ext)),
+        };
+        if script.version > SCRIPT_VERSION {
+            return Err(format!("Script version {} is newer than this version of xray", script.version));
+        }
+        Ok(script)
+    }
+
+    /// Writes the script; the extension picks YAML (`.yaml`, `.yml`) or JSON.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = match extension(path).as_str() {
+            "yaml" | "yml" => serde_yaml::to_string(self).map_err(|e| e.to_string())?,
+            "json" => serde_json::to_string_pretty(self).map_err(|e| e.to_string())?,
+            ext => return Err(format!("Unsupported script format: .{}", ext)),
+        };
+        std::fs::write(path, text).map_err(|e| e.to_string())
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase()
+}
+
+/// Describes a click on `info` as a step, with the shortest selector that finds it again.
+///
+/// Selectors by identifier, title or description are tried first and kept only
+/// if they lead back to the same element; otherwise the element's locator path
+/// is spelled out. `info` must carry a locator.
+pub fn click_step(info: &UIElementInfo) -> Option<ScriptStep> {
+    let locator = info.locator.as_ref()?;
+    let element = actions::copy_element(info)?;
+    let (identifier, title, description) = unsafe {
+        let read = |attribute| get_string_attribute(element, attribute).filter(|s| !s.is_empty());
+        let attributes = (read(ax_attributes::IDENTIFIER), read(ax_attributes::TITLE), read(ax_attributes::DESCRIPTION));
+        core_foundation::base::CFRelease(element as *const c_void);
+        attributes
+    };
+
+    let attributes = [("identifier", identifier.as_deref()), ("title", title.as_deref()), ("description", description.as_deref())];
+    let selector = selector_candidates(&info.role, &attributes)
+        .into_iter()
+        .find(|selector| finds(selector, locator.pid, info))
+        .unwrap_or_else(|| {
+            let steps: Vec<String> = locator.path.iter().map(|s| format!("{}:nth({})", s.role, s.index + 1)).collect();
+            format!("> {}", steps.join(" > "))
+        });
+
+    Some(ScriptStep {
+        action: StepAction::Click,
+        selector,
+        role: info.role.clone(),
+        label: title.or(description),
+        identifier,
+        text: None,
+    })
+}
+
+/// Selectors matching `role` and one of the named `attributes`, in order, skipping
+/// missing values and those that can't be quoted.
+fn selector_candidates(role: &str, attributes: &[(&str, Option<&str>)]) -> Vec<String> {
+    attributes
+        .iter()
+        .filter_map(|(name, value)| Some(format!("{}[{}={}]", role, name, quote((*value)?)?)))
+        .collect()
+}
+
+/// Quotes a selector value, or returns `None` when it contains both kinds of quotes.
+fn quote(value: &str) -> Option<String> {
+    if !value.contains('"') {
+        Some(format!("\"{}\"", value))
+    } else if !value.contains('\'') {
+        Some(format!("'{}'", value))
+    } else {
+        None
+    }
+}
+
+fn finds(selector: &str, pid: i32, info: &UIElementInfo) -> bool {
+    let Ok(selector) = selector.parse::<Selector>() else {
+        return false;
+    };
+    selector.find_in_app(pid).is_some_and(|found| same_rect(&found, info))
+}
+
+fn same_rect(a: &UIElementInfo, b: &UIElementInfo) -> bool {
+    let close = |a: f64, b: f64| (a - b).abs() < 1.0;
+    close(a.global_x, b.global_x) && close(a.global_y, b.global_y) && close(a.width, b.width) && close(a.height, b.height)
+}
+
+/// Whether the user can type into elements with `role`.
+pub fn is_text_input(role: &str) -> bool {
+    matches!(role, ax_roles::TEXT_FIELD | ax_roles::TEXT_AREA | ax_roles::COMBO_BOX)
+}
+
+/// Whether `info` is a password field, whose text must not be recorded.
+pub fn is_secure(info: &UIElementInfo) -> bool {
+    read_attribute(info, ax_attributes::SUBROLE).as_deref() == Some(ax_roles::SECURE_TEXT_FIELD)
+}
+
+/// Reads a string attribute of the live element `info` describes, e.g. the `AXValue` of a text field.
+pub fn read_attribute(info: &UIElementInfo, attribute: &str) -> Option<String> {
+    let element = actions::copy_element(info)?;
+    unsafe {
+        let value = get_string_attribute(element, attribute);
+        core_foundation::base::CFRelease(element as *const c_void);
+        value
+    }
+}
+
+/// Replays `script` step by step through accessibility actions, stopping at the first failure.
+///
+/// Each step waits up to [`SCRIPT_STEP_TIMEOUT_MS`] for its element. When a
+/// step fails, the app's window is captured to `failure_dir`.
+pub fn replay(script: &Script, failure_dir: &Path) -> ReplayReport {
+    let total = script.steps.len();
+    for (i, step) in script.steps.iter().enumerate() {
+        if let Err(error) = run_step(&script.app, step) {
+            log::warn!("Step {} of {} failed: {}", i + 1, total, error);
+            let capture = capture_failure(&script.app, failure_dir, i + 1)
+                .map_err(|e| log::warn!("Failed to capture the failing step: {}", e))
+                .ok();
+            let failure = StepFailure { index: i + 1, step: step.clone(), error, capture };
+            return ReplayReport { passed: i, total, failure: Some(failure) };
+        }
+        thread::sleep(Duration::from_millis(SCRIPT_STEP_DELAY_MS));
+    }
+    ReplayReport { passed: total, total, failure: None }
+}
+
+fn run_step(app: &str, step: &ScriptStep) -> Result<(), String> {
+    let selector: Selector = step.selector.parse()?;
+    let element = wait_for(app, &selector)?;
+    match step.action {
+        // Text fields usually can't be pressed; focusing them is what a click does.
+        StepAction::Click => actions::perform_action(&element, ax_actions::PRESS).or_else(|e| {
+            actions::set_attribute(&element, ax_attributes::FOCUSED, &AttributeValue::Bool(true)).map_err(|_| e)
+        }),
+        StepAction::Type => {
+            let text = step.text.clone().unwrap_or_default();
+            let _ = actions::set_attribute(&element, ax_attributes::FOCUSED, &AttributeValue::Bool(true));
+            actions::set_attribute(&element, ax_attributes::VALUE, &AttributeValue::Text(text))
+        }
+    }
+}
+
+/// Polls for the element until it appears or the step times out.
+fn wait_for(app: &str, selector: &Selector) -> Result<UIElementInfo, String> {
+    let started = Instant::now();
+    loop {
+        let pid = window_list::find_app_pid(app);
+        if let Some(element) = pid.and_then(|pid| selector.find_in_app(pid)) {
+            return Ok(element);
+        }
+        if started.elapsed() >= Duration::from_millis(SCRIPT_STEP_TIMEOUT_MS) {
+            return Err(match pid {
+                Some(_) => "Selector matched nothing".to_string(),
+                None => format!("{} has no window on screen", app),
+            });
+        }
+        thread::sleep(Duration::from_millis(SCRIPT_POLL_MS));
+    }
+}
+
+/// Captures the app's frontmost window to `step-N-failure.png` in `dir`.
+fn capture_failure(app: &str, dir: &Path, index: usize) -> Result<String, String> {
+    let window = window_list::list_windows()
+        .into_iter()
+        .find(|w| w.layer == 0 && w.owner_name.eq_ignore_ascii_case(app))
+        .ok_or_else(|| format!("{} has no window on screen", app))?;
+    let rect = ScreenRect { x: window.x, y: window.y, width: window.width, height: window.height };
+    let image = capture::grab_rect(&rect)?;
+
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let path: PathBuf = capture::next_available_path(&dir.join(format!("step-{}-failure.png", index)));
+    let path = path.to_string_lossy().into_owned();
+    capture::save_image(&image, &path)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quote_picks_the_other_quote() {
+        assert_eq!(quote("Save").as_deref(), Some("\"Save\""));
+        assert_eq!(quote("Say \"hi\"").as_deref(), Some("'Say \"hi\"'"));
+        assert_eq!(quote("it's").as_deref(), Some("\"it's\""));
+        assert_eq!(quote("it's \"quoted\""), None);
+    }
+
+    #[test]
+    fn candidates_skip_missing_and_unquotable_values() {
+        let attributes = [("identifier", None), ("title", Some("it's \"both\"")), ("description", Some("Back"))];
+        assert_eq!(selector_candidates("AXButton", &attributes), ["AXButton[description=\"Back\"]"]);
+    }
+
+    #[test]
+    fn candidates_parse_back() {
+        let values = ["Save", "Say \"hi\"", "it's", "a ] b", "line\nbreak", "#1 > x:nth(2)"];
+        for value in values {
+            let candidates = selector_candidates("AXButton", &[("title", Some(value))]);
+            assert_eq!(candidates.len(), 1);
+            let parsed = candidates[0].parse::<Selector>();
+            assert!(parsed.is_ok(), "{:?}: {:?}", candidates[0], parsed);
+        }
+    }
+
+    #[test]
+    fn script_round_trips_through_yaml_and_json() {
+        let dir = std::env::temp_dir().join(format!("xray-script-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut script = Script::new("Finder".to_string(), Some("com.apple.finder".to_string()));
+        script.steps.push(ScriptStep {
+            action: StepAction::Type,
+            selector: "AXTextField[identifier=\"search\"]".to_string(),
+            role: "AXTextField".to_string(),
+            label: None,
+            identifier: Some("search".to_string()),
+            text: Some("invoices".to_string()),
+        });
+
+        for name in ["script.yaml", "script.json"] {
+            let path = dir.join(name);
+            script.save(&path).unwrap();
+            let loaded = Script::load(&path).unwrap();
+            assert_eq!((loaded.app.as_str(), loaded.bundle_id.as_deref()), ("Finder", Some("com.apple.finder")));
+            assert_eq!(loaded.steps, script.steps);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
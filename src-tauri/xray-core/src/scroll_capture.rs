@@ -0,0 +1,184 @@
+use std::ffi::c_void;
+use std::thread;
+use std::time::Duration;
+use accessibility_sys::AXUIElementRef;
+use core_graphics::event::{CGEvent, CGEventTapLocation, ScrollEventUnit};
+use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+use core_graphics::geometry::CGPoint;
+use image::RgbaImage;
+use crate::accessibility::{
+    copy_children, copy_element_at_position, copy_element_attribute, get_number_attribute, get_position,
+    get_role, get_size, release_all, set_number_attribute, UIElementInfo,
+};
+use crate::capture::{self, ScreenRect};
+use crate::constants::{
+    ax_attributes, ax_roles, ACCESSIBILITY_RECURSION_LIMIT, SCROLL_CAPTURE_MAX_FRAMES, SCROLL_SETTLE_MS,
+    SCROLL_STEP_FRACTION,
+};
+use crate::redaction::{self, RedactionConfig};
+use crate::stitch::{self, StitchOptions};
+
+/// Captures the entire content of the scroll area containing `element` as one tall image.
+///
+/// The scroll area is scrolled to the top, then stepped down through its
+/// vertical scroll bar (or with scroll-wheel events when it has none),
+/// grabbing the viewport after each step. The frames are stitched together.
+/// When the scroll bar was used, the original scroll position is restored
+/// afterwards; scrolling with the wheel leaves the area at the end of its content.
+pub fn capture_scroll_area(element: &UIElementInfo, redaction: &RedactionConfig) -> Result<RgbaImage, String> {
+    unsafe {
+        let center_x = element.global_x + element.width / 2.0;
+        let center_y = element.global_y + element.height / 2.0;
+        let hit = copy_element_at_position(center_x, center_y)
+            .ok_or_else(|| "No element under the capture area".to_string())?;
+        let scroll_area = find_scroll_area(hit).ok_or_else(|| "The element is not inside a scroll area".to_string())?;
+
+        let result = capture_frames(scroll_area, element, redaction);
+        core_foundation::base::CFRelease(scroll_area as *const c_void);
+
+        let (frames, ignore_right) = result?;
+        stitch::stitch_vertical(&frames, &StitchOptions { ignore_right })
+    }
+}
+
+/// Walks up from `element` (inclusive) to the nearest `AXScrollArea`. Consumes `element`.
+unsafe fn find_scroll_area(element: AXUIElementRef) -> Option<AXUIElementRef> {
+    let mut current = element;
+    for _ in 0..ACCESSIBILITY_RECURSION_LIMIT {
+        if get_role(current).as_deref() == Some(ax_roles::SCROLL_AREA) {
+            return Some(current);
+        }
+        let parent = copy_element_attribute(current, ax_attributes::PARENT);
+        core_foundation::base::CFRelease(current as *const c_void);
+        current = parent?;
+    }
+    core_foundation::base::CFRelease(current as *const c_void);
+    None
+}
+
+/// Scrolls through `scroll_area`, returning the frames and the scroll bar width in pixels.
+unsafe fn capture_frames(
+    scroll_area: AXUIElementRef,
+    element: &UIElementInfo,
+    redaction: &RedactionConfig,
+) -> Result<(Vec<RgbaImage>, u32), String> {
+    let (Some((x, y)), Some((width, height))) = (get_position(scroll_area), get_size(scroll_area)) else {
+        return Err("The scroll area has no geometry".to_string());
+    };
+    let viewport = ScreenRect { x, y, width, height };
+
+    let grab = || -> Result<RgbaImage, String> {
+        thread::sleep(Duration::from_millis(SCROLL_SETTLE_MS));
+        let mut frame = capture::grab_rect(&viewport)?;
+        if redaction.enabled {
+            redaction::redact(&mut frame, &viewport, element, redaction)?;
+        }
+        Ok(frame)
+    };
+
+    match copy_element_attribute(scroll_area, ax_attributes::VERTICAL_SCROLL_BAR) {
+        Some(bar) => {
+            let bar_width = get_size(bar).map_or(0.0, |(w, _)| w);
+            let result = scroll_with_bar(bar, grab);
+            core_foundation::base::CFRelease(bar as *const c_void);
+            result.map(|frames| {
+                let scale = frames.first().map_or(1.0, |f| f.width() as f64 / width);
+                (frames, (bar_width * scale).ceil() as u32)
+            })
+        }
+        None => scroll_with_wheel(&viewport, grab).map(|frames| (frames, 0)),
+    }
+}
+
+/// Steps the scroll bar's `AXValue` from 0 to 1, restoring the original value afterwards.
+unsafe fn scroll_with_bar(
+    bar: AXUIElementRef,
+    grab: impl Fn() -> Result<RgbaImage, String>,
+) -> Result<Vec<RgbaImage>, String> {
+    let original = get_number_attribute(bar, ax_attributes::VALUE).unwrap_or(0.0);
+    let step = scroll_step(bar);
+
+    let mut frames = Vec::new();
+    let mut value = 0.0;
+    let result = loop {
+        if !set_number_attribute(bar, ax_attributes::VALUE, value) {
+            break Err("The scroll bar cannot be moved".to_string());
+        }
+        match grab() {
+            Ok(frame) => frames.push(frame),
+            Err(e) => break Err(e),
+        }
+        if value >= 1.0 || frames.len() >= SCROLL_CAPTURE_MAX_FRAMES {
+            break Ok(());
+        }
+        value = (value + step).min(1.0);
+    };
+
+    set_number_attribute(bar, ax_attributes::VALUE, original);
+    result.map(|_| frames)
+}
+
+/// Works out how far to move the scroll bar value per step from the size of its thumb.
+///
+/// The thumb covers the visible fraction `f` of the content, and the value
+/// spans the remaining `1 - f`, so one viewport is `f / (1 - f)` in value units.
+unsafe fn scroll_step(bar: AXUIElementRef) -> f64 {
+    let bar_length = get_size(bar).map_or(0.0, |(_, h)| h);
+    let children = copy_children(bar);
+    let thumb_length = children
+        .iter()
+        .find(|c| get_role(**c).as_deref() == Some(ax_roles::VALUE_INDICATOR))
+        .and_then(|c| get_size(*c))
+        .map_or(0.0, |(_, h)| h);
+    release_all(children);
+
+    if bar_length <= 0.0 || thumb_length <= 0.0 || thumb_length >= bar_length {
+        return 0.1;
+    }
+    let visible = thumb_length / bar_length;
+    (SCROLL_STEP_FRACTION * visible / (1.0 - visible)).clamp(0.01, 1.0)
+}
+
+/// Scrolls with synthetic scroll-wheel events over the viewport until the content stops moving.
+fn scroll_with_wheel(
+    viewport: &ScreenRect,
+    grab: impl Fn() -> Result<RgbaImage, String>,
+) -> Result<Vec<RgbaImage>, String> {
+    let step = (viewport.height * SCROLL_STEP_FRACTION) as i32;
+    let center = CGPoint::new(viewport.x + viewport.width / 2.0, viewport.y + viewport.height / 2.0);
+
+    // Scroll up until the content stops moving, so the capture starts at the top.
+    let mut previous = None;
+    for _ in 0..SCROLL_CAPTURE_MAX_FRAMES {
+        post_scroll(center, step * 4)?;
+        thread::sleep(Duration::from_millis(SCROLL_SETTLE_MS));
+        let frame = capture::grab_rect(viewport)?;
+        if previous.as_ref() == Some(&frame) {
+            break;
+        }
+        previous = Some(frame);
+    }
+
+    let mut frames: Vec<RgbaImage> = Vec::new();
+    while frames.len() < SCROLL_CAPTURE_MAX_FRAMES {
+        let frame = grab()?;
+        if frames.last() == Some(&frame) {
+            break;
+        }
+        frames.push(frame);
+        post_scroll(center, -step)?;
+    }
+    Ok(frames)
+}
+
+/// Posts a pixel-based scroll-wheel event at `location`, so it reaches the view there
+/// wherever the cursor is. Positive deltas scroll up.
+fn post_scroll(location: CGPoint, delta: i32) -> Result<(), String> {
+    let source = CGEventSource::new(CGEventSourceStateID::HIDSystemState)
+        .map_err(|_| "Failed to create event source".to_string())?;
+    let event = CGEvent::new_scroll_event(source, ScrollEventUnit::PIXEL, 1, delta, 0, 0)
+        .map_err(|_| "Failed to create scroll event".to_string())?;
+    event.set_location(location);
+    event.post(CGEventTapLocation::HID);
+    Ok(())
+}
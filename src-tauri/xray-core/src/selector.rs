@@ -0,0 +1,462 @@
+use std::ffi::c_void;
+use std::fmt;
+use std::str::FromStr;
+use accessibility_sys::{AXUIElementCreateApplication, AXUIElementRef};
+use crate::accessibility::{copy_children, element_info, get_role, get_string_attribute, release_all, UIElementInfo};
+use crate::constants::{ax_attributes, SELECTOR_SEARCH_LIMIT};
+use crate::tree;
+
+/// A CSS-like query over the accessibility tree.
+///
+/// Steps are separated by whitespace (any descendant) or `>` (direct child).
+/// Each step names a role, with or without the `AX` prefix, or `*` for any,
+/// and may add `#identifier`, attribute filters and `:nth(n)`:
+///
+/// ```text
+/// Window[title^="Settings"] > Toolbar Button#save
+/// AXTable Row:nth(3) StaticText[value*="Total"]
+/// ```
+///
+/// Filters match `title`, `description`, `identifier`, `subrole`, `value` or
+/// `role` with `=` (equals), `*=` (contains), `^=` (starts with) or `$=`
+/// (ends with). `:nth(n)` keeps only the n-th match (1-based) of its step.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selector {
+    steps: Vec<Step>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Step {
+    combinator: Combinator,
+    role: Option<String>,
+    filters: Vec<AttributeFilter>,
+    nth: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct AttributeFilter {
+    attribute: &'static str,
+    op: MatchOp,
+    value: String,
+}
+
+/// The attributes a filter can match, by the name used in selectors.
+const ATTRIBUTE_NAMES: &[(&str, &str)] = &[
+    ("title", ax_attributes::TITLE),
+    ("description", ax_attributes::DESCRIPTION),
+    ("identifier", ax_attributes::IDENTIFIER),
+    ("subrole", ax_attributes::SUBROLE),
+    ("value", ax_attributes::VALUE),
+    ("role", ax_attributes::ROLE),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MatchOp {
+    Equals,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+impl FromStr for Selector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser { chars: s.chars().collect(), pos: 0 }.parse()
+    }
+}
+
+/// Writes the selector in the syntax it is parsed from.
+///
+/// Values are quoted with `"`, or `'` when they contain `"`; a value
+/// containing both can't be written back.
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, step) in self.steps.iter().enumerate() {
+            match (i, step.combinator) {
+                (0, Combinator::Descendant) => {}
+                (0, Combinator::Child) => f.write_str("> ")?,
+                (_, Combinator::Descendant) => f.write_str(" ")?,
+                (_, Combinator::Child) => f.write_str(" > ")?,
+            }
+            f.write_str(step.role.as_deref().unwrap_or("*"))?;
+            for filter in &step.filters {
+                let name = ATTRIBUTE_NAMES.iter().find(|(_, a)| *a == filter.attribute).map_or("", |(n, _)| *n);
+                let op = match filter.op {
+                    MatchOp::Equals => "=",
+                    MatchOp::Contains => "*=",
+                    MatchOp::StartsWith => "^=",
+                    MatchOp::EndsWith => "$=",
+                };
+                let quote = if filter.value.contains('"') { '\'' } else { '"' };
+                write!(f, "[{}{}{}{}{}]", name, op, quote, filter.value, quote)?;
+            }
+            if let Some(n) = step.nth {
+                write!(f, ":nth({})", n)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn parse(mut self) -> Result<Selector, String> {
+        let mut steps = Vec::new();
+        loop {
+            let had_space = self.skip_whitespace();
+            let Some(c) = self.peek() else {
+                break;
+            };
+
+            let combinator = if c == '>' {
+                self.pos += 1;
+                self.skip_whitespace();
+                Combinator::Child
+            } else {
+                if !steps.is_empty() && !had_space {
+                    return Err(format!("Unexpected '{}' at {}", c, self.pos));
+                }
+                Combinator::Descendant
+            };
+            steps.push(self.parse_step(combinator)?);
+        }
+
+        if steps.is_empty() {
+            return Err("Empty selector".to_string());
+        }
+        Ok(Selector { steps })
+    }
+
+    fn parse_step(&mut self, combinator: Combinator) -> Result<Step, String> {
+        let mut step = Step { combinator, role: None, filters: Vec::new(), nth: None };
+
+        let any = self.peek() == Some('*');
+        if any {
+            self.pos += 1;
+        } else {
+            let name = self.ident();
+            if !name.is_empty() {
+                step.role = Some(if name.starts_with("AX") { name } else { format!("AX{}", name) });
+            }
+        }
+
+        loop {
+            match self.peek() {
+                Some('#') => {
+                    self.pos += 1;
+                    let id = self.ident();
+                    if id.is_empty() {
+                        return Err(format!("Expected an identifier after '#' at {}", self.pos));
+                    }
+                    step.filters.push(AttributeFilter { attribute: ax_attributes::IDENTIFIER, op: MatchOp::Equals, value: id });
+                }
+                Some('[') => {
+                    self.pos += 1;
+                    step.filters.push(self.parse_filter()?);
+                }
+                Some(':') => {
+                    self.pos += 1;
+                    step.nth = Some(self.parse_nth()?);
+                }
+                _ => break,
+            }
+        }
+
+        if !any && step.role.is_none() && step.filters.is_empty() && step.nth.is_none() {
+            return Err(format!("Expected a role at {}", self.pos));
+        }
+        Ok(step)
+    }
+
+    fn parse_filter(&mut self) -> Result<AttributeFilter, String> {
+        self.skip_whitespace();
+        let name = self.ident();
+        let name = if name == "id" { "identifier".to_string() } else { name };
+        let attribute = ATTRIBUTE_NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, attribute)| *attribute)
+            .ok_or_else(|| format!("Unknown attribute '{}'", name))?;
+
+        self.skip_whitespace();
+        let op = match (self.peek(), self.chars.get(self.pos + 1)) {
+            (Some('='), _) => MatchOp::Equals,
+            (Some('*'), Some('=')) => MatchOp::Contains,
+            (Some('^'), Some('=')) => MatchOp::StartsWith,
+            (Some('$'), Some('=')) => MatchOp::EndsWith,
+            _ => return Err(format!("Expected an operator at {}", self.pos)),
+        };
+        self.pos += if op == MatchOp::Equals { 1 } else { 2 };
+
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.pos += 1;
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c != quote) {
+                    self.pos += 1;
+                }
+                if self.peek().is_none() {
+                    return Err("Unterminated string".to_string());
+                }
+                let value: String = self.chars[start..self.pos].iter().collect();
+                self.pos += 1;
+                value
+            }
+            _ => self.ident(),
+        };
+
+        self.skip_whitespace();
+        if self.peek() != Some(']') {
+            return Err(format!("Expected ']' at {}", self.pos));
+        }
+        self.pos += 1;
+        Ok(AttributeFilter { attribute, op, value })
+    }
+
+    fn parse_nth(&mut self) -> Result<usize, String> {
+        if self.ident() != "nth" || self.peek() != Some('(') {
+            return Err(format!("Expected ':nth(n)' at {}", self.pos));
+        }
+        self.pos += 1;
+        let n = self.ident().parse::<usize>().ok().filter(|n| *n > 0)
+            .ok_or_else(|| format!("Expected a positive number at {}", self.pos))?;
+        if self.peek() != Some(')') {
+            return Err(format!("Expected ')' at {}", self.pos));
+        }
+        self.pos += 1;
+        Ok(n)
+    }
+
+    fn ident(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.')) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+}
+
+impl Step {
+    unsafe fn matches(&self, element: AXUIElementRef) -> bool {
+        if let Some(role) = &self.role {
+            if get_role(element).as_deref() != Some(role.as_str()) {
+                return false;
+            }
+        }
+        self.filters.iter().all(|f| {
+            get_string_attribute(element, f.attribute).is_some_and(|actual| match f.op {
+                MatchOp::Equals => actual == f.value,
+                MatchOp::Contains => actual.contains(&f.value),
+                MatchOp::StartsWith => actual.starts_with(&f.value),
+                MatchOp::EndsWith => actual.ends_with(&f.value),
+            })
+        })
+    }
+}
+
+impl Selector {
+    /// Finds every element below `root` matching the selector, in tree order.
+    ///
+    /// The returned elements are retained and must be released by the caller.
+    ///
+    /// # Safety
+    /// `root` must be a valid accessibility element.
+    pub unsafe fn select_all(&self, root: AXUIElementRef) -> Vec<AXUIElementRef> {
+        core_foundation::base::CFRetain(root as *const c_void);
+        let mut current = vec![root];
+
+        for step in &self.steps {
+            let mut next: Vec<AXUIElementRef> = Vec::new();
+            let keep = |element: AXUIElementRef, next: &mut Vec<AXUIElementRef>| {
+                let seen = next.iter().any(|e| {
+                    core_foundation::base::CFEqual(*e as *const c_void, element as *const c_void) != 0
+                });
+                if !seen {
+                    core_foundation::base::CFRetain(element as *const c_void);
+                    next.push(element);
+                }
+            };
+
+            for &parent in &current {
+                match step.combinator {
+                    Combinator::Child => {
+                        let children = copy_children(parent);
+                        for &child in &children {
+                            if step.matches(child) {
+                                keep(child, &mut next);
+                            }
+                        }
+                        release_all(children);
+                    }
+                    Combinator::Descendant => {
+                        tree::walk_descendants(parent, SELECTOR_SEARCH_LIMIT, |child, _| {
+                            if step.matches(child) {
+                                keep(child, &mut next);
+                            }
+                            true
+                        });
+                    }
+                }
+            }
+
+            release_all(current);
+            current = match step.nth {
+                Some(n) if n <= next.len() => {
+                    let chosen = next.remove(n - 1);
+                    release_all(next);
+                    vec![chosen]
+                }
+                Some(_) => {
+                    release_all(next);
+                    Vec::new()
+                }
+                None => next,
+            };
+            if current.is_empty() {
+                break;
+            }
+        }
+        current
+    }
+
+    /// Finds the first element in the app with process id `pid` matching the selector.
+    pub fn find_in_app(&self, pid: i32) -> Option<UIElementInfo> {
+        unsafe {
+            let app = AXUIElementCreateApplication(pid);
+            if app.is_null() {
+                return None;
+            }
+            let found = self.select_all(app);
+            let info = found.first().and_then(|e| element_info(*e));
+            release_all(found);
+            core_foundation::base::CFRelease(app as *const c_void);
+            info
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Selector {
+        s.parse().unwrap_or_else(|e| panic!("{:?}: {}", s, e))
+    }
+
+    fn filter(attribute: &'static str, op: MatchOp, value: &str) -> AttributeFilter {
+        AttributeFilter { attribute, op, value: value.to_string() }
+    }
+
+    #[test]
+    fn parses_steps_and_combinators() {
+        let selector = parse("Window[title^=\"Settings\"] > Toolbar  AXButton#save");
+        assert_eq!(
+            selector.steps,
+            [
+                Step {
+                    combinator: Combinator::Descendant,
+                    role: Some("AXWindow".to_string()),
+                    filters: vec![filter(ax_attributes::TITLE, MatchOp::StartsWith, "Settings")],
+                    nth: None,
+                },
+                Step { combinator: Combinator::Child, role: Some("AXToolbar".to_string()), filters: Vec::new(), nth: None },
+                Step {
+                    combinator: Combinator::Descendant,
+                    role: Some("AXButton".to_string()),
+                    filters: vec![filter(ax_attributes::IDENTIFIER, MatchOp::Equals, "save")],
+                    nth: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_operators_and_quotes() {
+        let selector = parse("*[title=Save][description*='say \"hi\"'][value^=\"a ] b\"][id$=x-1.2]:nth(3)");
+        let step = &selector.steps[0];
+        assert_eq!(step.role, None);
+        assert_eq!(step.nth, Some(3));
+        assert_eq!(
+            step.filters,
+            [
+                filter(ax_attributes::TITLE, MatchOp::Equals, "Save"),
+                filter(ax_attributes::DESCRIPTION, MatchOp::Contains, "say \"hi\""),
+                filter(ax_attributes::VALUE, MatchOp::StartsWith, "a ] b"),
+                filter(ax_attributes::IDENTIFIER, MatchOp::EndsWith, "x-1.2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn leading_child_and_bare_filters() {
+        let selector = parse("> Window:nth(1) > #main");
+        assert_eq!(selector.steps[0].combinator, Combinator::Child);
+        assert_eq!(selector.steps[1].role, None);
+        assert_eq!(selector.steps[1].filters, [filter(ax_attributes::IDENTIFIER, MatchOp::Equals, "main")]);
+    }
+
+    #[test]
+    fn round_trips_through_display() {
+        let selectors = [
+            "AXWindow[title^=\"Settings\"] > AXToolbar AXButton[identifier=\"save\"]",
+            "> AXWindow:nth(1) > AXGroup:nth(2) > AXButton:nth(3)",
+            "*[description*='say \"hi\"'][value$=\"it's\"]",
+            "AXRow[subrole=\"AXOutlineRow\"][role=\"AXRow\"]:nth(10)",
+        ];
+        for text in selectors {
+            let selector = parse(text);
+            assert_eq!(selector.to_string(), text);
+            assert_eq!(parse(&selector.to_string()), selector);
+        }
+        let loose = parse("Window  >Button#ok [ title = 'OK' ]");
+        assert_eq!(parse(&loose.to_string()), loose);
+    }
+
+    #[test]
+    fn rejects_malformed_selectors() {
+        let cases = [
+            ("", "Empty selector"),
+            ("   ", "Empty selector"),
+            ("Button#", "Expected an identifier"),
+            ("Button[name=\"x\"]", "Unknown attribute"),
+            ("Button[title~=\"x\"]", "Expected an operator"),
+            ("Button[title=\"x]", "Unterminated string"),
+            ("Button[title=\"x\"", "Expected ']'"),
+            ("Button:first", "Expected ':nth(n)'"),
+            ("Button:nth(0)", "Expected a positive number"),
+            ("Button:nth(two)", "Expected a positive number"),
+            ("Button:nth(2", "Expected ')'"),
+            ("Window >", "Expected a role"),
+            ("Window > > Button", "Expected a role"),
+            ("Button]", "Unexpected ']'"),
+        ];
+        for (text, error) in cases {
+            let result = text.parse::<Selector>();
+            assert!(result.as_ref().is_err_and(|e| e.contains(error)), "{:?}: {:?}", text, result);
+        }
+    }
+}
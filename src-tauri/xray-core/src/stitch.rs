@@ -0,0 +1,210 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use image::{GenericImage, GenericImageView, RgbaImage};
+
+/// Tuning for [`stitch_vertical`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StitchOptions {
+    /// Columns at the right edge excluded from overlap detection, e.g. a scroll
+    /// bar whose thumb moves between frames.
+    pub ignore_right: u32,
+}
+
+/// Stitches viewport frames taken while scrolling down into one tall image.
+///
+/// All frames must have the same size. Rows that stay put between the first
+/// two frames (sticky headers and footers) are kept once, at the top and bottom
+/// of the result. Between consecutive frames, the largest run of rows at the
+/// bottom of one frame that reappears at the top of the next is treated as the
+/// overlap and dropped. Frames identical to their predecessor add nothing.
+pub fn stitch_vertical(frames: &[RgbaImage], options: &StitchOptions) -> Result<RgbaImage, String> {
+    let first = frames.first().ok_or_else(|| "No frames to stitch".to_string())?;
+    let (width, height) = first.dimensions();
+    if frames.iter().any(|f| f.dimensions() != (width, height)) {
+        return Err("All frames must have the same size".to_string());
+    }
+
+    let hashes: Vec<Vec<u64>> = frames.iter().map(|f| row_hashes(f, options.ignore_right)).collect();
+    let (sticky_top, sticky_bottom) = match hashes.get(1) {
+        Some(second) => sticky_rows(&hashes[0], second),
+        None => (0, 0),
+    };
+    let body_end = height as usize - sticky_bottom;
+
+    // Collect (frame index, first row, last row exclusive) slices to copy.
+    let mut slices = vec![(0, 0, body_end)];
+    for i in 1..frames.len() {
+        let overlap = find_overlap(&hashes[i - 1][sticky_top..body_end], &hashes[i][sticky_top..body_end]);
+        let start = sticky_top + overlap;
+        if start < body_end {
+            slices.push((i, start, body_end));
+        }
+    }
+    slices.push((frames.len() - 1, body_end, height as usize));
+
+    let total: usize = slices.iter().map(|(_, start, end)| end - start).sum();
+    let mut output = RgbaImage::new(width, total as u32);
+    let mut y = 0u32;
+    for (index, start, end) in slices {
+        let rows = (end - start) as u32;
+        if rows == 0 {
+            continue;
+        }
+        let view = frames[index].view(0, start as u32, width, rows);
+        output.copy_from(&*view, 0, y).map_err(|e| e.to_string())?;
+        y += rows;
+    }
+
+    Ok(output)
+}
+
+/// Returns how many leading rows of `next` repeat the trailing rows of `prev`.
+///
+/// Only exact row matches count; `0` means the frames do not overlap and
+/// `prev.len()` means nothing moved.
+pub fn find_overlap(prev: &[u64], next: &[u64]) -> usize {
+    if prev == next {
+        return prev.len();
+    }
+    let max = prev.len().min(next.len());
+    (1..max)
+        .rev()
+        .find(|&n| prev[prev.len() - n..] == next[..n])
+        .unwrap_or(0)
+}
+
+/// Counts rows identical at the same position in both frames, from the top and from the bottom.
+fn sticky_rows(a: &[u64], b: &[u64]) -> (usize, usize) {
+    if a == b {
+        return (0, 0);
+    }
+    let top = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let bottom = a.iter().rev().zip(b.iter().rev()).take_while(|(x, y)| x == y).count();
+    (top, bottom.min(a.len() - top))
+}
+
+fn row_hashes(image: &RgbaImage, ignore_right: u32) -> Vec<u64> {
+    let width = image.width().saturating_sub(ignore_right).max(1) as usize;
+    image
+        .rows()
+        .map(|row| {
+            let mut hasher = DefaultHasher::new();
+            for pixel in row.take(width) {
+                pixel.0.hash(&mut hasher);
+            }
+            hasher.finish()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// A tall page whose rows all differ, so every overlap is unambiguous.
+    fn page(rows: u32) -> RgbaImage {
+        RgbaImage::from_fn(4, rows, |x, y| Rgba([y as u8, (y * 7) as u8, x as u8, 255]))
+    }
+
+    fn rows(image: &RgbaImage, top: u32, height: u32) -> RgbaImage {
+        image.view(0, top, image.width(), height).to_image()
+    }
+
+    fn stack(parts: &[&RgbaImage]) -> RgbaImage {
+        let height = parts.iter().map(|p| p.height()).sum();
+        let mut output = RgbaImage::new(parts[0].width(), height);
+        let mut y = 0;
+        for part in parts {
+            output.copy_from(*part, 0, y).unwrap();
+            y += part.height();
+        }
+        output
+    }
+
+    fn solid(rows: u32, shade: u8) -> RgbaImage {
+        RgbaImage::from_pixel(4, rows, Rgba([shade, shade, 255 - shade, 255]))
+    }
+
+    #[test]
+    fn overlapping_frames_are_joined() {
+        let page = page(40);
+        let frames = [rows(&page, 0, 20), rows(&page, 8, 20), rows(&page, 16, 20)];
+        let stitched = stitch_vertical(&frames, &StitchOptions::default()).unwrap();
+        assert_eq!(stitched, rows(&page, 0, 36));
+    }
+
+    #[test]
+    fn frames_without_overlap_are_stacked() {
+        let page = page(30);
+        let frames = [rows(&page, 0, 10), rows(&page, 10, 10), rows(&page, 20, 10)];
+        let stitched = stitch_vertical(&frames, &StitchOptions::default()).unwrap();
+        assert_eq!(stitched, page);
+    }
+
+    #[test]
+    fn unchanged_frames_add_nothing() {
+        let frame = rows(&page(10), 0, 10);
+        let stitched = stitch_vertical(&[frame.clone(), frame.clone(), frame.clone()], &StitchOptions::default()).unwrap();
+        assert_eq!(stitched, frame);
+    }
+
+    #[test]
+    fn sticky_header_and_footer_are_kept_once() {
+        let page = page(40);
+        let (header, footer) = (solid(3, 200), solid(2, 100));
+        let frames: Vec<RgbaImage> =
+            [0, 6, 12].iter().map(|&top| stack(&[&header, &rows(&page, top, 14), &footer])).collect();
+        let stitched = stitch_vertical(&frames, &StitchOptions::default()).unwrap();
+        assert_eq!(stitched, stack(&[&header, &rows(&page, 0, 26), &footer]));
+    }
+
+    #[test]
+    fn moving_scroll_bar_is_ignored() {
+        let page = page(30);
+        let mut frames = vec![rows(&page, 0, 20), rows(&page, 10, 20)];
+        // A scroll bar thumb in the last column that moves between frames.
+        for (i, frame) in frames.iter_mut().enumerate() {
+            for y in 0..frame.height() {
+                frame.put_pixel(3, y, Rgba([0, 0, 0, if y / 10 == i as u32 { 255 } else { 0 }]));
+            }
+        }
+        let stitched = stitch_vertical(&frames, &StitchOptions { ignore_right: 1 }).unwrap();
+        assert_eq!(stitched.height(), 30);
+    }
+
+    #[test]
+    fn mismatched_frames_are_rejected() {
+        assert!(stitch_vertical(&[page(10), page(12)], &StitchOptions::default()).is_err());
+        assert!(stitch_vertical(&[], &StitchOptions::default()).is_err());
+    }
+
+    #[test]
+    fn find_overlap_prefers_the_largest_exact_match() {
+        assert_eq!(find_overlap(&[1, 2, 3, 4], &[3, 4, 5, 6]), 2);
+        assert_eq!(find_overlap(&[1, 2, 3, 4], &[5, 6, 7, 8]), 0);
+        assert_eq!(find_overlap(&[1, 2, 3, 4], &[1, 2, 3, 4]), 4);
+        // A repeating pattern matches at several offsets; the largest one wins even
+        // if the view actually scrolled further.
+        assert_eq!(find_overlap(&[9, 1, 2, 1, 2], &[1, 2, 1, 2, 7]), 4);
+        // Uniform content can't be told apart from an unchanged frame.
+        assert_eq!(find_overlap(&[5; 6], &[5; 6]), 6);
+        assert_eq!(find_overlap(&[5; 6], &[5, 5, 5, 5, 5, 8]), 5);
+    }
+
+    #[test]
+    fn sticky_rows_counts_rows_in_place() {
+        assert_eq!(sticky_rows(&[1, 2, 3, 4, 5], &[1, 2, 6, 7, 5]), (2, 1));
+        assert_eq!(sticky_rows(&[1, 2, 3], &[1, 2, 3]), (0, 0));
+        assert_eq!(sticky_rows(&[1, 2, 3], &[4, 5, 6]), (0, 0));
+    }
+
+    #[test]
+    fn row_hashes_skip_ignored_columns() {
+        let mut a = page(3);
+        let b = a.clone();
+        a.put_pixel(3, 1, Rgba([1, 2, 3, 4]));
+        assert_ne!(row_hashes(&a, 0), row_hashes(&b, 0));
+        assert_eq!(row_hashes(&a, 1), row_hashes(&b, 1));
+    }
+}
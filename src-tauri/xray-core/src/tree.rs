@@ -0,0 +1,143 @@
+use crate::accessibility::{copy_children, element_info, get_role, get_string_attribute, release_all, UIElementInfo};
+use crate::constants::{ax_attributes, ACCESSIBILITY_RECURSION_LIMIT, SNAPSHOT_NODE_LIMIT};
+use crate::window_list;
+use accessibility_sys::{AXUIElementCreateApplication, AXUIElementRef};
+use std::collections::VecDeque;
+use std::ffi::c_void;
+
+/// Visits the descendants of `root` breadth-first.
+///
+/// `visit` receives each element and its depth (children of `root` have depth 1)
+/// and returns whether to descend into that element's children. Elements are only
+/// borrowed for the duration of the callback. The walk stops after `max_nodes`
+/// elements or at [`ACCESSIBILITY_RECURSION_LIMIT`] levels, whichever comes first.
+/// Returns `false` if elements were skipped because of `max_nodes`.
+///
+/// # Safety
+/// `root` must be a valid accessibility element.
+pub unsafe fn walk_descendants(
+    root: AXUIElementRef,
+    max_nodes: usize,
+    mut visit: impl FnMut(AXUIElementRef, usize) -> bool,
+) -> bool {
+    let mut queue: VecDeque<(AXUIElementRef, usize)> =
+        copy_children(root).into_iter().map(|c| (c, 1)).collect();
+    let mut visited = 0;
+
+    while let Some((element, depth)) = queue.pop_front() {
+        visited += 1;
+        if visited <= max_nodes
+            && visit(element, depth)
+            && depth < ACCESSIBILITY_RECURSION_LIMIT as usize
+        {
+            queue.extend(copy_children(element).into_iter().map(|c| (c, depth + 1)));
+        }
+        core_foundation::base::CFRelease(element as *const c_void);
+    }
+    visited <= max_nodes
+}
+
+/// An element and its descendants, as captured by [`snapshot`].
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElementNode {
+    #[serde(flatten)]
+    pub info: UIElementInfo,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ElementNode>,
+}
+
+/// Copies `root` and its descendants up to `max_depth` levels into plain data.
+///
+/// Elements without a position (such as the application element) get an empty rect.
+/// At most `max_nodes` elements are copied.
+///
+/// # Safety
+/// `root` must be a valid accessibility element.
+pub unsafe fn snapshot(root: AXUIElementRef, max_depth: usize, max_nodes: usize) -> ElementNode {
+    let mut remaining = max_nodes;
+    snapshot_node(root, max_depth.min(ACCESSIBILITY_RECURSION_LIMIT as usize), &mut remaining)
+}
+
+unsafe fn snapshot_node(element: AXUIElementRef, depth: usize, remaining: &mut usize) -> ElementNode {
+    *remaining = remaining.saturating_sub(1);
+    let info = element_info(element).unwrap_or_else(|| UIElementInfo {
+        x: 0.0,
+        y: 0.0,
+        width: 0.0,
+        height: 0.0,
+        global_x: 0.0,
+        global_y: 0.0,
+        window_id: 0,
+        role: get_role(element).unwrap_or_else(|| "Unknown".to_string()),
+        title: get_string_attribute(element, ax_attributes::TITLE),
+        locator: None,
+        pid: 0,
+        app_name: None,
+        bundle_id: None,
+        executable_path: None,
+        window_title: None,
+        window_layer: None,
+    });
+
+    let mut children = Vec::new();
+    if depth > 0 {
+        let elements = copy_children(element);
+        for &child in &elements {
+            if *remaining == 0 {
+                break;
+            }
+            children.push(snapshot_node(child, depth - 1, remaining));
+        }
+        release_all(elements);
+    }
+    ElementNode { info, children }
+}
+
+/// Snapshots the accessibility tree of the app with process id `pid`.
+pub fn snapshot_app(pid: i32, max_depth: usize) -> Option<ElementNode> {
+    unsafe {
+        let app = AXUIElementCreateApplication(pid);
+        if app.is_null() {
+            return None;
+        }
+        let node = snapshot(app, max_depth, SNAPSHOT_NODE_LIMIT);
+        core_foundation::base::CFRelease(app as *const c_void);
+        Some(node)
+    }
+}
+
+/// Snapshots the accessibility tree of the window with window server number `window_id`.
+pub fn snapshot_window(window_id: u32, max_depth: usize) -> Option<ElementNode> {
+    unsafe {
+        let window = copy_window(window_id)?;
+        let node = snapshot(window, max_depth, SNAPSHOT_NODE_LIMIT);
+        core_foundation::base::CFRelease(window as *const c_void);
+        Some(node)
+    }
+}
+
+/// Finds the accessibility element of the window with window server number `window_id`.
+///
+/// The returned element is retained and must be released by the caller.
+pub fn copy_window(window_id: u32) -> Option<AXUIElementRef> {
+    let owner = window_list::find_window(window_id)?.owner_pid;
+    unsafe {
+        let app = AXUIElementCreateApplication(owner);
+        if app.is_null() {
+            return None;
+        }
+        let windows = copy_children(app);
+        core_foundation::base::CFRelease(app as *const c_void);
+
+        let window = windows
+            .iter()
+            .copied()
+            .find(|w| element_info(*w).is_some_and(|info| info.window_id == window_id));
+        if let Some(window) = window {
+            core_foundation::base::CFRetain(window as *const c_void);
+        }
+        release_all(windows);
+        window
+    }
+}
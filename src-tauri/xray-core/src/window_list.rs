@@ -0,0 +1,154 @@
+use crate::app_info::{self, AppInfo};
+use core_foundation::base::{CFType, TCFType};
+use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+use core_foundation::number::CFNumber;
+use core_foundation::string::{CFString, CFStringRef};
+use core_graphics::window::{
+    copy_window_info, kCGNullWindowID, kCGWindowBounds, kCGWindowLayer, kCGWindowListExcludeDesktopElements,
+    kCGWindowListOptionIncludingWindow, kCGWindowListOptionOnScreenOnly, kCGWindowName, kCGWindowNumber,
+    kCGWindowOwnerName, kCGWindowOwnerPID,
+};
+
+/// Describes an on-screen window as reported by the window server.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowInfo {
+    pub number: u32,
+    pub owner_pid: i32,
+    pub owner_name: String,
+    pub title: String,
+    pub layer: i32,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// An app and its on-screen windows, for picking a window without hovering.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppWindows {
+    pub app: AppInfo,
+    /// Frontmost first.
+    pub windows: Vec<WindowInfo>,
+}
+
+/// Lists the windows currently on screen, frontmost first, excluding the desktop.
+pub fn list_windows() -> Vec<WindowInfo> {
+    copy_windows(kCGWindowListOptionOnScreenOnly | kCGWindowListExcludeDesktopElements, kCGNullWindowID)
+}
+
+/// Lists the regular on-screen windows of other processes grouped by app, frontmost app first.
+///
+/// With a non-empty `query`, only windows whose title, app name or bundle
+/// identifier contains it (ignoring case) are listed.
+pub fn list_app_windows(query: &str) -> Vec<AppWindows> {
+    let query = query.trim().to_lowercase();
+    let own_pid = std::process::id() as i32;
+    let mut groups: Vec<AppWindows> = Vec::new();
+
+    let windows = list_windows()
+        .into_iter()
+        .filter(|w| w.layer == 0 && w.owner_pid != own_pid && w.width > 0.0 && w.height > 0.0);
+    for window in windows {
+        let index = match groups.iter().position(|g| g.app.pid == window.owner_pid) {
+            Some(index) => index,
+            None => {
+                let mut app = app_info::app_info(window.owner_pid).unwrap_or_default();
+                app.pid = window.owner_pid;
+                if app.name.is_empty() {
+                    app.name = window.owner_name.clone();
+                }
+                groups.push(AppWindows { app, windows: Vec::new() });
+                groups.len() - 1
+            }
+        };
+        groups[index].windows.push(window);
+    }
+
+    if !query.is_empty() {
+        for group in &mut groups {
+            let app = &group.app;
+            let app_matches = app.name.to_lowercase().contains(&query)
+                || app.bundle_id.as_ref().is_some_and(|id| id.to_lowercase().contains(&query));
+            if !app_matches {
+                group.windows.retain(|w| w.title.to_lowercase().contains(&query));
+            }
+        }
+        groups.retain(|g| !g.windows.is_empty());
+    }
+    groups
+}
+
+/// Finds the process id of the app named `name` (case-insensitively) among the apps with a window on screen.
+pub fn find_app_pid(name: &str) -> Option<i32> {
+    list_windows().into_iter().find(|w| w.owner_name.eq_ignore_ascii_case(name)).map(|w| w.owner_pid)
+}
+
+/// Finds the name of the app with process id `pid` among the apps with a window on screen.
+pub fn find_app_name(pid: i32) -> Option<String> {
+    list_windows().into_iter().find(|w| w.owner_pid == pid).map(|w| w.owner_name)
+}
+
+/// Looks up a single window by its window server number.
+pub fn find_window(number: u32) -> Option<WindowInfo> {
+    if number == 0 {
+        return None;
+    }
+    copy_windows(kCGWindowListOptionIncludingWindow, number).into_iter().next()
+}
+
+fn copy_windows(option: u32, relative_to: u32) -> Vec<WindowInfo> {
+    let Some(array) = copy_window_info(option, relative_to) else {
+        return Vec::new();
+    };
+
+    array
+        .iter()
+        .filter_map(|item| unsafe {
+            let dict = CFDictionary::<CFString, CFType>::wrap_under_get_rule(*item as CFDictionaryRef);
+            parse_window(&dict)
+        })
+        .collect()
+}
+
+unsafe fn parse_window(dict: &CFDictionary<CFString, CFType>) -> Option<WindowInfo> {
+    let number = get_number(dict, kCGWindowNumber)?.to_i64()? as u32;
+    let bounds = get_value(dict, kCGWindowBounds)
+        .and_then(|v| v.downcast::<CFDictionary>())
+        .map(|b| CFDictionary::<CFString, CFType>::wrap_under_get_rule(b.as_concrete_TypeRef()));
+
+    let bound = |key: &str| -> f64 {
+        bounds
+            .as_ref()
+            .and_then(|b| b.find(CFString::new(key)))
+            .and_then(|v| v.downcast::<CFNumber>())
+            .and_then(|n| n.to_f64())
+            .unwrap_or(0.0)
+    };
+
+    Some(WindowInfo {
+        number,
+        owner_pid: get_number(dict, kCGWindowOwnerPID).and_then(|n| n.to_i32()).unwrap_or(0),
+        owner_name: get_string(dict, kCGWindowOwnerName).unwrap_or_default(),
+        title: get_string(dict, kCGWindowName).unwrap_or_default(),
+        layer: get_number(dict, kCGWindowLayer).and_then(|n| n.to_i32()).unwrap_or(0),
+        x: bound("X"),
+        y: bound("Y"),
+        width: bound("Width"),
+        height: bound("Height"),
+    })
+}
+
+unsafe fn get_value(dict: &CFDictionary<CFString, CFType>, key: CFStringRef) -> Option<CFType> {
+    let key = CFString::wrap_under_get_rule(key);
+    dict.find(&key).map(|v| v.clone())
+}
+
+unsafe fn get_number(dict: &CFDictionary<CFString, CFType>, key: CFStringRef) -> Option<CFNumber> {
+    get_value(dict, key)?.downcast::<CFNumber>()
+}
+
+unsafe fn get_string(dict: &CFDictionary<CFString, CFType>, key: CFStringRef) -> Option<String> {
+    get_value(dict, key)?.downcast::<CFString>().map(|s| s.to_string())
+}
@@ -0,0 +1,126 @@
+import { useEffect, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+
+type Color = [number, number, number, number];
+interface Point { x: number; y: number; }
+interface Rect { x: number; y: number; width: number; height: number; }
+
+type Annotation =
+  | { kind: "arrow"; from: Point; to: Point; color: Color; width: number }
+  | { kind: "rectangle"; rect: Rect; color: Color; width: number; fill?: Color | null }
+  | { kind: "callout"; center: Point; number: number; color: Color; radius: number }
+  | { kind: "text"; position: Point; text: string; color: Color; size: number; background?: Color | null }
+  | { kind: "highlight"; rect: Rect; color: Color };
+
+interface AnnotationDocument {
+  version: number;
+  source: string;
+  annotations: Annotation[];
+}
+
+type Tool = Annotation["kind"];
+
+const RED: Color = [230, 30, 30, 255];
+const YELLOW: Color = [255, 230, 0, 110];
+
+const rectBetween = (a: Point, b: Point): Rect => ({
+  x: Math.min(a.x, b.x),
+  y: Math.min(a.y, b.y),
+  width: Math.abs(a.x - b.x),
+  height: Math.abs(a.y - b.y),
+});
+
+function Annotator({ path }: { path: string }) {
+  const [doc, setDoc] = useState<AnnotationDocument | null>(null);
+  const [preview, setPreview] = useState<string | null>(null);
+  const [tool, setTool] = useState<Tool>("arrow");
+  const dragStart = useRef<Point | null>(null);
+  const imgRef = useRef<HTMLImageElement>(null);
+
+  useEffect(() => {
+    invoke<AnnotationDocument>("load_annotation_document").then(setDoc);
+  }, [path]);
+
+  // Re-render in Rust whenever the document changes so the preview matches the output exactly
+  useEffect(() => {
+    if (doc) invoke<string>("render_annotation_preview", { annotations: doc.annotations }).then(setPreview);
+  }, [doc]);
+
+  // Convert a mouse position to image pixel coordinates
+  const toImage = (e: React.MouseEvent): Point => {
+    const img = imgRef.current!;
+    const bounds = img.getBoundingClientRect();
+    return {
+      x: ((e.clientX - bounds.left) * img.naturalWidth) / bounds.width,
+      y: ((e.clientY - bounds.top) * img.naturalHeight) / bounds.height,
+    };
+  };
+
+  const add = (annotation: Annotation) =>
+    setDoc((d) => (d ? { ...d, annotations: [...d.annotations, annotation] } : d));
+
+  const onMouseUp = (e: React.MouseEvent) => {
+    const start = dragStart.current;
+    dragStart.current = null;
+    if (!start || !doc) return;
+    const end = toImage(e);
+
+    switch (tool) {
+      case "arrow":
+        add({ kind: "arrow", from: start, to: end, color: RED, width: 4 });
+        break;
+      case "rectangle":
+        add({ kind: "rectangle", rect: rectBetween(start, end), color: RED, width: 3 });
+        break;
+      case "highlight":
+        add({ kind: "highlight", rect: rectBetween(start, end), color: YELLOW });
+        break;
+      case "callout": {
+        const number = doc.annotations.filter((a) => a.kind === "callout").length + 1;
+        add({ kind: "callout", center: end, number, color: RED, radius: 14 });
+        break;
+      }
+      case "text": {
+        const text = window.prompt("Label text");
+        if (text) add({ kind: "text", position: end, text, color: [255, 255, 255, 255], size: 18, background: [0, 0, 0, 180] });
+        break;
+      }
+    }
+  };
+
+  const undo = () => setDoc((d) => (d ? { ...d, annotations: d.annotations.slice(0, -1) } : d));
+
+  const save = async () => {
+    if (!doc) return;
+    await invoke("flatten_annotations", { annotations: doc.annotations });
+  };
+
+  const tools: Tool[] = ["arrow", "rectangle", "callout", "text", "highlight"];
+
+  return (
+    <div style={{ fontFamily: "system-ui, sans-serif", background: "#222", minHeight: "100vh", color: "white" }}>
+      <div style={{ display: "flex", gap: 6, padding: 8 }}>
+        {tools.map((t) => (
+          <button key={t} onClick={() => setTool(t)} style={{ fontWeight: tool === t ? "bold" : "normal" }}>
+            {t}
+          </button>
+        ))}
+        <span style={{ flex: 1 }} />
+        <button onClick={undo}>Undo</button>
+        <button onClick={save}>Save</button>
+      </div>
+      {preview && (
+        <img
+          ref={imgRef}
+          src={preview}
+          draggable={false}
+          onMouseDown={(e) => (dragStart.current = toImage(e))}
+          onMouseUp={onMouseUp}
+          style={{ maxWidth: "100%", cursor: "crosshair", display: "block", margin: "0 auto" }}
+        />
+      )}
+    </div>
+  );
+}
+
+export default Annotator;
@@ -1,7 +1,7 @@
 import { useEffect, useState } from "react";
 import { listen } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/core";
-import { save } from "@tauri-apps/plugin-dialog";
+import { confirm, message, save } from "@tauri-apps/plugin-dialog";
 import "./App.css";
 
 interface UIElementInfo {
@@ -13,10 +13,96 @@ interface UIElementInfo {
   globalX: number;
   globalY: number;
   windowId: number;
+  title?: string | null;
+  pid?: number;
+  appName?: string | null;
+  bundleId?: string | null;
+  executablePath?: string | null;
+  windowTitle?: string | null;
+  windowLayer?: number | null;
 }
 
+type CaptureMode = "element" | "scrollArea" | "delayed" | "record" | "pick";
+
+interface Settings {
+  save_directory: string | null;
+  image_format: string;
+  overlay: { border: string; fill: string; label: string };
+}
+
+// Serialized `CaptureError` returned by the capture commands
+interface CaptureError {
+  kind:
+    | "permissionDenied"
+    | "elementVanished"
+    | "invalidRect"
+    | "writeFailed"
+    | "encoderFailed"
+    | "clipboardFailed"
+    | "redactionFailed"
+    | "other";
+  message: string;
+  permission: "accessibility" | "screenRecording" | null;
+}
+
+interface CaptureClick {
+  element: UIElementInfo;
+  mode: CaptureMode;
+  pending: boolean;
+}
+
+// Suggests `name` inside the configured save directory, if there is one
+const defaultPath = (settings: Settings | null, name: string) =>
+  settings?.save_directory ? `${settings.save_directory}/${name}` : name;
+
+// Names captures after the app they came from, e.g. `safari-1700000000000`
+const fileStem = (element: { appName?: string | null }, fallback: string) => {
+  const app = (element.appName ?? "").toLowerCase().replace(/[^a-z0-9]+/g, "-").replace(/^-|-$/g, "");
+  return `${app || fallback}-${Date.now()}`;
+};
+
+// Tells the user why a capture failed; the overlay is already hidden, so a dialog is used
+const reportCaptureError = async (error: unknown) => {
+  const captureError = error as CaptureError;
+  const text = captureError?.message ?? String(error);
+  console.error("Capture failed:", error);
+
+  if (captureError?.kind === "permissionDenied" && captureError.permission) {
+    const open = await confirm(text, {
+      title: "Capture Failed",
+      kind: "warning",
+      okLabel: "Open System Settings",
+      cancelLabel: "Later",
+    });
+    if (open) await invoke("open_permission_settings", { permission: captureError.permission });
+    return;
+  }
+  const title = captureError?.kind === "clipboardFailed" ? "Capture Saved" : "Capture Failed";
+  await message(text, { title, kind: "error" });
+};
+
 function App() {
+  const [settings, setSettings] = useState<Settings | null>(null);
   const [highlight, setHighlight] = useState<UIElementInfo | null>(null);
+  const [frozenFrame, setFrozenFrame] = useState<string | null>(null);
+  // Accessibility actions of the highlighted element, listed on demand with the A key
+  const [actions, setActions] = useState<string[] | null>(null);
+  const [actionError, setActionError] = useState<string | null>(null);
+
+  // A new element under the cursor gets its own actions
+  useEffect(() => {
+    setActions(null);
+    setActionError(null);
+  }, [highlight?.globalX, highlight?.globalY, highlight?.width, highlight?.height, highlight?.role]);
+
+  // Load the settings and follow changes made from the tray or another window
+  useEffect(() => {
+    invoke<Settings>("get_settings").then(setSettings);
+    const unlistenPromise = listen<Settings>("settings-changed", (event) => {
+      setSettings(event.payload);
+    });
+    return () => { unlistenPromise.then((u) => u()); };
+  }, []);
 
   // Listen for element-hover events from the Rust backend
   useEffect(() => {
@@ -26,38 +112,73 @@ function App() {
     return () => { unlistenPromise.then((u) => u()); };
   }, []);
 
+  // Listen for the frozen screen image to inspect against (null when inspecting live)
+  useEffect(() => {
+    const unlistenPromise = listen<string | null>("frozen-frame", (event) => {
+      setFrozenFrame(event.payload);
+    });
+    return () => { unlistenPromise.then((u) => u()); };
+  }, []);
+
   // Listen for capture-click: show save dialog, then invoke capture command
   useEffect(() => {
-    const unlistenPromise = listen<UIElementInfo>("capture-click", async (event) => {
-      const info = event.payload;
+    const unlistenPromise = listen<CaptureClick>("capture-click", async (event) => {
+      const { element, mode, pending } = event.payload;
+      setFrozenFrame(null);
 
+      const format = settings?.image_format ?? "png";
       const path = await save({
-        defaultPath: `capture-${Date.now()}.png`,
-        filters: [{ name: "PNG Image", extensions: ["png"] }],
+        defaultPath: defaultPath(settings, `${fileStem(element, "capture")}.${format}`),
+        filters: [{ name: `${format.toUpperCase()} Image`, extensions: [format] }],
       });
 
       // Hide the overlay after the dialog closes (dialog may have brought the window back into view)
       await invoke("hide_window");
 
-      if (!path) return; // User cancelled the dialog
+      try {
+        // Delayed and frozen captures were already grabbed before the dialog opened
+        if (pending) {
+          if (path) await invoke("save_pending_capture", { path });
+          else await invoke("discard_pending_capture");
+          return;
+        }
 
-      await invoke("capture_rect_to_file", {
-        x: info.globalX,
-        y: info.globalY,
-        width: info.width,
-        height: info.height,
-        windowId: info.windowId,
-        role: info.role,
-        path,
+        if (!path) return; // User cancelled the dialog
+
+        const command = mode === "scrollArea" ? "capture_scroll_area_to_file" : "capture_rect_to_file";
+        await invoke(command, { element, path });
+      } catch (error) {
+        await reportCaptureError(error);
+      }
+    });
+    return () => { unlistenPromise.then((u) => u()); };
+  }, [settings]);
+
+  // Listen for recording-finished: ask where to save the recording, the extension picks the format
+  useEffect(() => {
+    const unlistenPromise = listen<{ appName: string | null }>("recording-finished", async (event) => {
+      const path = await save({
+        defaultPath: defaultPath(settings, `${fileStem(event.payload, "recording")}.gif`),
+        filters: [
+          { name: "Animated GIF", extensions: ["gif"] },
+          { name: "Animated PNG", extensions: ["png"] },
+          { name: "MP4 Video", extensions: ["mp4"] },
+        ],
       });
+
+      await invoke("hide_window");
+
+      if (path) await invoke("save_recording", { path });
+      else await invoke("discard_recording");
     });
     return () => { unlistenPromise.then((u) => u()); };
-  }, []);
+  }, [settings]);
 
   // Hide the overlay window when ESC is pressed
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
       if (e.key === "Escape") {
+        setFrozenFrame(null);
         invoke("hide_window");
       }
     };
@@ -65,6 +186,31 @@ function App() {
     return () => window.removeEventListener("keydown", handleKeyDown);
   }, []);
 
+  // A lists the highlighted element's actions, 1-9 performs one, F focuses the element
+  useEffect(() => {
+    const handleKeyDown = async (e: KeyboardEvent) => {
+      if (!highlight || e.metaKey || e.ctrlKey || e.altKey) return;
+      const key = e.key.toLowerCase();
+      const index = Number(e.key) - 1;
+      try {
+        if (key === "a") {
+          setActions(await invoke<string[]>("list_element_actions", { element: highlight }));
+        } else if (key === "f") {
+          await invoke("set_element_attribute", { element: highlight, attribute: "AXFocused", value: true });
+        } else if (actions && index >= 0 && index < Math.min(actions.length, 9)) {
+          await invoke("perform_element_action", { element: highlight, action: actions[index] });
+        } else {
+          return;
+        }
+        setActionError(null);
+      } catch (error) {
+        setActionError(String(error));
+      }
+    };
+    window.addEventListener("keydown", handleKeyDown);
+    return () => window.removeEventListener("keydown", handleKeyDown);
+  }, [highlight, actions]);
+
   // Helper to remove "AX" prefix from accessibility roles
   const formatRole = (role: string) => role.replace(/^AX/, "");
 
@@ -77,6 +223,13 @@ function App() {
         position: "relative",
       }}
     >
+      {frozenFrame && (
+        <img
+          src={frozenFrame}
+          draggable={false}
+          style={{ position: "absolute", inset: 0, width: "100%", height: "100%", pointerEvents: "none" }}
+        />
+      )}
       {highlight && (
         <div
           style={{
@@ -86,8 +239,8 @@ function App() {
             width: `${highlight.width}px`,
             height: `${highlight.height}px`,
             // Use inset box-shadow instead of border to prevent clipping on screen edges
-            boxShadow: "inset 0 0 0 2px red",
-            backgroundColor: "rgba(255, 0, 0, 0.1)",
+            boxShadow: `inset 0 0 0 2px ${settings?.overlay.border ?? "red"}`,
+            backgroundColor: settings?.overlay.fill ?? "rgba(255, 0, 0, 0.1)",
             pointerEvents: "none",
             boxSizing: "border-box",
             transition: "all 0.05s ease-out",
@@ -99,7 +252,7 @@ function App() {
               position: "absolute",
               top: highlight.y < 30 ? "100%" : "-26px",
               left: "0",
-              backgroundColor: "#cc0000",
+              backgroundColor: settings?.overlay.label ?? "#cc0000",
               color: "white",
               padding: "2px 6px",
               fontSize: "11px",
@@ -117,6 +270,16 @@ function App() {
             <span style={{ fontFamily: "monospace" }}>
               {Math.round(highlight.width)} × {Math.round(highlight.height)}
             </span>
+            {actions && (
+              <div style={{ fontWeight: "normal", marginTop: "2px" }}>
+                {actions.length === 0
+                  ? "No actions"
+                  : actions.slice(0, 9).map((action, i) => (
+                      <div key={action}>{i + 1}. {formatRole(action)}</div>
+                    ))}
+              </div>
+            )}
+            {actionError && <div style={{ fontWeight: "normal", marginTop: "2px" }}>{actionError}</div>}
           </div>
         </div>
       )}
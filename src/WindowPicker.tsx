@@ -0,0 +1,113 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { save } from "@tauri-apps/plugin-dialog";
+
+interface WindowInfo {
+  number: number;
+  ownerPid: number;
+  ownerName: string;
+  title: string;
+  layer: number;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+interface AppWindows {
+  app: { pid: number; name: string; bundleId?: string | null; executablePath?: string | null };
+  windows: WindowInfo[];
+}
+
+// Turns an app name into a file name, e.g. `Google Chrome` into `google-chrome`
+const slug = (name: string, fallback: string) =>
+  name.toLowerCase().replace(/[^a-z0-9]+/g, "-").replace(/^-|-$/g, "") || fallback;
+
+// Lists every on-screen window grouped by app, to capture, dump or inspect one without hovering
+function WindowPicker() {
+  const [query, setQuery] = useState("");
+  const [groups, setGroups] = useState<AppWindows[]>([]);
+  const [status, setStatus] = useState<string | null>(null);
+
+  const refresh = () => invoke<AppWindows[]>("list_app_windows", { query }).then(setGroups);
+
+  useEffect(() => {
+    refresh();
+  }, [query]);
+
+  const run = async (task: () => Promise<string | void>) => {
+    try {
+      const done = await task();
+      if (done) setStatus(done);
+    } catch (error) {
+      setStatus(String((error as { message?: string })?.message ?? error));
+    }
+  };
+
+  const capture = (app: string, window: WindowInfo) =>
+    run(async () => {
+      const element = await invoke("get_window_element", { windowId: window.number });
+      const path = await save({
+        defaultPath: `${slug(app, "window")}-${Date.now()}.png`,
+        filters: [{ name: "Image", extensions: ["png", "jpg", "webp"] }],
+      });
+      if (!path) return;
+      await invoke("capture_rect_to_file", { element, path });
+      return `Saved ${path}`;
+    });
+
+  const dumpTree = (app: string, window: WindowInfo) =>
+    run(async () => {
+      const path = await save({
+        defaultPath: `${slug(app, "window")}-tree.json`,
+        filters: [{ name: "JSON", extensions: ["json"] }],
+      });
+      if (!path) return;
+      await invoke("export_window_tree", { windowId: window.number, path });
+      return `Saved ${path}`;
+    });
+
+  const inspect = (window: WindowInfo) => run(() => invoke("inspect_window", { windowId: window.number }));
+
+  return (
+    <div style={{ fontFamily: "system-ui, sans-serif", background: "#222", height: "100vh", color: "white", display: "flex", flexDirection: "column" }}>
+      <div style={{ display: "flex", gap: 6, padding: 8 }}>
+        <input
+          autoFocus
+          placeholder="Search windows and apps"
+          value={query}
+          onChange={(e) => setQuery(e.target.value)}
+          style={{ flex: 1 }}
+        />
+        <button onClick={refresh}>Refresh</button>
+      </div>
+      {status && <div style={{ padding: "0 8px 8px", opacity: 0.7, fontSize: 12 }}>{status}</div>}
+      <div style={{ flex: 1, overflowY: "auto", padding: "0 8px 8px" }}>
+        {groups.length === 0 && <div style={{ opacity: 0.6 }}>No matching windows</div>}
+        {groups.map(({ app, windows }) => (
+          <div key={app.pid} style={{ marginBottom: 12 }}>
+            <div style={{ fontWeight: "bold" }}>
+              {app.name}
+              {app.bundleId && <span style={{ fontWeight: "normal", opacity: 0.5, marginLeft: 6 }}>{app.bundleId}</span>}
+            </div>
+            {windows.map((window) => (
+              <div key={window.number} style={{ display: "flex", alignItems: "center", gap: 6, padding: "4px 0 4px 12px" }}>
+                <span style={{ flex: 1, overflow: "hidden", textOverflow: "ellipsis", whiteSpace: "nowrap" }}>
+                  {window.title || <i style={{ opacity: 0.6 }}>Untitled</i>}
+                  <span style={{ fontFamily: "monospace", opacity: 0.5, marginLeft: 6 }}>
+                    {Math.round(window.width)} × {Math.round(window.height)}
+                  </span>
+                </span>
+                <button onClick={() => capture(app.name, window)}>Capture</button>
+                <button onClick={() => dumpTree(app.name, window)}>Dump Tree</button>
+                <button onClick={() => inspect(window)}>Inspect</button>
+              </div>
+            ))}
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
+
+export default WindowPicker;
@@ -1,9 +1,15 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import Annotator from "./Annotator";
+import WindowPicker from "./WindowPicker";
+
+// The preview window loads the same bundle with `?annotate=<path>`, the window picker with `?picker`
+const params = new URLSearchParams(window.location.search);
+const annotatePath = params.get("annotate");
 
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   <React.StrictMode>
-    <App />
+    {annotatePath ? <Annotator path={annotatePath} /> : params.has("picker") ? <WindowPicker /> : <App />}
   </React.StrictMode>,
 );